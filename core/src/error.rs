@@ -83,6 +83,8 @@ pub enum ExitError {
     CreateCollision,
     /// Create init code exceeds limit (runtime).
     CreateContractLimit,
+    /// Created code starts with the reserved 0xEF byte (runtime, EIP-3541).
+    InvalidCode,
 
     /// An opcode accesses external information, but the request is off offset
     /// limit (runtime).
@@ -116,6 +118,8 @@ pub enum ExitFatal {
     UnhandledInterrupt,
     /// The environment explictly set call errors as fatal error.
     CallErrorAsFatal(ExitError),
+    /// Reading state from the backend's provider failed, in the named RPC call.
+    ProviderError(&'static str),
 
     /// Other fatal errors.
     Other(&'static str),
@@ -1,6 +1,6 @@
 use super::Control;
 use crate::{ExitError, ExitFatal, ExitRevert, ExitSucceed, Machine};
-use core::cmp::min;
+use core::cmp::{max, min};
 use primitive_types::{H256, U256};
 
 pub fn codesize(state: &mut Machine) -> Control {
@@ -99,6 +99,23 @@ pub fn mstore8(state: &mut Machine) -> Control {
     }
 }
 
+pub fn mcopy(state: &mut Machine) -> Control {
+    pop_u256!(state, dst, src, len);
+    if len == U256::zero() {
+        return Control::Continue(1);
+    }
+
+    try_or_fail!(state.memory.resize_offset(max(dst, src), len));
+    let src = as_usize_or_fail!(src);
+    let len = as_usize_or_fail!(len);
+    let data = state.memory.get(src, len);
+    let dst = as_usize_or_fail!(dst);
+    match state.memory.set(dst, &data, Some(len)) {
+        Ok(()) => Control::Continue(1),
+        Err(e) => Control::Exit(e.into()),
+    }
+}
+
 pub fn jump(state: &mut Machine) -> Control {
     pop_u256!(state, dest);
     let dest = as_usize_or_fail!(dest, ExitError::InvalidJump);
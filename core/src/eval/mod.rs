@@ -56,6 +56,7 @@ pub fn eval(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
         Opcode::PC => self::misc::pc(state, position),
         Opcode::MSize => self::misc::msize(state),
         Opcode::JumpDest => Control::Continue(1),
+        Opcode::MCopy => self::misc::mcopy(state),
         Opcode::Push(n) => self::misc::push(state, n as usize, position),
         Opcode::Dup(n) => self::misc::dup(state, n as usize),
         Opcode::Swap(n) => self::misc::swap(state, n as usize),
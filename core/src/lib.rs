@@ -83,6 +83,11 @@ impl Machine {
         }
     }
 
+    /// Current program counter, or the exit reason if the machine has stopped.
+    pub fn position(&self) -> &Result<usize, ExitReason> {
+        &self.position
+    }
+
     /// Explict exit of the machine. Further step will return error.
     pub fn exit(&mut self, reason: ExitReason) {
         self.position = Err(reason);
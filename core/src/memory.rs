@@ -27,6 +27,11 @@ impl Memory {
         self.limit
     }
 
+    /// Get the raw memory data.
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
     /// Get the length of the current memory range.
     pub fn len(&self) -> usize {
         self.data.len()
@@ -85,6 +85,8 @@ pub enum Opcode {
     MSize,
     /// `JUMPDEST`
     JumpDest,
+    /// `MCOPY`
+    MCopy,
 
     /// `PUSHn`
     Push(u8),
@@ -162,6 +164,7 @@ impl Opcode {
             0x45 => Err(ExternalOpcode::GasLimit),
             0x46 => Err(ExternalOpcode::ChainId),
             0x47 => Err(ExternalOpcode::SelfBalance),
+            0x48 => Err(ExternalOpcode::BaseFee),
 
             0x50 => Ok(Opcode::Pop),
             0x51 => Ok(Opcode::MLoad),
@@ -175,7 +178,11 @@ impl Opcode {
             0x59 => Ok(Opcode::MSize),
             0x5a => Err(ExternalOpcode::Gas),
             0x5b => Ok(Opcode::JumpDest),
+            0x5c => Err(ExternalOpcode::TLoad),
+            0x5d => Err(ExternalOpcode::TStore),
+            0x5e => Ok(Opcode::MCopy),
 
+            0x5f => Ok(Opcode::Push(0)),
             0x60 => Ok(Opcode::Push(1)),
             0x61 => Ok(Opcode::Push(2)),
             0x62 => Ok(Opcode::Push(3)),
@@ -305,10 +312,16 @@ pub enum ExternalOpcode {
     Difficulty,
     /// `GASLIMIT`
     GasLimit,
+    /// `BASEFEE`
+    BaseFee,
     /// `SLOAD`
     SLoad,
     /// `SSTORE`
     SStore,
+    /// `TLOAD`
+    TLoad,
+    /// `TSTORE`
+    TStore,
     /// `GAS`
     Gas,
     /// `LOGn`
@@ -23,6 +23,11 @@ impl Stack {
         self.limit
     }
 
+    /// Stack data, with the top of the stack last.
+    pub fn data(&self) -> &Vec<H256> {
+        &self.data
+    }
+
     /// Pop a value from the stack. If the stack is already empty, returns the
     /// `StackUnderflow` error.
     pub fn pop(&mut self) -> Result<H256, ExitError> {
@@ -11,8 +11,8 @@ pub fn call_extra_check(gas: U256, after_gas: usize, config: &Config) -> Result<
     }
 }
 
-pub fn suicide_refund(already_removed: bool) -> isize {
-    if already_removed {
+pub fn suicide_refund(already_removed: bool, config: &Config) -> isize {
+    if already_removed || !config.has_suicide_refund {
         0
     } else {
         R_SUICIDE
@@ -52,7 +52,36 @@ pub fn sstore_refund(original: H256, current: H256, new: H256, config: &Config)
     }
 }
 
-pub fn create2_cost(len: U256) -> Result<usize, ExitError> {
+pub fn initcode_cost(len: U256, config: &Config) -> Result<usize, ExitError> {
+    if let Some(limit) = config.max_initcode_size {
+        if len > U256::from(limit) {
+            return Err(ExitError::CreateContractLimit);
+        }
+    }
+
+    // ceil(len / 32.0)
+    let words = len / U256::from(32)
+        + if len % U256::from(32) == U256::zero() {
+            U256::zero()
+        } else {
+            U256::one()
+        };
+    let gas = U256::from(config.gas_initcode_word)
+        .checked_mul(words)
+        .ok_or(ExitError::OutOfGas)?;
+
+    if gas > U256::from(usize::max_value()) {
+        return Err(ExitError::OutOfGas);
+    }
+
+    Ok(gas.as_usize())
+}
+
+pub fn create_cost(len: U256, config: &Config) -> Result<usize, ExitError> {
+    Ok(G_CREATE + initcode_cost(len, config)?)
+}
+
+pub fn create2_cost(len: U256, config: &Config) -> Result<usize, ExitError> {
     let base = U256::from(G_CREATE);
     // ceil(len / 32.0)
     let sha_addup_base = len / U256::from(32)
@@ -64,7 +93,11 @@ pub fn create2_cost(len: U256) -> Result<usize, ExitError> {
     let sha_addup = U256::from(G_SHA3WORD)
         .checked_mul(sha_addup_base)
         .ok_or(ExitError::OutOfGas)?;
-    let gas = base.checked_add(sha_addup).ok_or(ExitError::OutOfGas)?;
+    let gas = base
+        .checked_add(sha_addup)
+        .ok_or(ExitError::OutOfGas)?
+        .checked_add(U256::from(initcode_cost(len, config)?))
+        .ok_or(ExitError::OutOfGas)?;
 
     if gas > U256::from(usize::max_value()) {
         return Err(ExitError::OutOfGas);
@@ -116,11 +149,11 @@ pub fn verylowcopy_cost(len: U256) -> Result<usize, ExitError> {
     Ok(gas.as_usize())
 }
 
-pub fn extcodecopy_cost(len: U256, config: &Config) -> Result<usize, ExitError> {
+pub fn extcodecopy_cost(len: U256, is_cold: bool, config: &Config) -> Result<usize, ExitError> {
     let wordd = len / U256::from(32);
     let wordr = len % U256::from(32);
 
-    let gas = U256::from(config.gas_ext_code)
+    let gas = U256::from(address_access_cost(is_cold, config.gas_ext_code, config))
         .checked_add(
             U256::from(G_COPY)
                 .checked_mul(if wordr == U256::zero() {
@@ -180,19 +213,44 @@ pub fn sha3_cost(len: U256) -> Result<usize, ExitError> {
     Ok(gas.as_usize())
 }
 
+pub fn sload_cost(is_cold: bool, config: &Config) -> usize {
+    if config.increase_state_access_gas {
+        if is_cold {
+            config.gas_sload_cold
+        } else {
+            config.gas_storage_read_warm
+        }
+    } else {
+        config.gas_sload
+    }
+}
+
+pub fn address_access_cost(is_cold: bool, regular_value: usize, config: &Config) -> usize {
+    if config.increase_state_access_gas {
+        if is_cold {
+            config.gas_account_access_cold
+        } else {
+            config.gas_storage_read_warm
+        }
+    } else {
+        regular_value
+    }
+}
+
 pub fn sstore_cost(
     original: H256,
     current: H256,
     new: H256,
     gas: usize,
+    is_cold: bool,
     config: &Config,
 ) -> Result<usize, ExitError> {
-    if config.sstore_gas_metering {
+    let gas_cost = if config.sstore_gas_metering {
         if config.sstore_revert_under_stipend && gas < config.call_stipend {
             return Err(ExitError::OutOfGas);
         }
 
-        Ok(if new == current {
+        if new == current {
             config.gas_sload
         } else if original == current {
             if original == H256::zero() {
@@ -202,17 +260,21 @@ pub fn sstore_cost(
             }
         } else {
             config.gas_sload
-        })
+        }
+    } else if current == H256::zero() && new != H256::zero() {
+        config.gas_sstore_set
     } else {
-        Ok(if current == H256::zero() && new != H256::zero() {
-            config.gas_sstore_set
-        } else {
-            config.gas_sstore_reset
-        })
-    }
+        config.gas_sstore_reset
+    };
+
+    Ok(if config.increase_state_access_gas && is_cold {
+        gas_cost + config.gas_sload_cold
+    } else {
+        gas_cost
+    })
 }
 
-pub fn suicide_cost(value: U256, target_exists: bool, config: &Config) -> usize {
+pub fn suicide_cost(value: U256, is_cold: bool, target_exists: bool, config: &Config) -> usize {
     let eip161 = !config.empty_considered_exists;
     let should_charge_topup = if eip161 {
         value != U256::zero() && !target_exists
@@ -226,18 +288,25 @@ pub fn suicide_cost(value: U256, target_exists: bool, config: &Config) -> usize
         0
     };
 
-    config.gas_suicide + suicide_gas_topup
+    let suicide_gas_cold = if config.increase_state_access_gas && is_cold {
+        config.gas_account_access_cold
+    } else {
+        0
+    };
+
+    config.gas_suicide + suicide_gas_topup + suicide_gas_cold
 }
 
 pub fn call_cost(
     value: U256,
     is_call_or_callcode: bool,
     is_call_or_staticcall: bool,
+    is_cold: bool,
     new_account: bool,
     config: &Config,
 ) -> usize {
     let transfers_value = value != U256::default();
-    config.gas_call
+    address_access_cost(is_cold, config.gas_call, config)
         + xfer_cost(is_call_or_callcode, transfers_value)
         + new_cost(is_call_or_staticcall, new_account, transfers_value, config)
 }
@@ -4,11 +4,14 @@
 #![forbid(unsafe_code, missing_docs, unused_variables, unused_imports)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
 mod consts;
 mod costs;
 mod memory;
 mod utils;
 
+use alloc::vec::Vec;
 use core::cmp::max;
 use evm_core::{ExitError, ExternalOpcode, Opcode, Stack};
 use evm_runtime::{Config, Handler};
@@ -163,18 +166,35 @@ impl<'config> Gasometer<'config> {
             TransactionCost::Call {
                 zero_data_len,
                 non_zero_data_len,
+                access_list_address_len,
+                access_list_storage_len,
             } => {
                 self.config.gas_transaction_call
                     + zero_data_len * self.config.gas_transaction_zero_data
                     + non_zero_data_len * self.config.gas_transaction_non_zero_data
+                    + access_list_address_len * self.config.gas_access_list_address
+                    + access_list_storage_len * self.config.gas_access_list_storage_key
             }
             TransactionCost::Create {
                 zero_data_len,
                 non_zero_data_len,
+                access_list_address_len,
+                access_list_storage_len,
             } => {
+                let initcode_cost = try_or_fail!(
+                    self.inner,
+                    costs::initcode_cost(
+                        U256::from(zero_data_len + non_zero_data_len),
+                        self.config
+                    )
+                );
+
                 self.config.gas_transaction_create
                     + zero_data_len * self.config.gas_transaction_zero_data
                     + non_zero_data_len * self.config.gas_transaction_non_zero_data
+                    + access_list_address_len * self.config.gas_access_list_address
+                    + access_list_storage_len * self.config.gas_access_list_storage_key
+                    + initcode_cost
             }
         };
 
@@ -325,18 +345,35 @@ impl GasometerOwned {
             TransactionCost::Call {
                 zero_data_len,
                 non_zero_data_len,
+                access_list_address_len,
+                access_list_storage_len,
             } => {
                 self.config.gas_transaction_call
                     + zero_data_len * self.config.gas_transaction_zero_data
                     + non_zero_data_len * self.config.gas_transaction_non_zero_data
+                    + access_list_address_len * self.config.gas_access_list_address
+                    + access_list_storage_len * self.config.gas_access_list_storage_key
             }
             TransactionCost::Create {
                 zero_data_len,
                 non_zero_data_len,
+                access_list_address_len,
+                access_list_storage_len,
             } => {
+                let initcode_cost = try_or_fail!(
+                    self.inner,
+                    costs::initcode_cost(
+                        U256::from(zero_data_len + non_zero_data_len),
+                        &self.config
+                    )
+                );
+
                 self.config.gas_transaction_create
                     + zero_data_len * self.config.gas_transaction_zero_data
                     + non_zero_data_len * self.config.gas_transaction_non_zero_data
+                    + access_list_address_len * self.config.gas_access_list_address
+                    + access_list_storage_len * self.config.gas_access_list_storage_key
+                    + initcode_cost
             }
         };
 
@@ -352,28 +389,42 @@ impl GasometerOwned {
 
 /// Calculate the call transaction cost.
 #[allow(clippy::naive_bytecount)]
-pub fn call_transaction_cost(data: &[u8]) -> TransactionCost {
+pub fn call_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) -> TransactionCost {
     let zero_data_len = data.iter().filter(|v| **v == 0).count();
     let non_zero_data_len = data.len() - zero_data_len;
+    let (access_list_address_len, access_list_storage_len) = count_access_list(access_list);
 
     TransactionCost::Call {
         zero_data_len,
         non_zero_data_len,
+        access_list_address_len,
+        access_list_storage_len,
     }
 }
 
 /// Calculate the create transaction cost.
 #[allow(clippy::naive_bytecount)]
-pub fn create_transaction_cost(data: &[u8]) -> TransactionCost {
+pub fn create_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) -> TransactionCost {
     let zero_data_len = data.iter().filter(|v| **v == 0).count();
     let non_zero_data_len = data.len() - zero_data_len;
+    let (access_list_address_len, access_list_storage_len) = count_access_list(access_list);
 
     TransactionCost::Create {
         zero_data_len,
         non_zero_data_len,
+        access_list_address_len,
+        access_list_storage_len,
     }
 }
 
+/// Count the addresses and storage keys of an access list.
+fn count_access_list(access_list: &[(H160, Vec<H256>)]) -> (usize, usize) {
+    let access_list_address_len = access_list.len();
+    let access_list_storage_len = access_list.iter().map(|(_, keys)| keys.len()).sum();
+
+    (access_list_address_len, access_list_storage_len)
+}
+
 /// Calculate the opcode cost.
 pub fn opcode_cost<H: Handler>(
     address: H160,
@@ -382,7 +433,8 @@ pub fn opcode_cost<H: Handler>(
     is_static: bool,
     config: &Config,
     handler: &mut H,
-) -> Result<(GasCost, Option<MemoryCost>), ExitError> {
+) -> Result<(GasCost, StorageTarget, Option<MemoryCost>), ExitError> {
+    let mut storage_target = StorageTarget::None;
     let gas_cost = match opcode {
         Ok(Opcode::Stop) | Ok(Opcode::Return) => GasCost::Zero,
 
@@ -409,6 +461,12 @@ pub fn opcode_cost<H: Handler>(
         Err(ExternalOpcode::ChainId) if config.has_chain_id => GasCost::Base,
         Err(ExternalOpcode::ChainId) => GasCost::Invalid,
 
+        Err(ExternalOpcode::BaseFee) if config.has_base_fee => GasCost::Base,
+        Err(ExternalOpcode::BaseFee) => GasCost::Invalid,
+
+        Ok(Opcode::Push(0)) if config.has_push0 => GasCost::Base,
+        Ok(Opcode::Push(0)) => GasCost::Invalid,
+
         Ok(Opcode::Add)
         | Ok(Opcode::Sub)
         | Ok(Opcode::Not)
@@ -449,43 +507,94 @@ pub fn opcode_cost<H: Handler>(
 
         Ok(Opcode::JumpI) => GasCost::High,
 
-        Err(ExternalOpcode::ExtCodeSize) => GasCost::ExtCodeSize,
-        Err(ExternalOpcode::Balance) => GasCost::Balance,
+        Err(ExternalOpcode::ExtCodeSize) => {
+            let target = stack.peek(0)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::ExtCodeSize {
+                target_is_cold: handler.is_cold(target, None),
+            }
+        }
+        Err(ExternalOpcode::Balance) => {
+            let target = stack.peek(0)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::Balance {
+                target_is_cold: handler.is_cold(target, None),
+            }
+        }
         Err(ExternalOpcode::BlockHash) => GasCost::BlockHash,
 
-        Err(ExternalOpcode::ExtCodeHash) if config.has_ext_code_hash => GasCost::ExtCodeHash,
+        Err(ExternalOpcode::ExtCodeHash) if config.has_ext_code_hash => {
+            let target = stack.peek(0)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::ExtCodeHash {
+                target_is_cold: handler.is_cold(target, None),
+            }
+        }
         Err(ExternalOpcode::ExtCodeHash) => GasCost::Invalid,
 
-        Err(ExternalOpcode::CallCode) => GasCost::CallCode {
-            value: U256::from_big_endian(&stack.peek(2)?[..]),
-            gas: U256::from_big_endian(&stack.peek(0)?[..]),
-            target_exists: handler.exists(stack.peek(1)?.into()),
-        },
-        Err(ExternalOpcode::StaticCall) => GasCost::StaticCall {
-            gas: U256::from_big_endian(&stack.peek(0)?[..]),
-            target_exists: handler.exists(stack.peek(1)?.into()),
-        },
+        Err(ExternalOpcode::CallCode) => {
+            let target = stack.peek(1)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::CallCode {
+                value: U256::from_big_endian(&stack.peek(2)?[..]),
+                gas: U256::from_big_endian(&stack.peek(0)?[..]),
+                target_is_cold: handler.is_cold(target, None),
+                target_exists: handler.exists(target),
+            }
+        }
+        Err(ExternalOpcode::StaticCall) => {
+            let target = stack.peek(1)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::StaticCall {
+                gas: U256::from_big_endian(&stack.peek(0)?[..]),
+                target_is_cold: handler.is_cold(target, None),
+                target_exists: handler.exists(target),
+            }
+        }
         Err(ExternalOpcode::Sha3) => GasCost::Sha3 {
             len: U256::from_big_endian(&stack.peek(1)?[..]),
         },
-        Err(ExternalOpcode::ExtCodeCopy) => GasCost::ExtCodeCopy {
-            len: U256::from_big_endian(&stack.peek(3)?[..]),
-        },
+        Err(ExternalOpcode::ExtCodeCopy) => {
+            let target = stack.peek(0)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::ExtCodeCopy {
+                target_is_cold: handler.is_cold(target, None),
+                len: U256::from_big_endian(&stack.peek(3)?[..]),
+            }
+        }
         Ok(Opcode::CallDataCopy) | Ok(Opcode::CodeCopy) => GasCost::VeryLowCopy {
             len: U256::from_big_endian(&stack.peek(2)?[..]),
         },
+        Ok(Opcode::MCopy) if config.has_mcopy => GasCost::VeryLowCopy {
+            len: U256::from_big_endian(&stack.peek(2)?[..]),
+        },
+        Ok(Opcode::MCopy) => GasCost::Invalid,
         Ok(Opcode::Exp) => GasCost::Exp {
             power: U256::from_big_endian(&stack.peek(1)?[..]),
         },
         Ok(Opcode::JumpDest) => GasCost::JumpDest,
-        Err(ExternalOpcode::SLoad) => GasCost::SLoad,
+        Err(ExternalOpcode::SLoad) => {
+            let index = stack.peek(0)?;
+            storage_target = StorageTarget::Slot(address, index);
+            GasCost::SLoad {
+                target_is_cold: handler.is_cold(address, Some(index)),
+            }
+        }
 
-        Err(ExternalOpcode::DelegateCall) if config.has_delegate_call => GasCost::DelegateCall {
-            gas: U256::from_big_endian(&stack.peek(0)?[..]),
-            target_exists: handler.exists(stack.peek(1)?.into()),
-        },
+        Err(ExternalOpcode::DelegateCall) if config.has_delegate_call => {
+            let target = stack.peek(1)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::DelegateCall {
+                gas: U256::from_big_endian(&stack.peek(0)?[..]),
+                target_is_cold: handler.is_cold(target, None),
+                target_exists: handler.exists(target),
+            }
+        }
         Err(ExternalOpcode::DelegateCall) => GasCost::Invalid,
 
+        Err(ExternalOpcode::TLoad) if config.has_transient_storage => GasCost::WarmStorageRead,
+        Err(ExternalOpcode::TLoad) => GasCost::Invalid,
+
         Err(ExternalOpcode::ReturnDataSize) if config.has_return_data => GasCost::Base,
         Err(ExternalOpcode::ReturnDataCopy) if config.has_return_data => GasCost::VeryLowCopy {
             len: U256::from_big_endian(&stack.peek(2)?[..]),
@@ -497,33 +606,48 @@ pub fn opcode_cost<H: Handler>(
         Err(ExternalOpcode::SStore) if !is_static => {
             let index = stack.peek(0)?;
             let value = stack.peek(1)?;
+            storage_target = StorageTarget::Slot(address, index);
 
             GasCost::SStore {
                 original: handler.original_storage(address, index),
                 current: handler.storage(address, index),
                 new: value,
+                target_is_cold: handler.is_cold(address, Some(index)),
             }
         }
+        Err(ExternalOpcode::TStore) if !is_static && config.has_transient_storage => {
+            GasCost::WarmStorageRead
+        }
         Err(ExternalOpcode::Log(n)) if !is_static => GasCost::Log {
             n,
             len: U256::from_big_endian(&stack.peek(1)?[..]),
         },
-        Err(ExternalOpcode::Create) if !is_static => GasCost::Create,
-        Err(ExternalOpcode::Create2) if !is_static && config.has_create2 => GasCost::Create2 {
+        Err(ExternalOpcode::Create) if !is_static => GasCost::Create {
             len: U256::from_big_endian(&stack.peek(2)?[..]),
         },
-        Err(ExternalOpcode::Suicide) if !is_static => GasCost::Suicide {
-            value: handler.balance(address),
-            target_exists: handler.exists(stack.peek(0)?.into()),
-            already_removed: handler.deleted(address),
+        Err(ExternalOpcode::Create2) if !is_static && config.has_create2 => GasCost::Create2 {
+            len: U256::from_big_endian(&stack.peek(2)?[..]),
         },
+        Err(ExternalOpcode::Suicide) if !is_static => {
+            let target = stack.peek(0)?.into();
+            storage_target = StorageTarget::Address(target);
+            GasCost::Suicide {
+                value: handler.balance(address),
+                target_is_cold: handler.is_cold(target, None),
+                target_exists: handler.exists(target),
+                already_removed: handler.deleted(address),
+            }
+        }
         Err(ExternalOpcode::Call)
             if !is_static || U256::from_big_endian(&stack.peek(2)?[..]) == U256::zero() =>
         {
+            let target = stack.peek(1)?.into();
+            storage_target = StorageTarget::Address(target);
             GasCost::Call {
                 value: U256::from_big_endian(&stack.peek(2)?[..]),
                 gas: U256::from_big_endian(&stack.peek(0)?[..]),
-                target_exists: handler.exists(stack.peek(1)?.into()),
+                target_is_cold: handler.is_cold(target, None),
+                target_exists: handler.exists(target),
             }
         }
 
@@ -532,6 +656,7 @@ pub fn opcode_cost<H: Handler>(
         Err(ExternalOpcode::Create)
         | Err(ExternalOpcode::Create2)
         | Err(ExternalOpcode::SStore)
+        | Err(ExternalOpcode::TStore)
         | Err(ExternalOpcode::Log(_))
         | Err(ExternalOpcode::Suicide)
         | Err(ExternalOpcode::Call)
@@ -558,6 +683,19 @@ pub fn opcode_cost<H: Handler>(
             offset: U256::from_big_endian(&stack.peek(1)?[..]),
             len: U256::from_big_endian(&stack.peek(3)?[..]),
         }),
+        Ok(Opcode::MCopy) => {
+            let len = U256::from_big_endian(&stack.peek(2)?[..]);
+            Some(
+                MemoryCost {
+                    offset: U256::from_big_endian(&stack.peek(0)?[..]),
+                    len,
+                }
+                .join(MemoryCost {
+                    offset: U256::from_big_endian(&stack.peek(1)?[..]),
+                    len,
+                }),
+            )
+        }
 
         Ok(Opcode::MLoad) | Ok(Opcode::MStore) => Some(MemoryCost {
             offset: U256::from_big_endian(&stack.peek(0)?[..]),
@@ -599,7 +737,7 @@ pub fn opcode_cost<H: Handler>(
         _ => None,
     };
 
-    Ok((gas_cost, memory_cost))
+    Ok((gas_cost, storage_target, memory_cost))
 }
 
 #[derive(Clone)]
@@ -648,40 +786,65 @@ impl<'config> Inner<'config> {
         Ok(match cost {
             GasCost::Call {
                 value,
+                target_is_cold,
                 target_exists,
                 ..
-            } => costs::call_cost(value, true, true, !target_exists, self.config),
+            } => costs::call_cost(value, true, true, target_is_cold, !target_exists, self.config),
             GasCost::CallCode {
                 value,
+                target_is_cold,
                 target_exists,
                 ..
-            } => costs::call_cost(value, true, false, !target_exists, self.config),
-            GasCost::DelegateCall { target_exists, .. } => {
-                costs::call_cost(U256::zero(), false, false, !target_exists, self.config)
-            }
-            GasCost::StaticCall { target_exists, .. } => {
-                costs::call_cost(U256::zero(), false, true, !target_exists, self.config)
-            }
+            } => costs::call_cost(value, true, false, target_is_cold, !target_exists, self.config),
+            GasCost::DelegateCall {
+                target_is_cold,
+                target_exists,
+                ..
+            } => costs::call_cost(
+                U256::zero(),
+                false,
+                false,
+                target_is_cold,
+                !target_exists,
+                self.config,
+            ),
+            GasCost::StaticCall {
+                target_is_cold,
+                target_exists,
+                ..
+            } => costs::call_cost(
+                U256::zero(),
+                false,
+                true,
+                target_is_cold,
+                !target_exists,
+                self.config,
+            ),
             GasCost::Suicide {
                 value,
+                target_is_cold,
                 target_exists,
                 ..
-            } => costs::suicide_cost(value, target_exists, self.config),
+            } => costs::suicide_cost(value, target_is_cold, target_exists, self.config),
             GasCost::SStore {
                 original,
                 current,
                 new,
-            } => costs::sstore_cost(original, current, new, gas, self.config)?,
+                target_is_cold,
+            } => costs::sstore_cost(original, current, new, gas, target_is_cold, self.config)?,
 
             GasCost::Sha3 { len } => costs::sha3_cost(len)?,
             GasCost::Log { n, len } => costs::log_cost(n, len)?,
-            GasCost::ExtCodeCopy { len } => costs::extcodecopy_cost(len, self.config)?,
+            GasCost::ExtCodeCopy {
+                target_is_cold,
+                len,
+            } => costs::extcodecopy_cost(len, target_is_cold, self.config)?,
             GasCost::VeryLowCopy { len } => costs::verylowcopy_cost(len)?,
             GasCost::Exp { power } => costs::exp_cost(power, self.config)?,
-            GasCost::Create => consts::G_CREATE,
-            GasCost::Create2 { len } => costs::create2_cost(len)?,
+            GasCost::Create { len } => costs::create_cost(len, self.config)?,
+            GasCost::Create2 { len } => costs::create2_cost(len, self.config)?,
             GasCost::JumpDest => consts::G_JUMPDEST,
-            GasCost::SLoad => self.config.gas_sload,
+            GasCost::SLoad { target_is_cold } => costs::sload_cost(target_is_cold, self.config),
 
             GasCost::Zero => consts::G_ZERO,
             GasCost::Base => consts::G_BASE,
@@ -689,12 +852,19 @@ impl<'config> Inner<'config> {
             GasCost::Low => consts::G_LOW,
             GasCost::Mid => consts::G_MID,
             GasCost::High => consts::G_HIGH,
+            GasCost::WarmStorageRead => self.config.gas_storage_read_warm,
             GasCost::Invalid => return Err(ExitError::OutOfGas),
 
-            GasCost::ExtCodeSize => self.config.gas_ext_code,
-            GasCost::Balance => self.config.gas_balance,
+            GasCost::ExtCodeSize { target_is_cold } => {
+                costs::address_access_cost(target_is_cold, self.config.gas_ext_code, self.config)
+            }
+            GasCost::Balance { target_is_cold } => {
+                costs::address_access_cost(target_is_cold, self.config.gas_balance, self.config)
+            }
             GasCost::BlockHash => consts::G_BLOCKHASH,
-            GasCost::ExtCodeHash => self.config.gas_ext_code_hash,
+            GasCost::ExtCodeHash { target_is_cold } => {
+                costs::address_access_cost(target_is_cold, self.config.gas_ext_code_hash, self.config)
+            }
         })
     }
 
@@ -704,10 +874,11 @@ impl<'config> Inner<'config> {
                 original,
                 current,
                 new,
+                ..
             } => costs::sstore_refund(original, current, new, self.config),
             GasCost::Suicide {
                 already_removed, ..
-            } => costs::suicide_refund(already_removed),
+            } => costs::suicide_refund(already_removed, self.config),
             _ => 0,
         }
     }
@@ -761,40 +932,65 @@ impl InnerOwned {
         Ok(match cost {
             GasCost::Call {
                 value,
+                target_is_cold,
                 target_exists,
                 ..
-            } => costs::call_cost(value, true, true, !target_exists, &self.config),
+            } => costs::call_cost(value, true, true, target_is_cold, !target_exists, &self.config),
             GasCost::CallCode {
                 value,
+                target_is_cold,
                 target_exists,
                 ..
-            } => costs::call_cost(value, true, false, !target_exists, &self.config),
-            GasCost::DelegateCall { target_exists, .. } => {
-                costs::call_cost(U256::zero(), false, false, !target_exists, &self.config)
-            }
-            GasCost::StaticCall { target_exists, .. } => {
-                costs::call_cost(U256::zero(), false, true, !target_exists, &self.config)
-            }
+            } => costs::call_cost(value, true, false, target_is_cold, !target_exists, &self.config),
+            GasCost::DelegateCall {
+                target_is_cold,
+                target_exists,
+                ..
+            } => costs::call_cost(
+                U256::zero(),
+                false,
+                false,
+                target_is_cold,
+                !target_exists,
+                &self.config,
+            ),
+            GasCost::StaticCall {
+                target_is_cold,
+                target_exists,
+                ..
+            } => costs::call_cost(
+                U256::zero(),
+                false,
+                true,
+                target_is_cold,
+                !target_exists,
+                &self.config,
+            ),
             GasCost::Suicide {
                 value,
+                target_is_cold,
                 target_exists,
                 ..
-            } => costs::suicide_cost(value, target_exists, &self.config),
+            } => costs::suicide_cost(value, target_is_cold, target_exists, &self.config),
             GasCost::SStore {
                 original,
                 current,
                 new,
-            } => costs::sstore_cost(original, current, new, gas, &self.config)?,
+                target_is_cold,
+            } => costs::sstore_cost(original, current, new, gas, target_is_cold, &self.config)?,
 
             GasCost::Sha3 { len } => costs::sha3_cost(len)?,
             GasCost::Log { n, len } => costs::log_cost(n, len)?,
-            GasCost::ExtCodeCopy { len } => costs::extcodecopy_cost(len, &self.config)?,
+            GasCost::ExtCodeCopy {
+                target_is_cold,
+                len,
+            } => costs::extcodecopy_cost(len, target_is_cold, &self.config)?,
             GasCost::VeryLowCopy { len } => costs::verylowcopy_cost(len)?,
             GasCost::Exp { power } => costs::exp_cost(power, &self.config)?,
-            GasCost::Create => consts::G_CREATE,
-            GasCost::Create2 { len } => costs::create2_cost(len)?,
+            GasCost::Create { len } => costs::create_cost(len, &self.config)?,
+            GasCost::Create2 { len } => costs::create2_cost(len, &self.config)?,
             GasCost::JumpDest => consts::G_JUMPDEST,
-            GasCost::SLoad => self.config.gas_sload,
+            GasCost::SLoad { target_is_cold } => costs::sload_cost(target_is_cold, &self.config),
 
             GasCost::Zero => consts::G_ZERO,
             GasCost::Base => consts::G_BASE,
@@ -802,12 +998,19 @@ impl InnerOwned {
             GasCost::Low => consts::G_LOW,
             GasCost::Mid => consts::G_MID,
             GasCost::High => consts::G_HIGH,
+            GasCost::WarmStorageRead => self.config.gas_storage_read_warm,
             GasCost::Invalid => return Err(ExitError::OutOfGas),
 
-            GasCost::ExtCodeSize => self.config.gas_ext_code,
-            GasCost::Balance => self.config.gas_balance,
+            GasCost::ExtCodeSize { target_is_cold } => {
+                costs::address_access_cost(target_is_cold, self.config.gas_ext_code, &self.config)
+            }
+            GasCost::Balance { target_is_cold } => {
+                costs::address_access_cost(target_is_cold, self.config.gas_balance, &self.config)
+            }
             GasCost::BlockHash => consts::G_BLOCKHASH,
-            GasCost::ExtCodeHash => self.config.gas_ext_code_hash,
+            GasCost::ExtCodeHash { target_is_cold } => {
+                costs::address_access_cost(target_is_cold, self.config.gas_ext_code_hash, &self.config)
+            }
         })
     }
 
@@ -817,10 +1020,11 @@ impl InnerOwned {
                 original,
                 current,
                 new,
+                ..
             } => costs::sstore_refund(original, current, new, &self.config),
             GasCost::Suicide {
                 already_removed, ..
-            } => costs::suicide_refund(already_removed),
+            } => costs::suicide_refund(already_removed, &self.config),
             _ => 0,
         }
     }
@@ -841,17 +1045,28 @@ pub enum GasCost {
     Mid,
     /// High gas cost.
     High,
+    /// Warm storage read gas cost, charged by `TLOAD` and `TSTORE`.
+    WarmStorageRead,
     /// Fail the gasometer.
     Invalid,
 
     /// Gas cost for `EXTCODESIZE`.
-    ExtCodeSize,
+    ExtCodeSize {
+        /// Whether the target is cold.
+        target_is_cold: bool,
+    },
     /// Gas cost for `BALANCE`.
-    Balance,
+    Balance {
+        /// Whether the target is cold.
+        target_is_cold: bool,
+    },
     /// Gas cost for `BLOCKHASH`.
     BlockHash,
     /// Gas cost for `EXTBLOCKHASH`.
-    ExtCodeHash,
+    ExtCodeHash {
+        /// Whether the target is cold.
+        target_is_cold: bool,
+    },
 
     /// Gas cost for `CALL`.
     Call {
@@ -859,6 +1074,8 @@ pub enum GasCost {
         value: U256,
         /// Call gas.
         gas: U256,
+        /// Whether the target is cold.
+        target_is_cold: bool,
         /// Whether the target exists.
         target_exists: bool,
     },
@@ -868,6 +1085,8 @@ pub enum GasCost {
         value: U256,
         /// Call gas.
         gas: U256,
+        /// Whether the target is cold.
+        target_is_cold: bool,
         /// Whether the target exists.
         target_exists: bool,
     },
@@ -875,6 +1094,8 @@ pub enum GasCost {
     DelegateCall {
         /// Call gas.
         gas: U256,
+        /// Whether the target is cold.
+        target_is_cold: bool,
         /// Whether the target exists.
         target_exists: bool,
     },
@@ -882,6 +1103,8 @@ pub enum GasCost {
     StaticCall {
         /// Call gas.
         gas: U256,
+        /// Whether the target is cold.
+        target_is_cold: bool,
         /// Whether the target exists.
         target_exists: bool,
     },
@@ -889,6 +1112,8 @@ pub enum GasCost {
     Suicide {
         /// Value.
         value: U256,
+        /// Whether the target is cold.
+        target_is_cold: bool,
         /// Whether the target exists.
         target_exists: bool,
         /// Whether the target has already been removed.
@@ -902,6 +1127,8 @@ pub enum GasCost {
         current: H256,
         /// New value.
         new: H256,
+        /// Whether the slot is cold.
+        target_is_cold: bool,
     },
     /// Gas cost for `SHA3`.
     Sha3 {
@@ -917,6 +1144,8 @@ pub enum GasCost {
     },
     /// Gas cost for `EXTCODECOPY`.
     ExtCodeCopy {
+        /// Whether the target is cold.
+        target_is_cold: bool,
         /// Length.
         len: U256,
     },
@@ -931,7 +1160,10 @@ pub enum GasCost {
         power: U256,
     },
     /// Gas cost for `CREATE`.
-    Create,
+    Create {
+        /// Length.
+        len: U256,
+    },
     /// Gas cost for `CREATE2`.
     Create2 {
         /// Length.
@@ -940,7 +1172,22 @@ pub enum GasCost {
     /// Gas cost for `JUMPDEST`.
     JumpDest,
     /// Gas cost for `SLOAD`.
-    SLoad,
+    SLoad {
+        /// Whether the slot is cold.
+        target_is_cold: bool,
+    },
+}
+
+/// Account or storage slot accessed by an opcode, to be marked warm once the
+/// opcode is charged.
+#[derive(Debug, Clone, Copy)]
+pub enum StorageTarget {
+    /// No account or slot is accessed.
+    None,
+    /// An account is accessed.
+    Address(H160),
+    /// A storage slot of an account is accessed.
+    Slot(H160, H256),
 }
 
 /// Memory cost.
@@ -961,6 +1208,10 @@ pub enum TransactionCost {
         zero_data_len: usize,
         /// Length of non-zeros in transaction data.
         non_zero_data_len: usize,
+        /// Number of addresses in the access list.
+        access_list_address_len: usize,
+        /// Number of storage keys in the access list.
+        access_list_storage_len: usize,
     },
     /// Create transaction cost.
     Create {
@@ -968,6 +1219,10 @@ pub enum TransactionCost {
         zero_data_len: usize,
         /// Length of non-zeros in transaction data.
         non_zero_data_len: usize,
+        /// Number of addresses in the access list.
+        access_list_address_len: usize,
+        /// Number of storage keys in the access list.
+        access_list_storage_len: usize,
     },
 }
 
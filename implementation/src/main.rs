@@ -23,6 +23,8 @@ fn main() {
         block_timestamp: U256::zero(),
         block_difficulty: U256::zero(),
         block_gas_limit: U256::from(12500000i128),
+        block_base_fee_per_gas: U256::zero(),
+        block_randomness: None,
     };
     let state: BTreeMap<H160, MemoryAccount> = BTreeMap::new();
     let provider = args[1].clone();
@@ -73,6 +75,7 @@ fn main() {
         U256::zero(), // value: 0 eth
         my_bytes,     // data
         1250000,      // gas_limit
+        Vec::new(),
     );
     let my_new_contract;
     match s.1 {
@@ -92,6 +95,7 @@ fn main() {
         U256::zero(),
         hex::decode("f8a8fd6d").unwrap(),
         50000,
+        Vec::new(),
     );
     // we expect a U256 response, equal to 20000, so decode it below
     println!("ret: {:?}", U256::from(from_slice(&ret.1[..])));
@@ -103,6 +107,7 @@ fn main() {
         U256::zero(),
         hex::decode("48c8ec72").unwrap(),
         50000,
+        Vec::new(),
     );
 
     let (applies, logs) = exec.deconstruct();
@@ -2,6 +2,8 @@
 mod macros;
 mod system;
 
+pub use self::system::{finish_call, finish_create};
+
 use crate::{CallScheme, ExitReason, ExternalOpcode, Handler, Runtime};
 
 pub enum Control<H: Handler> {
@@ -36,8 +38,11 @@ pub fn eval<H: Handler>(
         ExternalOpcode::Number => system::number(state, handler),
         ExternalOpcode::Difficulty => system::difficulty(state, handler),
         ExternalOpcode::GasLimit => system::gaslimit(state, handler),
+        ExternalOpcode::BaseFee => system::basefee(state, handler),
         ExternalOpcode::SLoad => system::sload(state, handler),
         ExternalOpcode::SStore => system::sstore(state, handler),
+        ExternalOpcode::TLoad => system::tload(state, handler),
+        ExternalOpcode::TStore => system::tstore(state, handler),
         ExternalOpcode::Gas => system::gas(state, handler),
         ExternalOpcode::Log(n) => system::log(state, n, handler),
         ExternalOpcode::Suicide => system::suicide(state, handler),
@@ -1,11 +1,12 @@
 use super::Control;
+use crate::interrupt::Pending;
 use crate::{
     CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitReason, ExitSucceed,
     Handler, Runtime, Transfer,
 };
 use alloc::vec::Vec;
 use core::cmp::min;
-use primitive_types::{H256, U256};
+use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 
 pub fn sha3<H: Handler>(runtime: &mut Runtime) -> Control<H> {
@@ -174,7 +175,11 @@ pub fn number<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 }
 
 pub fn difficulty<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-    push_u256!(runtime, handler.block_difficulty());
+    if runtime.config.has_prevrandao {
+        push!(runtime, handler.block_randomness().unwrap_or_default());
+    } else {
+        push_u256!(runtime, handler.block_difficulty());
+    }
     Control::Continue
 }
 
@@ -183,6 +188,11 @@ pub fn gaslimit<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
     Control::Continue
 }
 
+pub fn basefee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+    push_u256!(runtime, handler.block_base_fee_per_gas());
+    Control::Continue
+}
+
 pub fn sload<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
     pop!(runtime, index);
     push!(runtime, handler.storage(runtime.context.address, index));
@@ -198,6 +208,21 @@ pub fn sstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H>
     }
 }
 
+pub fn tload<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+    pop!(runtime, index);
+    push!(runtime, handler.transient_storage(runtime.context.address, index));
+
+    Control::Continue
+}
+
+pub fn tstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
+    pop!(runtime, index, value);
+    match handler.set_transient_storage(runtime.context.address, index, value) {
+        Ok(()) => Control::Continue,
+        Err(e) => Control::Exit(e.into()),
+    }
+}
+
 pub fn gas<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
     push_u256!(runtime, handler.gas_left());
 
@@ -275,35 +300,38 @@ pub fn create<H: Handler>(runtime: &mut Runtime, is_create2: bool, handler: &mut
 
     match handler.create(runtime.context.address, scheme, value, code, None) {
         Capture::Exit((reason, address, return_data)) => {
-            runtime.return_data_buffer = return_data;
-            let create_address: H256 = address.map(|a| a.into()).unwrap_or_default();
-
-            match reason {
-                ExitReason::Succeed(_) => {
-                    push!(runtime, create_address);
-                    Control::Continue
-                }
-                ExitReason::Revert(_) => {
-                    push!(runtime, H256::default());
-                    Control::Continue
-                }
-                ExitReason::Error(_) => {
-                    push!(runtime, H256::default());
-                    Control::Continue
-                }
-                ExitReason::Fatal(e) => {
-                    push!(runtime, H256::default());
-                    Control::Exit(e.into())
-                }
+            match finish_create(runtime, reason, address, return_data) {
+                Ok(()) => Control::Continue,
+                Err(e) => Control::Exit(e),
             }
         }
         Capture::Trap(interrupt) => {
-            push!(runtime, H256::default());
+            runtime.pending = Some(Pending::Create);
             Control::CreateInterrupt(interrupt)
         }
     }
 }
 
+/// Push the result of a `CREATE` and keep its return data.
+pub fn finish_create(
+    runtime: &mut Runtime,
+    reason: ExitReason,
+    address: Option<H160>,
+    return_data: Vec<u8>,
+) -> Result<(), ExitReason> {
+    runtime.return_data_buffer = return_data;
+    let create_address: H256 = match reason {
+        ExitReason::Succeed(_) => address.map(|a| a.into()).unwrap_or_default(),
+        _ => H256::default(),
+    };
+    runtime.machine.stack_mut().push(create_address)?;
+
+    match reason {
+        ExitReason::Fatal(e) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
 pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut H) -> Control<H> {
     runtime.return_data_buffer = Vec::new();
 
@@ -386,54 +414,60 @@ pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut
         context,
     ) {
         Capture::Exit((reason, return_data)) => {
-            runtime.return_data_buffer = return_data;
-            let target_len = min(out_len, U256::from(runtime.return_data_buffer.len()));
-
-            match reason {
-                ExitReason::Succeed(_) => {
-                    match runtime.machine.memory_mut().copy_large(
-                        out_offset,
-                        U256::zero(),
-                        target_len,
-                        &runtime.return_data_buffer[..],
-                    ) {
-                        Ok(()) => {
-                            push_u256!(runtime, U256::one());
-                            Control::Continue
-                        }
-                        Err(_) => {
-                            push_u256!(runtime, U256::zero());
-                            Control::Continue
-                        }
-                    }
-                }
-                ExitReason::Revert(_) => {
-                    push_u256!(runtime, U256::zero());
-
-                    let _ = runtime.machine.memory_mut().copy_large(
-                        out_offset,
-                        U256::zero(),
-                        target_len,
-                        &runtime.return_data_buffer[..],
-                    );
-
-                    Control::Continue
-                }
-                ExitReason::Error(_) => {
-                    push_u256!(runtime, U256::zero());
-
-                    Control::Continue
-                }
-                ExitReason::Fatal(e) => {
-                    push_u256!(runtime, U256::zero());
-
-                    Control::Exit(e.into())
-                }
+            match finish_call(runtime, out_offset, out_len, reason, return_data) {
+                Ok(()) => Control::Continue,
+                Err(e) => Control::Exit(e),
             }
         }
         Capture::Trap(interrupt) => {
-            push!(runtime, H256::default());
+            runtime.pending = Some(Pending::Call {
+                out_offset,
+                out_len,
+            });
             Control::CallInterrupt(interrupt)
         }
     }
 }
+
+/// Push the result of a `CALL`, copying its return data to the output range.
+pub fn finish_call(
+    runtime: &mut Runtime,
+    out_offset: U256,
+    out_len: U256,
+    reason: ExitReason,
+    return_data: Vec<u8>,
+) -> Result<(), ExitReason> {
+    runtime.return_data_buffer = return_data;
+    let target_len = min(out_len, U256::from(runtime.return_data_buffer.len()));
+
+    let success = match reason {
+        ExitReason::Succeed(_) => runtime
+            .machine
+            .memory_mut()
+            .copy_large(
+                out_offset,
+                U256::zero(),
+                target_len,
+                &runtime.return_data_buffer[..],
+            )
+            .is_ok(),
+        ExitReason::Revert(_) => {
+            let _ = runtime.machine.memory_mut().copy_large(
+                out_offset,
+                U256::zero(),
+                target_len,
+                &runtime.return_data_buffer[..],
+            );
+            false
+        }
+        ExitReason::Error(_) | ExitReason::Fatal(_) => false,
+    };
+    let mut value = H256::default();
+    U256::from(success as u8).to_big_endian(&mut value[..]);
+    runtime.machine.stack_mut().push(value)?;
+
+    match reason {
+        ExitReason::Fatal(e) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -38,6 +38,8 @@ pub trait Handler {
     fn storage(&mut self, address: H160, index: H256) -> H256;
     /// Get original storage value of address at index.
     fn original_storage(&mut self, address: H160, index: H256) -> H256;
+    /// Get transient storage value of address at index.
+    fn transient_storage(&self, address: H160, index: H256) -> H256;
 
     /// Get the gas left value.
     fn gas_left(&self) -> U256;
@@ -57,6 +59,10 @@ pub trait Handler {
     fn block_difficulty(&self) -> U256;
     /// Get environmental gas limit.
     fn block_gas_limit(&self) -> U256;
+    /// Get environmental base fee per gas.
+    fn block_base_fee_per_gas(&self) -> U256;
+    /// Get environmental block randomness (`PREVRANDAO`).
+    fn block_randomness(&self) -> Option<H256>;
     /// Get environmental chain ID.
     fn chain_id(&self) -> U256;
 
@@ -64,9 +70,19 @@ pub trait Handler {
     fn exists(&self, address: H160) -> bool;
     /// Check whether an address has already been deleted.
     fn deleted(&self, address: H160) -> bool;
+    /// Check whether an address, or a storage slot of it if `index` is given,
+    /// has not yet been accessed in the transaction.
+    fn is_cold(&self, address: H160, index: Option<H256>) -> bool;
 
     /// Set storage value of address at index.
     fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError>;
+    /// Set transient storage value of address at index.
+    fn set_transient_storage(
+        &mut self,
+        address: H160,
+        index: H256,
+        value: H256,
+    ) -> Result<(), ExitError>;
     /// Create a log owned by address with given topics and data.
     fn log(&mut self, address: H160, topcis: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError>;
     /// Mark an address to be deleted, with funds transferred to target.
@@ -106,6 +122,26 @@ pub trait Handler {
         opcode: Result<Opcode, ExternalOpcode>,
         stack: &Stack,
     ) -> Result<(), ExitError>;
+    /// Called before the opcode at `pc` is validated and executed.
+    fn step_begin(
+        &mut self,
+        _context: &Context,
+        _pc: usize,
+        _opcode: Result<Opcode, ExternalOpcode>,
+        _machine: &Machine,
+    ) {
+    }
+    /// Called after the opcode at `pc` has executed, with the exit reason if
+    /// the machine stopped on it.
+    fn step_end(
+        &mut self,
+        _context: &Context,
+        _pc: usize,
+        _opcode: Result<Opcode, ExternalOpcode>,
+        _machine: &Machine,
+        _exit: Option<ExitReason>,
+    ) {
+    }
     /// Handle other unknown xternal opcodes.
     fn other(&mut self, _opcode: u8, _stack: &mut Machine) -> Result<(), ExitError> {
         Err(ExitError::OutOfGas)
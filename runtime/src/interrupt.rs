@@ -1,4 +1,6 @@
-use crate::{ExitFatal, Handler, Runtime};
+use crate::{ExitReason, Handler, Runtime};
+use alloc::vec::Vec;
+use primitive_types::{H160, U256};
 
 /// Interrupt resolution.
 pub enum Resolve<'a, 'config, H: Handler> {
@@ -8,7 +10,15 @@ pub enum Resolve<'a, 'config, H: Handler> {
     Call(H::CallInterrupt, ResolveCall<'a, 'config>),
 }
 
+/// Interrupt a runtime is waiting on.
+pub(crate) enum Pending {
+    Call { out_offset: U256, out_len: U256 },
+    Create,
+}
+
 /// Create interrupt resolution.
+///
+/// Dropping it leaves the runtime waiting for `Runtime::finish_create`.
 pub struct ResolveCreate<'a, 'config> {
     runtime: &'a mut Runtime<'config>,
 }
@@ -17,18 +27,21 @@ impl<'a, 'config> ResolveCreate<'a, 'config> {
     pub(crate) fn new(runtime: &'a mut Runtime<'config>) -> Self {
         Self { runtime }
     }
-}
 
-impl<'a, 'config> Drop for ResolveCreate<'a, 'config> {
-    fn drop(&mut self) {
-        self.runtime.status = Err(ExitFatal::UnhandledInterrupt.into());
-        self.runtime
-            .machine
-            .exit(ExitFatal::UnhandledInterrupt.into());
+    /// Resume the runtime with the result of the create.
+    pub fn finish(
+        self,
+        reason: ExitReason,
+        address: Option<H160>,
+        return_data: Vec<u8>,
+    ) -> Result<(), ExitReason> {
+        self.runtime.finish_create(reason, address, return_data)
     }
 }
 
 /// Call interrupt resolution.
+///
+/// Dropping it leaves the runtime waiting for `Runtime::finish_call`.
 pub struct ResolveCall<'a, 'config> {
     runtime: &'a mut Runtime<'config>,
 }
@@ -37,13 +50,9 @@ impl<'a, 'config> ResolveCall<'a, 'config> {
     pub(crate) fn new(runtime: &'a mut Runtime<'config>) -> Self {
         Self { runtime }
     }
-}
 
-impl<'a, 'config> Drop for ResolveCall<'a, 'config> {
-    fn drop(&mut self) {
-        self.runtime.status = Err(ExitFatal::UnhandledInterrupt.into());
-        self.runtime
-            .machine
-            .exit(ExitFatal::UnhandledInterrupt.into());
+    /// Resume the runtime with the result of the call.
+    pub fn finish(self, reason: ExitReason, return_data: Vec<u8>) -> Result<(), ExitReason> {
+        self.runtime.finish_call(reason, return_data)
     }
 }
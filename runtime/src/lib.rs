@@ -17,11 +17,29 @@ pub use crate::context::{CallScheme, Context, CreateScheme};
 pub use crate::handler::{Handler, Transfer};
 pub use crate::interrupt::{Resolve, ResolveCall, ResolveCreate};
 
+use crate::interrupt::Pending;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
+use primitive_types::H160;
+
+macro_rules! step_end {
+	( $self:expr, $handler:expr, $step:expr, $exit:expr ) => {
+		if let Some((pc, opcode)) = $step {
+			$handler.step_end(&$self.context, pc, opcode, &$self.machine, $exit);
+		}
+	};
+}
 
 macro_rules! step {
 	( $self:expr, $handler:expr, $return:tt $($err:path)?; $($ok:path)? ) => ({
+		let step = match $self.machine.position() {
+			Ok(pc) => $self.machine.inspect().map(|(opcode, _)| (*pc, opcode)),
+			Err(_) => None,
+		};
+		if let Some((pc, opcode)) = step {
+			$handler.step_begin(&$self.context, pc, opcode, &$self.machine);
+		}
+
 		if let Some((opcode, stack)) = $self.machine.inspect() {
 			match $handler.pre_validate(&$self.context, opcode, stack) {
 				Ok(()) => (),
@@ -35,27 +53,37 @@ macro_rules! step {
 		match $self.status.clone() {
 			Ok(()) => (),
 			Err(e) => {
+				step_end!($self, $handler, step, Some(e));
 				#[allow(unused_parens)]
 				$return $($err)*(Capture::Exit(e))
 			},
 		}
 
 		match $self.machine.step() {
-			Ok(()) => $($ok)?(()),
+			Ok(()) => {
+				step_end!($self, $handler, step, None);
+				$($ok)?(())
+			},
 			Err(Capture::Exit(e)) => {
 				$self.status = Err(e);
+				step_end!($self, $handler, step, Some(e));
 				#[allow(unused_parens)]
 				$return $($err)*(Capture::Exit(e))
 			},
 			Err(Capture::Trap(opcode)) => {
 				match eval::eval($self, opcode, $handler) {
-					eval::Control::Continue => $($ok)?(()),
+					eval::Control::Continue => {
+						step_end!($self, $handler, step, None);
+						$($ok)?(())
+					},
 					eval::Control::CallInterrupt(interrupt) => {
+						step_end!($self, $handler, step, None);
 						let resolve = ResolveCall::new($self);
 						#[allow(unused_parens)]
 						$return $($err)*(Capture::Trap(Resolve::Call(interrupt, resolve)))
 					},
 					eval::Control::CreateInterrupt(interrupt) => {
+						step_end!($self, $handler, step, None);
 						let resolve = ResolveCreate::new($self);
 						#[allow(unused_parens)]
 						$return $($err)*(Capture::Trap(Resolve::Create(interrupt, resolve)))
@@ -63,6 +91,7 @@ macro_rules! step {
 					eval::Control::Exit(exit) => {
 						$self.machine.exit(exit.into());
 						$self.status = Err(exit);
+						step_end!($self, $handler, step, Some(exit));
 						#[allow(unused_parens)]
 						$return $($err)*(Capture::Exit(exit))
 					},
@@ -82,7 +111,8 @@ pub struct Runtime<'config> {
     /// return data buffer
     pub return_data_buffer: Vec<u8>,
     context: Context,
-    _config: &'config Config,
+    config: &'config Config,
+    pending: Option<Pending>,
 }
 
 impl<'config> Runtime<'config> {
@@ -98,7 +128,8 @@ impl<'config> Runtime<'config> {
             status: Ok(()),
             return_data_buffer: Vec::new(),
             context,
-            _config: config,
+            config,
+            pending: None,
         }
     }
 
@@ -112,6 +143,9 @@ impl<'config> Runtime<'config> {
         &'a mut self,
         handler: &mut H,
     ) -> Result<(), Capture<ExitReason, Resolve<'a, 'config, H>>> {
+        if self.pending.is_some() {
+            return Err(Capture::Exit(self.unhandled_interrupt()));
+        }
         step!(self, handler, return Err; Ok)
     }
 
@@ -120,10 +154,56 @@ impl<'config> Runtime<'config> {
         &'a mut self,
         handler: &mut H,
     ) -> Capture<ExitReason, Resolve<'a, 'config, H>> {
+        if self.pending.is_some() {
+            return Capture::Exit(self.unhandled_interrupt());
+        }
         loop {
             step!(self, handler, return;)
         }
     }
+
+    /// Resume after a `CALL` interrupt with the result of the call.
+    pub fn finish_call(
+        &mut self,
+        reason: ExitReason,
+        return_data: Vec<u8>,
+    ) -> Result<(), ExitReason> {
+        match self.pending.take() {
+            Some(Pending::Call {
+                out_offset,
+                out_len,
+            }) => eval::finish_call(self, out_offset, out_len, reason, return_data)
+                .map_err(|e| self.exit(e)),
+            _ => Err(self.unhandled_interrupt()),
+        }
+    }
+
+    /// Resume after a `CREATE` interrupt with the result of the create.
+    pub fn finish_create(
+        &mut self,
+        reason: ExitReason,
+        address: Option<H160>,
+        return_data: Vec<u8>,
+    ) -> Result<(), ExitReason> {
+        match self.pending.take() {
+            Some(Pending::Create) => {
+                eval::finish_create(self, reason, address, return_data).map_err(|e| self.exit(e))
+            }
+            _ => Err(self.unhandled_interrupt()),
+        }
+    }
+
+    /// Stop the runtime because an interrupt was not resolved as expected.
+    fn unhandled_interrupt(&mut self) -> ExitReason {
+        self.pending = None;
+        self.exit(ExitFatal::UnhandledInterrupt.into())
+    }
+
+    fn exit(&mut self, reason: ExitReason) -> ExitReason {
+        self.machine.exit(reason);
+        self.status = Err(reason);
+        reason
+    }
 }
 
 /// Runtime configuration.
@@ -159,10 +239,32 @@ pub struct Config {
     pub gas_transaction_zero_data: usize,
     /// Gas paid for non-zero data in a transaction.
     pub gas_transaction_non_zero_data: usize,
+    /// Gas paid per word of init code (EIP-3860).
+    pub gas_initcode_word: usize,
+    /// Gas paid per address in a transaction access list.
+    pub gas_access_list_address: usize,
+    /// Gas paid per storage key in a transaction access list.
+    pub gas_access_list_storage_key: usize,
+    /// Gas paid for accessing a cold account.
+    pub gas_account_access_cold: usize,
+    /// Gas paid for reading a warm account or storage slot.
+    pub gas_storage_read_warm: usize,
+    /// Gas paid for SLOAD of a cold storage slot.
+    pub gas_sload_cold: usize,
     /// EIP-1283.
     pub sstore_gas_metering: bool,
     /// EIP-1706.
     pub sstore_revert_under_stipend: bool,
+    /// EIP-2929.
+    pub increase_state_access_gas: bool,
+    /// Maximum share of used gas that can be refunded, as a quotient (EIP-3529).
+    pub max_refund_quotient: usize,
+    /// Whether `SUICIDE` is refunded. Removed in EIP-3529.
+    pub has_suicide_refund: bool,
+    /// Whether new code starting with 0xEF is rejected (EIP-3541).
+    pub disallow_executable_format: bool,
+    /// Whether the coinbase is warm at the start of a transaction (EIP-3651).
+    pub warm_coinbase_address: bool,
     /// Whether to throw out of gas error when
     /// CALL/CALLCODE/DELEGATECALL requires more than maximum amount
     /// of gas.
@@ -181,6 +283,8 @@ pub struct Config {
     pub call_stack_limit: usize,
     /// Create contract limit.
     pub create_contract_limit: Option<usize>,
+    /// Init code size limit (EIP-3860).
+    pub max_initcode_size: Option<usize>,
     /// Call stipend.
     pub call_stipend: usize,
     /// Has delegate call.
@@ -199,6 +303,32 @@ pub struct Config {
     pub has_self_balance: bool,
     /// Has ext code hash.
     pub has_ext_code_hash: bool,
+    /// Has base fee (EIP-3198).
+    pub has_base_fee: bool,
+    /// `DIFFICULTY` returns the block randomness (EIP-4399).
+    pub has_prevrandao: bool,
+    /// Has push0 (EIP-3855).
+    pub has_push0: bool,
+    /// Has transient storage (EIP-1153).
+    pub has_transient_storage: bool,
+    /// Has mcopy (EIP-5656).
+    pub has_mcopy: bool,
+    /// Has the modexp precompile (EIP-198).
+    pub has_modexp: bool,
+    /// Modexp precompile is priced according to EIP-2565.
+    pub modexp_eip2565: bool,
+    /// Has the alt_bn128 precompiles (EIP-196, EIP-197).
+    pub has_bn128: bool,
+    /// Has the blake2f precompile (EIP-152).
+    pub has_blake2f: bool,
+    /// Gas paid for the alt_bn128 addition precompile.
+    pub gas_bn128_add: usize,
+    /// Gas paid for the alt_bn128 scalar multiplication precompile.
+    pub gas_bn128_mul: usize,
+    /// Base gas paid for the alt_bn128 pairing precompile.
+    pub gas_bn128_pairing_base: usize,
+    /// Gas paid for every point of the alt_bn128 pairing precompile.
+    pub gas_bn128_pairing_point: usize,
 }
 
 impl Config {
@@ -220,8 +350,19 @@ impl Config {
             gas_transaction_call: 21000,
             gas_transaction_zero_data: 4,
             gas_transaction_non_zero_data: 68,
+            gas_initcode_word: 0,
+            gas_access_list_address: 0,
+            gas_access_list_storage_key: 0,
+            gas_account_access_cold: 0,
+            gas_storage_read_warm: 0,
+            gas_sload_cold: 0,
             sstore_gas_metering: false,
             sstore_revert_under_stipend: false,
+            increase_state_access_gas: false,
+            max_refund_quotient: 2,
+            has_suicide_refund: true,
+            disallow_executable_format: false,
+            warm_coinbase_address: false,
             err_on_call_with_more_gas: true,
             empty_considered_exists: true,
             create_increase_nonce: false,
@@ -230,6 +371,7 @@ impl Config {
             memory_limit: usize::max_value(),
             call_stack_limit: 1024,
             create_contract_limit: None,
+            max_initcode_size: None,
             call_stipend: 2300,
             has_delegate_call: false,
             has_create2: false,
@@ -239,6 +381,19 @@ impl Config {
             has_chain_id: false,
             has_self_balance: false,
             has_ext_code_hash: false,
+            has_base_fee: false,
+            has_prevrandao: false,
+            has_push0: false,
+            has_transient_storage: false,
+            has_mcopy: false,
+            has_modexp: false,
+            modexp_eip2565: false,
+            has_bn128: false,
+            has_blake2f: false,
+            gas_bn128_add: 0,
+            gas_bn128_mul: 0,
+            gas_bn128_pairing_base: 0,
+            gas_bn128_pairing_point: 0,
         }
     }
 
@@ -260,8 +415,19 @@ impl Config {
             gas_transaction_call: 21000,
             gas_transaction_zero_data: 4,
             gas_transaction_non_zero_data: 16,
+            gas_initcode_word: 0,
+            gas_access_list_address: 0,
+            gas_access_list_storage_key: 0,
+            gas_account_access_cold: 0,
+            gas_storage_read_warm: 0,
+            gas_sload_cold: 0,
             sstore_gas_metering: true,
             sstore_revert_under_stipend: true,
+            increase_state_access_gas: false,
+            max_refund_quotient: 2,
+            has_suicide_refund: true,
+            disallow_executable_format: false,
+            warm_coinbase_address: false,
             err_on_call_with_more_gas: false,
             empty_considered_exists: false,
             create_increase_nonce: true,
@@ -270,6 +436,7 @@ impl Config {
             memory_limit: usize::max_value(),
             call_stack_limit: 1024,
             create_contract_limit: Some(0x6000),
+            max_initcode_size: None,
             call_stipend: 2300,
             has_delegate_call: true,
             has_create2: true,
@@ -279,6 +446,117 @@ impl Config {
             has_chain_id: true,
             has_self_balance: true,
             has_ext_code_hash: true,
+            has_base_fee: false,
+            has_prevrandao: false,
+            has_push0: false,
+            has_transient_storage: false,
+            has_mcopy: false,
+            has_modexp: true,
+            modexp_eip2565: false,
+            has_bn128: true,
+            has_blake2f: true,
+            gas_bn128_add: 150,
+            gas_bn128_mul: 6000,
+            gas_bn128_pairing_base: 45000,
+            gas_bn128_pairing_point: 34000,
+        }
+    }
+
+    /// Berlin hard fork configuration.
+    pub const fn berlin() -> Config {
+        Config {
+            gas_ext_code: 0,
+            gas_ext_code_hash: 0,
+            gas_balance: 0,
+            gas_sload: 100,
+            gas_sstore_set: 20000,
+            gas_sstore_reset: 2900,
+            refund_sstore_clears: 15000,
+            gas_suicide: 5000,
+            gas_suicide_new_account: 25000,
+            gas_call: 0,
+            gas_expbyte: 50,
+            gas_transaction_create: 53000,
+            gas_transaction_call: 21000,
+            gas_transaction_zero_data: 4,
+            gas_transaction_non_zero_data: 16,
+            gas_initcode_word: 0,
+            gas_access_list_address: 2400,
+            gas_access_list_storage_key: 1900,
+            gas_account_access_cold: 2600,
+            gas_storage_read_warm: 100,
+            gas_sload_cold: 2100,
+            sstore_gas_metering: true,
+            sstore_revert_under_stipend: true,
+            increase_state_access_gas: true,
+            max_refund_quotient: 2,
+            has_suicide_refund: true,
+            disallow_executable_format: false,
+            warm_coinbase_address: false,
+            err_on_call_with_more_gas: false,
+            empty_considered_exists: false,
+            create_increase_nonce: true,
+            call_l64_after_gas: true,
+            stack_limit: 1024,
+            memory_limit: usize::max_value(),
+            call_stack_limit: 1024,
+            create_contract_limit: Some(0x6000),
+            max_initcode_size: None,
+            call_stipend: 2300,
+            has_delegate_call: true,
+            has_create2: true,
+            has_revert: true,
+            has_return_data: true,
+            has_bitwise_shifting: true,
+            has_chain_id: true,
+            has_self_balance: true,
+            has_ext_code_hash: true,
+            has_base_fee: false,
+            has_prevrandao: false,
+            has_push0: false,
+            has_transient_storage: false,
+            has_mcopy: false,
+            has_modexp: true,
+            modexp_eip2565: true,
+            has_bn128: true,
+            has_blake2f: true,
+            gas_bn128_add: 150,
+            gas_bn128_mul: 6000,
+            gas_bn128_pairing_base: 45000,
+            gas_bn128_pairing_point: 34000,
+        }
+    }
+
+    /// London hard fork configuration.
+    pub const fn london() -> Config {
+        Config {
+            refund_sstore_clears: 4800,
+            max_refund_quotient: 5,
+            has_suicide_refund: false,
+            disallow_executable_format: true,
+            has_base_fee: true,
+            ..Config::berlin()
+        }
+    }
+
+    /// Shanghai hard fork configuration.
+    pub const fn shanghai() -> Config {
+        Config {
+            gas_initcode_word: 2,
+            warm_coinbase_address: true,
+            max_initcode_size: Some(0xc000),
+            has_prevrandao: true,
+            has_push0: true,
+            ..Config::london()
+        }
+    }
+
+    /// Cancun hard fork configuration.
+    pub const fn cancun() -> Config {
+        Config {
+            has_transient_storage: true,
+            has_mcopy: true,
+            ..Config::shanghai()
         }
     }
 }
@@ -2,10 +2,14 @@
 use super::shared::*;
 use actix::prelude::*;
 use evm::{
-    backend::*, executor::StackExecutor, provider::localprovider::Provider, Config, Handler,
+    backend::*,
+    executor::{Cheatcodes, StackExecutor},
+    provider::{cache::RpcCache, localprovider::Provider},
+    Config, Handler,
 };
 use parity_crypto::publickey::public_to_address;
 use std::collections::BTreeMap;
+use std::sync::Arc;
 use crate::shared::Action;
 use web3::types::*;
 
@@ -13,6 +17,9 @@ use web3::types::*;
 pub struct EVMService {
     pub config: Config,
     pub backend: ForkMemoryBackendOwned, // pub exec: StackExecutorOwned<ForkMemoryBackendOwned>
+    pub artifacts: Arc<BTreeMap<String, Vec<u8>>>,
+    pub labels: BTreeMap<H160, String>,
+    pub cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
 }
 
 impl Actor for EVMService {
@@ -24,25 +31,59 @@ impl Actor for EVMService {
 }
 
 impl EVMService {
-    pub fn new(provider: &str) -> Self {
-        let p = Provider::new(provider.to_string());
-        let block = p.get_block();
+    /// Service forking the chain behind the `provider` url at its latest
+    /// block.
+    pub fn new(provider: &str) -> Result<Self, String> {
+        let p = Provider::new(provider.to_string()).map_err(|e| e.to_string())?;
+        let block = p
+            .get_block()
+            .map_err(|e| format!("couldn't fetch the latest block: {}", e))?;
+        let block_number = block
+            .number
+            .ok_or("provider didn't give a block number. Is it working correctly?")?;
         let vicinity = MemoryVicinity {
             gas_price: U256::from(5),
             origin: H160::random(),
             chain_id: U256::from(1337),
             block_hashes: Vec::new(),
-            block_number: U256::from(block.number.expect("Provider didn't give a block number. Is it working correctly?").as_u64()),
+            block_number: U256::from(block_number.as_u64()),
             block_coinbase: H160::random(),
             block_timestamp: block.timestamp,
             block_difficulty: U256::zero(),
             block_gas_limit: U256::from(12500000000000i128),
+            block_base_fee_per_gas: U256::zero(),
+            block_randomness: None,
         };
         let state: BTreeMap<H160, MemoryAccount> = BTreeMap::new();
-        let backend = ForkMemoryBackendOwned::new(vicinity, state, provider.to_string());
-        let mut config = Config::istanbul();
+        // CEVM_RPC_CACHE points at a directory to persist forked state in;
+        // CEVM_OFFLINE=1 refuses any read that is not already cached.
+        let backend = match std::env::var("CEVM_RPC_CACHE") {
+            Ok(dir) => {
+                let offline = std::env::var("CEVM_OFFLINE").map(|v| v == "1").unwrap_or(false);
+                let chain_id = p
+                    .get_chain_id()
+                    .map_err(|e| format!("couldn't fetch the chain id: {}", e))?;
+                let cache = RpcCache::open(dir, chain_id, offline)
+                    .map_err(|e| format!("couldn't open rpc cache: {}", e))?;
+                ForkMemoryBackendOwned::new_with_cache(vicinity, state, provider.to_string(), cache)
+            }
+            Err(_) => ForkMemoryBackendOwned::new(vicinity, state, provider.to_string()),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok(Self::with_backend(backend))
+    }
+
+    /// Service running on `backend` with the default config and cheatcodes.
+    pub fn with_backend(backend: ForkMemoryBackendOwned) -> Self {
+        let mut config = Config::cancun();
         config.create_contract_limit = None;
-        Self { config, backend }
+        Self {
+            config,
+            backend,
+            artifacts: Arc::new(BTreeMap::new()),
+            labels: BTreeMap::new(),
+            cheatcodes: Arc::new(Cheatcodes::standard()),
+        }
     }
 
     pub fn update_vicinity_for_tx(&mut self, msg: &EthRequest, sim: &Option<Transaction>) {
@@ -64,6 +105,14 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
     type Result = EthResponse;
 
     fn handle(&mut self, msg: EthRequest, _ctx: &mut SyncContext<Self>) -> Self::Result {
+        self.execute(msg)
+    }
+}
+
+impl EVMService {
+    /// Run `msg` against the forked state, committing its changes unless it
+    /// failed or asked for `no_commit`.
+    pub fn execute(&mut self, msg: EthRequest) -> EthResponse {
         // store backup of current state
         let mut reset = true;
         let timestamp = self.backend.vicinity.block_timestamp;
@@ -74,11 +123,14 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
         match msg {
             EthRequest::eth_sim(ref tx_hash, ref _in_place, ref _opts) => {
                 sim_tx = Some(self.backend.tx(*tx_hash));
-                let full_block = self
+                match self
                     .backend
                     .provider
-                    .get_block_by_number(self.backend.vicinity.block_number);
-                self.backend.vicinity.block_timestamp = full_block.timestamp;
+                    .get_block_by_number(self.backend.vicinity.block_number)
+                {
+                    Ok(full_block) => self.backend.vicinity.block_timestamp = full_block.timestamp,
+                    Err(e) => eprintln!("eth_sim: {}", e),
+                }
             }
             _ => {}
         }
@@ -92,6 +144,9 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
             self.backend.vicinity.block_gas_limit.clone().as_usize(),
             &self.config,
         );
+        exec.artifacts = self.artifacts.clone();
+        exec.labels = self.labels.clone();
+        exec.cheatcodes = self.cheatcodes.clone();
 
         // default to committing
         let mut commit = true;
@@ -145,6 +200,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
 
                 let data;
                 let trace;
+                let error;
                 match action {
                     Action::Call(_) => {
                         let (succ, tx_data, tx_trace) = exec.transact_call(
@@ -154,6 +210,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                             tx.value.unwrap_or(U256::zero()),
                             uv_tx.unsigned.data,
                             tx.gas.unwrap_or(self.backend.vicinity.block_gas_limit).as_usize(),
+                            Vec::new(),
                         );
                         match succ {
                             evm::ExitReason::Succeed(_) => {}
@@ -161,6 +218,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                                 commit = false;
                             }
                         }
+                        error = fatal_error(&self.backend, &succ);
                         data = tx_data;
                         trace = tx_trace;
                     }
@@ -171,6 +229,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                             tx.value.unwrap_or(U256::zero()), // value: 0 eth
                             uv_tx.unsigned.data,              // data
                             tx.gas.unwrap_or(self.backend.vicinity.block_gas_limit).as_usize(),       // gas_limit
+                            Vec::new(),
                         );
                         match succ {
                             evm::ExitReason::Succeed(_) => {}
@@ -178,6 +237,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                                 commit = false;
                             }
                         }
+                        error = fatal_error(&self.backend, &succ);
                         data = tx_data.unwrap_or(H160::zero()).as_bytes().to_vec();
                         trace = tx_trace;
                     }
@@ -189,6 +249,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                     logs: None,
                     recs: None,
                     trace: None,
+                    error,
                 };
 
                 let (tx_data, tx_logs, tx_rec, tx_trace) = match re {
@@ -198,6 +259,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                         ref mut logs,
                         ref mut recs,
                         trace: ref mut tx_trace,
+                        error: _,
                     } => (tx_data, logs, recs, tx_trace),
                     _ => unreachable!(),
                 };
@@ -206,7 +268,6 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                 let mut with_return = false;
                 let mut with_receipt = false;
                 let mut with_trace = false;
-                println!("options: {:?}", options);
                 if let Some(opts) = options {
                     for option in opts.into_iter() {
                         match &*option {
@@ -256,6 +317,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                             tx.value,          // value: 0 eth
                             tx.data.clone(),   // data
                             tx.gas.as_usize(), // gas_limit
+                            Vec::new(),
                         );
                         match succ {
                             evm::ExitReason::Succeed(_) => {}
@@ -272,6 +334,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                             tx.value,
                             tx.data.clone(),
                             tx.gas.as_usize(),
+                            Vec::new(),
                         );
                         match succ {
                             evm::ExitReason::Succeed(_) => {}
@@ -300,6 +363,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                             tx.gas
                                 .unwrap_or(self.backend.vicinity.block_gas_limit)
                                 .as_usize(),
+                            Vec::new(),
                         );
                         data = tx_data;
                     }
@@ -312,6 +376,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                             tx.gas
                                 .unwrap_or(self.backend.vicinity.block_gas_limit)
                                 .as_usize(), // gas_limit
+                            Vec::new(),
                         );
                         data = tx_data.expect("Call: Create tx did not return a created address").as_bytes().to_vec();
                     }
@@ -332,6 +397,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                     tx.value.unwrap_or(U256::zero()), // value: 0 eth
                     uv_tx.unsigned.data,             // data
                     tx.gas.unwrap_or(self.backend.vicinity.block_gas_limit).as_usize(),       // gas_limit
+                    Vec::new(),
                 );
                 let addr = tx_data.expect("Temporary Deployment: Did not return a created address");
                 data = exec.code(addr);
@@ -344,28 +410,41 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                 if bn > self.backend.vicinity.block_number {
                     tmp_bn = self.backend.vicinity.block_number;
                 }
-                if txs {
-                    let mut b = self.backend.provider.get_block_by_number_txs(tmp_bn);
-                    b.number = Some(web3::types::U64::from(bn.as_u64()));
-                    EthResponse::eth_getBlock(None, Some(b))
+                let res = if txs {
+                    self.backend
+                        .provider
+                        .get_block_by_number_txs(tmp_bn)
+                        .map(|mut b| {
+                            b.number = Some(web3::types::U64::from(bn.as_u64()));
+                            EthResponse::eth_getBlock(None, Some(b))
+                        })
                 } else {
-                    let mut b = self.backend.provider.get_block_by_number(tmp_bn);
-                    b.number = Some(web3::types::U64::from(bn.as_u64()));
-                    EthResponse::eth_getBlock(Some(b), None)
-                }
+                    self.backend.provider.get_block_by_number(tmp_bn).map(|mut b| {
+                        b.number = Some(web3::types::U64::from(bn.as_u64()));
+                        EthResponse::eth_getBlock(Some(b), None)
+                    })
+                };
+                res.unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    EthResponse::eth_unimplemented
+                })
             }
             EthRequest::eth_getBlockByHash(bh, txs) => {
-                if txs {
-                    EthResponse::eth_getBlock(
-                        None,
-                        Some(self.backend.provider.get_block_by_hash_txs(bh)),
-                    )
+                let res = if txs {
+                    self.backend
+                        .provider
+                        .get_block_by_hash_txs(bh)
+                        .map(|b| EthResponse::eth_getBlock(None, Some(b)))
                 } else {
-                    EthResponse::eth_getBlock(
-                        Some(self.backend.provider.get_block_by_hash(bh)),
-                        None,
-                    )
-                }
+                    self.backend
+                        .provider
+                        .get_block_by_hash(bh)
+                        .map(|b| EthResponse::eth_getBlock(Some(b), None))
+                };
+                res.unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    EthResponse::eth_unimplemented
+                })
             }
             EthRequest::eth_chainId => EthResponse::eth_chainId(self.backend.vicinity.chain_id),
             EthRequest::eth_getTransactionReceipt(hash) => {
@@ -382,26 +461,31 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
 
                 let data;
                 let trace;
+                let error;
                 let Bytes(raw) = tx.input;
                 if tx.to != None {
-                    let (_tx_rec, tx_data, tx_trace) = exec.transact_call(
+                    let (tx_rec, tx_data, tx_trace) = exec.transact_call(
                         tx.hash,
                         tx.from,
                         tx.to.expect("Sim: tx.to defined, but can't unwrap"),
                         tx.value,
                         raw,
                         tx.gas.as_usize(),
+                        Vec::new(),
                     );
+                    error = fatal_error(&self.backend, &tx_rec);
                     data = tx_data;
                     trace = tx_trace;
                 } else {
-                    let (_tx_rec, tx_data, tx_trace) = exec.transact_create(
+                    let (tx_rec, tx_data, tx_trace) = exec.transact_create(
                         tx.hash,
                         tx.from,
                         tx.value,          // value: 0 eth
                         raw,               // data
                         tx.gas.as_usize(), // gas_limit
+                        Vec::new(),
                     );
+                    error = fatal_error(&self.backend, &tx_rec);
                     data = tx_data.unwrap_or(H160::zero()).as_bytes().to_vec();
                     trace = tx_trace;
                 }
@@ -412,6 +496,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                     logs: None,
                     recs: None,
                     trace: None,
+                    error,
                 };
 
                 let (tx_data, tx_logs, tx_rec, tx_trace) = match re {
@@ -421,6 +506,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                         ref mut logs,
                         ref mut recs,
                         trace: ref mut tx_trace,
+                        error: _,
                     } => (tx_data, logs, recs, tx_trace),
                     _ => unreachable!(),
                 };
@@ -429,7 +515,6 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                 let mut with_return = false;
                 let mut with_receipt = false;
                 let mut with_trace = false;
-                println!("options: {:?}", options);
                 if let Some(opts) = options {
                     for option in opts.into_iter() {
                         match &*option {
@@ -479,6 +564,10 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
                 new_ts = time;
                 EthResponse::eth_unimplemented
             }
+            EthRequest::set_artifacts(artifacts) => {
+                self.artifacts = Arc::new(artifacts);
+                EthResponse::eth_unimplemented
+            }
             _ => {
                 println!("!implemented");
                 EthResponse::eth_unimplemented
@@ -487,6 +576,7 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
 
         // if we are committing to backend, deconstruct, else, only keep fork info
         if commit {
+            self.labels = std::mem::take(&mut exec.labels);
             let (applies, logs, recs, created) = exec.deconstruct();
             self.backend.apply(
                 self.backend.vicinity.block_number,
@@ -530,3 +620,66 @@ impl actix::prelude::Handler<EthRequest> for EVMService {
         to_send
     }
 }
+
+/// Why a transaction that exited with `reason` stopped, if it hit a fatal
+/// error.
+fn fatal_error(backend: &ForkMemoryBackendOwned, reason: &evm::ExitReason) -> Option<String> {
+    match reason {
+        evm::ExitReason::Fatal(e) => Some(match backend.error() {
+            Some(error) => error.to_string(),
+            None => format!("{:?}", e),
+        }),
+        _ => None,
+    }
+}
+
+/// Request to write the fork RPC cache to disk.
+#[derive(Message)]
+#[rtype(result = "std::io::Result<()>")]
+pub struct Flush;
+
+impl actix::prelude::Handler<Flush> for EVMService {
+    type Result = std::io::Result<()>;
+
+    fn handle(&mut self, _msg: Flush, _ctx: &mut SyncContext<Self>) -> Self::Result {
+        self.backend.cache.flush()
+    }
+}
+
+/// Request for a copy of the service's state, to run requests on in isolation.
+#[derive(Message)]
+#[rtype(result = "EVMSnapshot")]
+pub struct Snapshot;
+
+/// Copy of an `EVMService`, sharing its fork RPC cache.
+#[derive(MessageResponse)]
+pub struct EVMSnapshot(pub EVMService);
+
+impl actix::prelude::Handler<Snapshot> for EVMService {
+    type Result = EVMSnapshot;
+
+    fn handle(&mut self, _msg: Snapshot, _ctx: &mut SyncContext<Self>) -> Self::Result {
+        EVMSnapshot(self.clone())
+    }
+}
+
+/// Request run on its own copy of a snapshot, leaving the snapshot untouched.
+#[derive(Message)]
+#[rtype(result = "EthResponse")]
+pub struct Isolated(pub EVMService, pub EthRequest);
+
+/// Worker of a pool running `Isolated` requests in parallel.
+pub struct EVMWorker;
+
+impl Actor for EVMWorker {
+    type Context = SyncContext<Self>;
+}
+
+impl actix::prelude::Handler<Isolated> for EVMWorker {
+    type Result = EthResponse;
+
+    fn handle(&mut self, msg: Isolated, _ctx: &mut SyncContext<Self>) -> Self::Result {
+        let Isolated(mut service, request) = msg;
+        service.execute(request)
+    }
+}
@@ -16,7 +16,12 @@ extern crate serde_json;
 async fn main() {
     let provider = "https://fee7372b6e224441b747bf1fde15b2bd.eth.rpc.rivet.cloud/";
     // let provider = "http://localhost:8855";
-    let evm_addr = SyncArbiter::start(1, move || EVMService::new(provider));
+    let evm_addr = SyncArbiter::start(1, move || {
+        EVMService::new(provider).unwrap_or_else(|e| {
+            eprintln!("couldn't fork {}: {}", provider, e);
+            std::process::exit(1)
+        })
+    });
     let _api = Api {
         evm: evm_addr.recipient(),
     }
@@ -9,6 +9,7 @@ use web3::types::{TransactionReceipt, TransactionRequest};
 use hash::keccak;
 use parity_crypto::publickey::{recover, Public, Signature, public_to_address};
 use rlp::{self, DecoderError, Encodable, Rlp, RlpStream};
+use std::collections::BTreeMap;
 use std::ops::Deref;
 use web3::types::*;
 
@@ -95,6 +96,7 @@ pub enum EthRequest {
     eth_sim(H256, bool, Option<Vec<String>>),
     set_block(U256),
     set_timestamp(U256),
+    set_artifacts(BTreeMap<String, Vec<u8>>),
 }
 
 impl EthRequest {
@@ -208,6 +210,9 @@ pub enum EthResponse {
         logs: Option<Vec<evm::backend::Log>>,
         recs: Option<Vec<TxReceipt>>,
         trace: Option<Vec<CallTrace>>,
+        /// Fatal error the transaction stopped on, such as the fork provider
+        /// failing, in which case its results are meaningless.
+        error: Option<String>,
     },
     eth_getLogs(Vec<web3::types::Log>),
     eth_unimplemented,
@@ -265,6 +270,7 @@ impl EthResponse {
                 logs: _,
                 recs: _,
                 trace: _,
+                error: _,
             } => Some(hash),
             _ => None,
         }
@@ -309,6 +315,7 @@ impl EthResponse {
                 logs: _,
                 recs,
                 trace: _,
+                error: _,
             } => recs,
             _ => None,
         }
@@ -321,6 +328,7 @@ impl EthResponse {
                 logs,
                 recs: _,
                 trace: _,
+                error: _,
             } => logs,
             _ => None,
         }
@@ -339,6 +347,7 @@ impl EthResponse {
                 logs: _,
                 recs: _,
                 trace,
+                error: _,
             } => trace,
             _ => None,
         }
@@ -351,6 +360,7 @@ impl EthResponse {
                 logs: _,
                 recs: _,
                 trace: _,
+                error: _,
             } => data,
             _ => None,
         }
@@ -371,6 +381,7 @@ impl EthResponse {
                 logs,
                 recs,
                 trace,
+                error: _,
             } => Some((hash, data, logs, recs, trace)),
             _ => None,
         }
@@ -391,6 +402,7 @@ impl EthResponse {
                 logs,
                 recs,
                 trace,
+                error: _,
             } => Some((data, logs, recs, trace)),
             _ => None,
         }
@@ -0,0 +1,94 @@
+//! Fixtures shared by the service tests.
+#![allow(dead_code)]
+
+use evm::backend::{ForkMemoryBackendOwned, MemoryVicinity};
+use serde_json::{json, Value};
+use service::EVM::EVMService;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use web3::types::{H160, U256};
+
+/// Answer to `method` on a chain where every account is empty.
+fn answer(method: &str) -> Value {
+    match method {
+        "eth_getBalance" | "eth_getTransactionCount" => json!({ "result": "0x0" }),
+        "eth_getCode" => json!({ "result": "0x" }),
+        "eth_getStorageAt" => json!({ "result": format!("0x{}", "00".repeat(32)) }),
+        _ => json!({ "error": { "code": -32601, "message": "method not found" } }),
+    }
+}
+
+/// Serve JSON-RPC for an empty chain over HTTP/1.1 on a local port, one
+/// request per connection, and return its url.
+pub fn mock_node() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                let lower = line.to_ascii_lowercase();
+                if let Some(v) = lower.strip_prefix("content-length:") {
+                    len = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            let respond = |call: &Value| {
+                let mut res = answer(call["method"].as_str().unwrap());
+                res["id"] = call["id"].clone();
+                res["jsonrpc"] = json!("2.0");
+                res
+            };
+            let response = match body {
+                Value::Array(calls) => Value::Array(calls.iter().map(respond).collect()),
+                call => respond(&call),
+            };
+
+            let out = serde_json::to_vec(&response).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                out.len()
+            )
+            .unwrap();
+            stream.write_all(&out).unwrap();
+        }
+    });
+    url
+}
+
+/// Vicinity of a block at height zero on chain 1.
+pub fn vicinity() -> MemoryVicinity {
+    MemoryVicinity {
+        gas_price: U256::zero(),
+        origin: H160::zero(),
+        chain_id: U256::one(),
+        block_hashes: Vec::new(),
+        block_number: U256::zero(),
+        block_coinbase: H160::zero(),
+        block_timestamp: U256::zero(),
+        block_difficulty: U256::zero(),
+        block_gas_limit: U256::from(10_000_000),
+        block_base_fee_per_gas: U256::zero(),
+        block_randomness: None,
+    }
+}
+
+/// Service forking the empty chain of a fresh `mock_node`.
+pub fn service() -> EVMService {
+    EVMService::with_backend(
+        ForkMemoryBackendOwned::new(vicinity(), BTreeMap::new(), mock_node()).unwrap(),
+    )
+}
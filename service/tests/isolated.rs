@@ -0,0 +1,61 @@
+mod common;
+
+use actix::prelude::*;
+use service::shared::{EthRequest, EthResponse};
+use service::EVM::{EVMWorker, Isolated};
+use web3::types::{Bytes, TransactionRequest, H160, U256};
+
+// Runtime: PUSH1 0 SLOAD PUSH1 1 ADD DUP1 PUSH1 0 SSTORE PUSH1 0 MSTORE
+// PUSH1 0x20 PUSH1 0 RETURN, returning the incremented counter.
+// Init: PUSH1 0x12 PUSH1 0x0c PUSH1 0 CODECOPY PUSH1 0x12 PUSH1 0 RETURN
+const COUNTER: &str = "6012600c60003960126000f36000546001018060005560005260206000f3";
+
+fn transaction(to: Option<H160>, data: Vec<u8>) -> EthRequest {
+    EthRequest::eth_sendTransaction(
+        TransactionRequest {
+            from: H160::from_low_u64_be(0x1000),
+            to,
+            gas: Some(U256::from(1_000_000)),
+            gas_price: Some(U256::from(1)),
+            data: Some(Bytes(data)),
+            value: None,
+            nonce: None,
+            condition: None,
+        },
+        Some(vec!["return".to_string()]),
+    )
+}
+
+fn returned(response: EthResponse) -> Vec<u8> {
+    match response {
+        EthResponse::eth_sendTransaction {
+            data: Some(data),
+            error: None,
+            ..
+        } => data,
+        _ => panic!("expected return data"),
+    }
+}
+
+#[test]
+fn isolated_requests_do_not_see_each_others_state() {
+    let mut service = common::service();
+    let counter = H160::from_slice(&returned(
+        service.execute(transaction(None, hex::decode(COUNTER).unwrap())),
+    ));
+
+    let mut system = System::new("isolated");
+    let counts = system.block_on(async move {
+        let workers = SyncArbiter::start(2, || EVMWorker);
+        let mut counts = Vec::new();
+        for _ in 0..2 {
+            let request = transaction(Some(counter), Vec::new());
+            let response = workers.send(Isolated(service.clone(), request)).await;
+            counts.push(U256::from_big_endian(&returned(response.unwrap())));
+        }
+        counts
+    });
+
+    // Both runs start from the deployed counter, even though each committed.
+    assert_eq!(counts, vec![U256::one(), U256::one()]);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use service::shared::{EthRequest, EthResponse};
+use web3::types::{Bytes, TransactionRequest, H160, H256, U256};
+
+// PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+const DEPLOY_2A: &str = "602a5f5260205ff3";
+
+#[test]
+fn deploys_push0_bytecode() {
+    let mut service = common::service();
+    let tx = TransactionRequest {
+        from: H160::from_low_u64_be(0x1000),
+        to: None,
+        gas: Some(U256::from(1_000_000)),
+        gas_price: Some(U256::from(1)),
+        data: Some(Bytes(hex::decode(DEPLOY_2A).unwrap())),
+        value: None,
+        nonce: None,
+        condition: None,
+    };
+
+    let created = match service.execute(EthRequest::eth_sendTransaction(
+        tx,
+        Some(vec!["return".to_string()]),
+    )) {
+        EthResponse::eth_sendTransaction {
+            data: Some(data),
+            error: None,
+            ..
+        } => H160::from_slice(&data),
+        _ => panic!("expected a deployment"),
+    };
+    assert_ne!(created, H160::zero());
+
+    match service.execute(EthRequest::eth_getCode(created, None)) {
+        EthResponse::eth_getCode(code) => {
+            assert_eq!(code, H256::from_low_u64_be(0x2a).as_bytes().to_vec())
+        }
+        _ => panic!("expected code"),
+    }
+}
@@ -0,0 +1,57 @@
+use evm::backend::{ForkMemoryBackendOwned, MemoryVicinity};
+use evm::provider::cache::RpcCache;
+use service::shared::{EthRequest, EthResponse};
+use service::EVM::EVMService;
+use std::collections::BTreeMap;
+use web3::types::{H160, U256};
+
+#[test]
+fn fork_reads_are_written_when_the_service_stops() {
+    let dir = std::env::temp_dir().join("cevm-service-rpc-cache");
+    let _ = std::fs::remove_dir_all(&dir);
+    let address = H160::from_low_u64_be(0x1000);
+    let block = U256::from(100);
+    let vicinity = MemoryVicinity {
+        gas_price: U256::zero(),
+        origin: H160::zero(),
+        chain_id: U256::one(),
+        block_hashes: Vec::new(),
+        block_number: block,
+        block_coinbase: H160::zero(),
+        block_timestamp: U256::zero(),
+        block_difficulty: U256::zero(),
+        block_gas_limit: U256::from(10_000_000),
+        block_base_fee_per_gas: U256::zero(),
+        block_randomness: None,
+    };
+    // Nothing listens on this port; reads are seeded into the cache instead.
+    let backend = ForkMemoryBackendOwned::new_with_cache(
+        vicinity,
+        BTreeMap::new(),
+        "http://127.0.0.1:1".to_string(),
+        RpcCache::open(&dir, U256::one(), false).unwrap(),
+    )
+    .unwrap();
+    let mut service = EVMService::with_backend(backend);
+    service
+        .backend
+        .cache
+        .balance(block, address, || Ok(U256::from(7)))
+        .unwrap();
+
+    match service.execute(EthRequest::eth_blockNumber) {
+        EthResponse::eth_blockNumber(number) => assert_eq!(number, block),
+        _ => panic!("expected a block number"),
+    }
+
+    // Workers execute requests in parallel, so writing is left to `Flush`
+    // and the last copy of the service going away.
+    assert!(!dir.join("1.json").exists());
+    drop(service);
+
+    let cache = RpcCache::open(&dir, U256::one(), true).unwrap();
+    assert_eq!(
+        cache.balance(block, address, || unreachable!()),
+        Ok(U256::from(7))
+    );
+}
@@ -1,6 +1,7 @@
 use super::{Apply, ApplyBackend, Backend, Basic, Log, MemoryAccount, MemoryVicinity, TxReceipt};
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
+use core::cell::RefCell;
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 use std::collections::BTreeSet;
@@ -8,41 +9,79 @@ use std::ops::Bound::Included;
 // #[cfg(feature = "web")]
 // use crate::provider::webprovider::Provider;
 
+use crate::provider::cache::RpcCache;
 #[cfg(feature = "local")]
 use crate::provider::localprovider::Provider;
+use crate::provider::{ProviderError, StateProvider};
 use web3::types::Transaction;
 
-/// Memory backend with ability to fork another chain from an HTTP provider, storing all state values in a `BTreeMap` in memory.
+/// Memory backend with ability to fork another chain from a state provider, storing all state values in a `BTreeMap` in memory.
 #[derive(Clone, Debug)]
-pub struct ForkMemoryBackendOwned {
+pub struct ForkMemoryBackendOwned<P = Provider> {
     /// backend vicinity
     pub vicinity: MemoryVicinity,
     /// state
     pub state: BTreeMap<H160, MemoryAccount>,
     archive_state: BTreeMap<U256, BTreeMap<H160, MemoryAccount>>,
     logs: BTreeMap<U256, Vec<Log>>,
-    /// Web3 provider
-    pub provider: Provider,
+    /// Provider of the forked state
+    pub provider: P,
     /// Local block number
     pub local_block_num: U256,
     tx_history: BTreeMap<H256, TxReceipt>,
+    /// Cache of state read from the provider
+    pub cache: RpcCache,
+    /// Storage slots fetched along with their account
+    known_slots: BTreeMap<H160, BTreeSet<H256>>,
+    error: RefCell<Option<ProviderError>>,
 }
 
-impl ForkMemoryBackendOwned {
-    /// Create a new memory backend.
+impl ForkMemoryBackendOwned<Provider> {
+    /// Create a new memory backend forking the chain behind an HTTP provider url.
     pub fn new(
         vicinity: MemoryVicinity,
         state: BTreeMap<H160, MemoryAccount>,
         provider: String,
+    ) -> Result<Self, ProviderError> {
+        Ok(Self::new_with_provider(
+            vicinity,
+            state,
+            Provider::new(provider)?,
+        ))
+    }
+
+    /// Create a new memory backend whose provider reads go through `cache`.
+    pub fn new_with_cache(
+        vicinity: MemoryVicinity,
+        state: BTreeMap<H160, MemoryAccount>,
+        provider: String,
+        cache: RpcCache,
+    ) -> Result<Self, ProviderError> {
+        Ok(Self {
+            cache,
+            ..Self::new(vicinity, state, provider)?
+        })
+    }
+}
+
+impl<P: StateProvider> ForkMemoryBackendOwned<P> {
+    /// Create a new memory backend forking the chain behind `provider`.
+    pub fn new_with_provider(
+        vicinity: MemoryVicinity,
+        state: BTreeMap<H160, MemoryAccount>,
+        provider: P,
     ) -> Self {
         Self {
             vicinity: vicinity.clone(),
             state,
             archive_state: BTreeMap::new(),
             logs: BTreeMap::new(),
-            provider: Provider::new(provider),
+            provider,
             local_block_num: vicinity.block_number,
             tx_history: BTreeMap::new(),
+            cache: RpcCache::in_memory(vicinity.chain_id),
+            known_slots: BTreeMap::new(),
+            error: RefCell::new(None),
         }
     }
 
@@ -50,9 +89,75 @@ impl ForkMemoryBackendOwned {
     pub fn state(&self) -> &BTreeMap<H160, MemoryAccount> {
         &self.state
     }
+
+    /// Unwrap a provider result, keeping the first error for `Backend::error`
+    /// and reading a default value in its place.
+    fn record<T: Default>(&self, res: Result<T, ProviderError>) -> T {
+        res.unwrap_or_else(|e| {
+            self.error.borrow_mut().get_or_insert(e);
+            T::default()
+        })
+    }
+
+    /// Storage slots of `address` to load together with its balance, nonce
+    /// and code the first time the account is touched.
+    pub fn prefetch_slots<I: IntoIterator<Item = H256>>(&mut self, address: H160, slots: I) {
+        self.known_slots.entry(address).or_default().extend(slots);
+    }
+
+    /// Load a whole account in one provider round trip unless it is cached.
+    fn fetch_account(&self, address: H160) {
+        let block = self.vicinity.block_number;
+        if self.cache.is_offline() || self.cache.has_account(block, address) {
+            return;
+        }
+        let slots: Vec<H256> = self
+            .known_slots
+            .get(&address)
+            .map(|s| s.iter().cloned().collect())
+            .unwrap_or_default();
+        if let Some(account) = self.record(
+            self.provider
+                .get_account(address, &slots, Some(block))
+                .map(Some),
+        ) {
+            self.cache.insert_account(block, address, account);
+        }
+    }
+
+    fn fetch_balance(&self, address: H160) -> U256 {
+        let block = self.vicinity.block_number;
+        self.fetch_account(address);
+        self.record(self.cache.balance(block, address, || {
+            self.provider.get_balance(address, Some(block))
+        }))
+    }
+
+    fn fetch_nonce(&self, address: H160) -> U256 {
+        let block = self.vicinity.block_number;
+        self.fetch_account(address);
+        self.record(self.cache.nonce(block, address, || {
+            self.provider.get_transaction_count(address, Some(block))
+        }))
+    }
+
+    fn fetch_code(&self, address: H160) -> Vec<u8> {
+        let block = self.vicinity.block_number;
+        self.fetch_account(address);
+        self.record(self.cache.code(block, address, || {
+            self.provider.get_code(address, Some(block))
+        }))
+    }
+
+    fn fetch_storage(&self, address: H160, index: H256) -> H256 {
+        let block = self.vicinity.block_number;
+        self.record(self.cache.storage(block, address, index, || {
+            self.provider.get_storage_at(address, index, Some(block))
+        }))
+    }
 }
 
-impl Backend for ForkMemoryBackendOwned {
+impl<P: StateProvider> Backend for ForkMemoryBackendOwned<P> {
     fn gas_price(&self) -> U256 {
         self.vicinity.gas_price
     }
@@ -85,6 +190,12 @@ impl Backend for ForkMemoryBackendOwned {
     fn block_gas_limit(&self) -> U256 {
         self.vicinity.block_gas_limit
     }
+    fn block_base_fee_per_gas(&self) -> U256 {
+        self.vicinity.block_base_fee_per_gas
+    }
+    fn block_randomness(&self) -> Option<H256> {
+        self.vicinity.block_randomness
+    }
 
     fn chain_id(&self) -> U256 {
         self.vicinity.chain_id
@@ -92,20 +203,9 @@ impl Backend for ForkMemoryBackendOwned {
 
     fn exists(&self, address: H160) -> bool {
         self.state.contains_key(&address)
-            || ((self
-                .provider
-                .get_balance(address, Some(self.vicinity.block_number))
-                != U256::default())
-                || (self
-                    .provider
-                    .get_transaction_count(address, Some(self.vicinity.block_number))
-                    != U256::default())
-                || (self
-                    .provider
-                    .get_code(address, Some(self.vicinity.block_number))
-                    .as_ref()
-                    .to_vec()
-                    != Vec::<u8>::new()))
+            || self.fetch_balance(address) != U256::default()
+            || self.fetch_nonce(address) != U256::default()
+            || !self.fetch_code(address).is_empty()
     }
 
     fn basic(&self, address: H160) -> Basic {
@@ -116,12 +216,8 @@ impl Backend for ForkMemoryBackendOwned {
                 nonce: a.nonce,
             })
             .unwrap_or_else(|| Basic {
-                balance: self
-                    .provider
-                    .get_balance(address, Some(self.vicinity.block_number)),
-                nonce: self
-                    .provider
-                    .get_transaction_count(address, Some(self.vicinity.block_number)),
+                balance: self.fetch_balance(address),
+                nonce: self.fetch_nonce(address),
             })
     }
 
@@ -130,10 +226,7 @@ impl Backend for ForkMemoryBackendOwned {
             .get(&address)
             .map(|v| H256::from_slice(Keccak256::digest(&v.code).as_slice()))
             .unwrap_or_else(|| {
-                let code = self
-                    .provider
-                    .get_code(address, Some(self.vicinity.block_number));
-                H256::from_slice(Keccak256::digest(&code.as_ref().to_vec()).as_slice())
+                H256::from_slice(Keccak256::digest(&self.fetch_code(address)).as_slice())
             })
     }
 
@@ -141,24 +234,14 @@ impl Backend for ForkMemoryBackendOwned {
         self.state
             .get(&address)
             .map(|v| v.code.len())
-            .unwrap_or_else(|| {
-                let code = self
-                    .provider
-                    .get_code(address, Some(self.vicinity.block_number));
-                code.as_ref().to_vec().len()
-            })
+            .unwrap_or_else(|| self.fetch_code(address).len())
     }
 
     fn code(&self, address: H160) -> Vec<u8> {
         self.state
             .get(&address)
             .map(|v| v.code.clone())
-            .unwrap_or_else(|| {
-                self.provider
-                    .get_code(address, Some(self.vicinity.block_number))
-                    .as_ref()
-                    .to_vec()
-            })
+            .unwrap_or_else(|| self.fetch_code(address))
     }
 
     fn storage(&self, address: H160, index: H256) -> H256 {
@@ -166,14 +249,12 @@ impl Backend for ForkMemoryBackendOwned {
             if let Some(store_data) = acct.storage.get(&index) {
                 *store_data
             } else if !acct.created {
-                self.provider
-                    .get_storage_at(address, index, Some(self.vicinity.block_number))
+                self.fetch_storage(address, index)
             } else {
                 H256::default()
             }
         } else {
-            self.provider
-                .get_storage_at(address, index, Some(self.vicinity.block_number))
+            self.fetch_storage(address, index)
         }
     }
 
@@ -181,7 +262,7 @@ impl Backend for ForkMemoryBackendOwned {
         if let Some(txrec) = self.tx_history.get(&hash) {
             txrec.clone()
         } else {
-            self.provider.get_transaction_receipt(hash)
+            self.record(self.provider.get_transaction_receipt(hash))
         }
     }
 
@@ -243,16 +324,24 @@ impl Backend for ForkMemoryBackendOwned {
             logs.extend(parsed.clone());
         }
 
-        logs.extend(self.provider.get_logs(from_block, to_block, addr, topics));
+        logs.extend(self.record(self.provider.get_logs(from_block, to_block, addr, topics)));
         logs
     }
 
     fn tx(&self, hash: H256) -> Transaction {
-        self.provider.get_transaction(hash)
+        self.record(self.provider.get_transaction(hash))
+    }
+
+    fn error(&self) -> Option<ProviderError> {
+        self.error.borrow().clone()
+    }
+
+    fn take_error(&self) -> Option<ProviderError> {
+        self.error.borrow_mut().take()
     }
 }
 
-impl ApplyBackend for ForkMemoryBackendOwned {
+impl<P: StateProvider> ApplyBackend for ForkMemoryBackendOwned<P> {
     fn apply<A, I, L>(
         &mut self,
         block: U256,
@@ -56,6 +56,10 @@ pub struct MemoryVicinity {
     pub block_difficulty: U256,
     /// Environmental block gas limit.
     pub block_gas_limit: U256,
+    /// Environmental base fee per gas.
+    pub block_base_fee_per_gas: U256,
+    /// Environmental block randomness (`PREVRANDAO`).
+    pub block_randomness: Option<H256>,
 }
 
 /// Account information of a memory backend.
@@ -137,6 +141,12 @@ impl<'vicinity> Backend for MemoryBackend<'vicinity> {
     fn block_gas_limit(&self) -> U256 {
         self.vicinity.block_gas_limit
     }
+    fn block_base_fee_per_gas(&self) -> U256 {
+        self.vicinity.block_base_fee_per_gas
+    }
+    fn block_randomness(&self) -> Option<H256> {
+        self.vicinity.block_randomness
+    }
 
     fn chain_id(&self) -> U256 {
         self.vicinity.chain_id
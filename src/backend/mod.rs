@@ -9,6 +9,7 @@ pub use self::memory::{MemoryAccount, MemoryBackend, MemoryVicinity, TxReceipt};
 // pub use self::fork_memory::ForkMemoryBackend;
 pub use self::fork_memory_owned::ForkMemoryBackendOwned;
 
+use crate::provider::ProviderError;
 use alloc::vec::Vec;
 use primitive_types::{H160, H256, U256};
 use std::collections::BTreeSet;
@@ -77,6 +78,10 @@ pub trait Backend {
     fn block_difficulty(&self) -> U256;
     /// Environmental block gas limit.
     fn block_gas_limit(&self) -> U256;
+    /// Environmental base fee per gas.
+    fn block_base_fee_per_gas(&self) -> U256;
+    /// Environmental block randomness (`PREVRANDAO`).
+    fn block_randomness(&self) -> Option<H256>;
     /// Environmental chain ID.
     fn chain_id(&self) -> U256;
 
@@ -104,6 +109,16 @@ pub trait Backend {
         addr: Vec<H160>,
         topics: Vec<H256>,
     ) -> Vec<web3::types::Log>;
+
+    /// First error met reading from the backend's data source, if any. Reads
+    /// that fail return default values, so results are invalid once set.
+    fn error(&self) -> Option<ProviderError> {
+        None
+    }
+    /// Take and clear the read error.
+    fn take_error(&self) -> Option<ProviderError> {
+        None
+    }
 }
 
 /// EVM backend that can apply changes.
@@ -0,0 +1,681 @@
+use super::stack::{
+    ExpectedCall, ExpectedEmit, ExpectedRevert, Prank, RecordedAccesses, RevertData, StackExecutor,
+};
+use crate::backend::Backend;
+use crate::{Context, ExitReason, ExitRevert, Handler};
+use alloc::string::String;
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Address the cheatcodes are called at.
+pub const CHEATCODE_ADDRESS: H160 = H160([
+    0x71, 0x09, 0x70, 0x9e, 0xcf, 0xa9, 0x1a, 0x80, 0x62, 0x6f, 0xf3, 0x98, 0x9d, 0x68, 0xf6, 0x7f,
+    0x5b, 0x1d, 0xd1, 0x2d,
+]);
+
+/// Revert data of an `assume` whose condition does not hold, telling the
+/// fuzzer to discard the input.
+pub const ASSUME_MAGIC: &[u8] = b"CEVM::ASSUME";
+
+/// Return data of a cheatcode, or the exit of a cheatcode call that failed.
+pub type CheatcodeResult = Result<Vec<u8>, (ExitReason, Vec<u8>)>;
+
+/// Handler of a cheatcode, called on the executor of the calling frame with
+/// the caller's context and the ABI encoded input, selector included.
+pub type CheatcodeHandler<B> =
+    fn(&mut StackExecutor<'_, '_, B>, &Context, &[u8]) -> CheatcodeResult;
+
+/// A cheatcode callable on `CHEATCODE_ADDRESS`.
+pub struct Cheatcode<B> {
+    /// Solidity declaration, as written in the `Vm` interface
+    pub declaration: &'static str,
+    /// Handler of the cheatcode
+    pub handler: CheatcodeHandler<B>,
+}
+
+/// Cheatcodes by selector.
+pub struct Cheatcodes<B> {
+    cheatcodes: Vec<([u8; 4], Cheatcode<B>)>,
+}
+
+impl<B> Default for Cheatcodes<B> {
+    fn default() -> Self {
+        Self {
+            cheatcodes: Vec::new(),
+        }
+    }
+}
+
+impl<B: Backend> Cheatcodes<B> {
+    /// Create a registry without any cheatcode.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in cheatcodes.
+    pub fn standard() -> Self {
+        let mut cheatcodes = Self::new();
+        let standard: &[(&'static str, CheatcodeHandler<B>)] = &[
+            ("function roll(uint256 newHeight) external", roll),
+            ("function warp(uint256 newTimestamp) external", warp),
+            (
+                "function store(address target, bytes32 slot, bytes32 value) external",
+                store,
+            ),
+            (
+                "function load(address target, bytes32 slot) external returns (bytes32 data)",
+                load,
+            ),
+            ("function deposit(address who, uint256 amount) external", deposit),
+            ("function prank(address msgSender) external", prank),
+            (
+                "function prank(address msgSender, address txOrigin) external",
+                prank_with_origin,
+            ),
+            ("function startPrank(address msgSender) external", start_prank),
+            (
+                "function startPrank(address msgSender, address txOrigin) external",
+                start_prank_with_origin,
+            ),
+            ("function stopPrank() external", stop_prank),
+            ("function expectRevert() external", expect_revert),
+            (
+                "function expectRevert(bytes calldata revertData) external",
+                expect_revert_data,
+            ),
+            (
+                "function expectRevert(bytes4 revertData) external",
+                expect_revert_selector,
+            ),
+            (
+                "function expectEmit(bool checkTopic1, bool checkTopic2, bool checkTopic3, bool checkData) external",
+                expect_emit,
+            ),
+            (
+                "function expectEmit(bool checkTopic1, bool checkTopic2, bool checkTopic3, bool checkData, address emitter) external",
+                expect_emit_from,
+            ),
+            (
+                "function mockCall(address callee, bytes calldata data, bytes calldata returnData) external",
+                mock_call,
+            ),
+            ("function clearMockedCalls() external", clear_mocked_calls),
+            ("function etch(address target, bytes calldata newRuntimeBytecode) external", etch),
+            (
+                "function getCode(string calldata artifactName) external returns (bytes memory creationBytecode)",
+                get_code,
+            ),
+            (
+                "function deployCode(string calldata artifactName) external returns (address deployedAddress)",
+                deploy_code,
+            ),
+            ("function snapshot() external returns (uint256 snapshotId)", snapshot),
+            (
+                "function revertTo(uint256 snapshotId) external returns (bool success)",
+                revert_to,
+            ),
+            ("function record() external", record),
+            (
+                "function accesses(address target) external returns (bytes32[] memory readSlots, bytes32[] memory writeSlots)",
+                accesses,
+            ),
+            (
+                "function addr(uint256 privateKey) external returns (address keyAddr)",
+                addr,
+            ),
+            (
+                "function sign(uint256 privateKey, bytes32 digest) external returns (uint8 v, bytes32 r, bytes32 s)",
+                sign,
+            ),
+            (
+                "function label(address account, string calldata newLabel) external",
+                label,
+            ),
+            (
+                "function expectCall(address callee, bytes calldata data) external",
+                expect_call,
+            ),
+            (
+                "function expectCall(address callee, uint256 msgValue, bytes calldata data) external",
+                expect_call_with_value,
+            ),
+            (
+                "function expectCall(address callee, bytes calldata data, uint64 count) external",
+                expect_call_with_count,
+            ),
+            (
+                "function expectCall(address callee, uint256 msgValue, bytes calldata data, uint64 count) external",
+                expect_call_with_value_and_count,
+            ),
+            ("function assume(bool condition) external", assume),
+        ];
+        for (declaration, handler) in standard.iter() {
+            cheatcodes.register(Cheatcode {
+                declaration,
+                handler: *handler,
+            });
+        }
+        cheatcodes
+    }
+
+    /// Register `cheatcode`, replacing the cheatcode with the same selector.
+    pub fn register(&mut self, cheatcode: Cheatcode<B>) {
+        let selector = selector(cheatcode.declaration);
+        self.cheatcodes.retain(|(s, _)| *s != selector);
+        self.cheatcodes.push((selector, cheatcode));
+    }
+
+    /// The cheatcode called with `input`.
+    pub fn get(&self, input: &[u8]) -> Option<&Cheatcode<B>> {
+        let selector = input.get(..4)?;
+        self.cheatcodes
+            .iter()
+            .find(|(s, _)| s[..] == *selector)
+            .map(|(_, cheatcode)| cheatcode)
+    }
+
+    /// The registered cheatcodes, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &Cheatcode<B>> {
+        self.cheatcodes.iter().map(|(_, cheatcode)| cheatcode)
+    }
+}
+
+/// Canonical signature of a Solidity function declaration, such as
+/// `warp(uint256)` for `function warp(uint256 newTimestamp) external`.
+pub fn signature(declaration: &str) -> String {
+    let declaration = declaration.trim();
+    let declaration = declaration.strip_prefix("function ").unwrap_or(declaration);
+    let open = declaration.find('(').unwrap_or(declaration.len());
+    let close = declaration[open..]
+        .find(')')
+        .map_or(declaration.len(), |i| open + i);
+    let params: Vec<&str> = declaration[(open + 1).min(close)..close]
+        .split(',')
+        .filter_map(|param| param.split_whitespace().next())
+        .collect();
+    format!("{}({})", declaration[..open].trim(), params.join(","))
+}
+
+/// Selector of a Solidity function declaration.
+pub fn selector(declaration: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature(declaration).as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// The `bytes` argument at position `arg` of ABI encoded `input`.
+pub fn abi_bytes(input: &[u8], arg: usize) -> Result<Vec<u8>, (ExitReason, Vec<u8>)> {
+    let offset = 4 + usize_at(input, 4 + 32 * arg)?;
+    let len = usize_at(input, offset)?;
+    Ok(slice(input, offset + 32, len)?.to_vec())
+}
+
+/// ABI encode `data` as a single `bytes` value.
+pub fn abi_encode_bytes(data: &[u8]) -> Vec<u8> {
+    let mut out = H256::from_low_u64_be(32).as_bytes().to_vec();
+    out.extend_from_slice(H256::from_low_u64_be(data.len() as u64).as_bytes());
+    out.extend_from_slice(data);
+    out.resize(out.len() + (32 - data.len() % 32) % 32, 0);
+    out
+}
+
+/// ABI encode `message` as an `Error(string)` revert.
+pub fn revert_message(message: &str) -> Vec<u8> {
+    let mut out = vec![0x08, 0xc3, 0x79, 0xa0];
+    out.extend_from_slice(&abi_encode_bytes(message.as_bytes()));
+    out
+}
+
+/// The exit of a cheatcode call reverting with `message`.
+fn revert(message: &str) -> (ExitReason, Vec<u8>) {
+    (ExitRevert::Reverted.into(), revert_message(message))
+}
+
+/// ABI encoding of a single word.
+fn word(value: U256) -> Vec<u8> {
+    let mut out = [0u8; 32];
+    value.to_big_endian(&mut out);
+    out.to_vec()
+}
+
+/// The exit of a cheatcode call whose input can't be decoded.
+fn invalid_input() -> (ExitReason, Vec<u8>) {
+    revert("invalid cheatcode input")
+}
+
+/// The `len` bytes of `input` from `start`.
+fn slice(input: &[u8], start: usize, len: usize) -> Result<&[u8], (ExitReason, Vec<u8>)> {
+    start
+        .checked_add(len)
+        .and_then(|end| input.get(start..end))
+        .ok_or_else(invalid_input)
+}
+
+/// The word at `start` of `input`, read as an offset or length within it.
+fn usize_at(input: &[u8], start: usize) -> Result<usize, (ExitReason, Vec<u8>)> {
+    let value = U256::from_big_endian(slice(input, start, 32)?);
+    if value > U256::from(input.len()) {
+        return Err(invalid_input());
+    }
+    Ok(value.as_usize())
+}
+
+/// The `address` argument at position `arg` of ABI encoded `input`.
+fn address_arg(input: &[u8], arg: usize) -> Result<H160, (ExitReason, Vec<u8>)> {
+    Ok(H160::from_slice(&slice(input, 4 + 32 * arg, 32)?[12..]))
+}
+
+/// The `bytes32` argument at position `arg` of ABI encoded `input`.
+fn bytes32_arg(input: &[u8], arg: usize) -> Result<H256, (ExitReason, Vec<u8>)> {
+    Ok(H256::from_slice(slice(input, 4 + 32 * arg, 32)?))
+}
+
+/// The `uint256` argument at position `arg` of ABI encoded `input`.
+fn word_arg(input: &[u8], arg: usize) -> Result<U256, (ExitReason, Vec<u8>)> {
+    Ok(U256::from_big_endian(slice(input, 4 + 32 * arg, 32)?))
+}
+
+fn roll<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    executor.tmp_bn = Some(word_arg(input, 0)?);
+    Ok(Vec::new())
+}
+
+fn warp<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    executor.tmp_timestamp = Some(word_arg(input, 0)?);
+    Ok(Vec::new())
+}
+
+fn store<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let slot = bytes32_arg(input, 1)?;
+    let value = bytes32_arg(input, 2)?;
+    let _ = executor.set_storage(address_arg(input, 0)?, slot, value);
+    Ok(Vec::new())
+}
+
+fn load<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let slot = bytes32_arg(input, 1)?;
+    Ok(executor
+        .storage(address_arg(input, 0)?, slot)
+        .as_bytes()
+        .to_vec())
+}
+
+fn deposit<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    executor.deposit(address_arg(input, 0)?, word_arg(input, 1)?);
+    Ok(Vec::new())
+}
+
+/// Prank the calls of `context.caller`, from the sender and optional origin
+/// in `input`.
+fn start_prank_of<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+    with_origin: bool,
+    single: bool,
+) -> CheatcodeResult {
+    executor.prank = Some(Prank {
+        caller: context.caller,
+        sender: address_arg(input, 0)?,
+        origin: if with_origin {
+            Some(address_arg(input, 1)?)
+        } else {
+            None
+        },
+        single,
+    });
+    Ok(Vec::new())
+}
+
+fn prank<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    start_prank_of(executor, context, input, false, true)
+}
+
+fn prank_with_origin<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    start_prank_of(executor, context, input, true, true)
+}
+
+fn start_prank<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    start_prank_of(executor, context, input, false, false)
+}
+
+fn start_prank_with_origin<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    start_prank_of(executor, context, input, true, false)
+}
+
+fn stop_prank<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    executor.prank = None;
+    Ok(Vec::new())
+}
+
+fn expect_revert<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    executor.expected_revert = Some(ExpectedRevert {
+        caller: context.caller,
+        data: RevertData::Any,
+    });
+    Ok(Vec::new())
+}
+
+fn expect_revert_data<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    executor.expected_revert = Some(ExpectedRevert {
+        caller: context.caller,
+        data: RevertData::Exact(abi_bytes(input, 0)?),
+    });
+    Ok(Vec::new())
+}
+
+fn expect_revert_selector<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(slice(input, 4, 4)?);
+    executor.expected_revert = Some(ExpectedRevert {
+        caller: context.caller,
+        data: RevertData::Selector(selector),
+    });
+    Ok(Vec::new())
+}
+
+/// Expect the next call of `context.caller` to emit a log matching the
+/// checks in `input`, from `emitter` if given.
+fn expect_emit_of<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+    emitter: Option<H160>,
+) -> CheatcodeResult {
+    let mut checks = [false; 4];
+    for (i, check) in checks.iter_mut().enumerate() {
+        *check = !word_arg(input, i)?.is_zero();
+    }
+    executor.expected_emit = Some(ExpectedEmit {
+        caller: context.caller,
+        checks,
+        emitter,
+        log: None,
+    });
+    Ok(Vec::new())
+}
+
+fn expect_emit<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    expect_emit_of(executor, context, input, None)
+}
+
+fn expect_emit_from<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let emitter = address_arg(input, 4)?;
+    expect_emit_of(executor, context, input, Some(emitter))
+}
+
+fn mock_call<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    executor
+        .mocked_calls
+        .entry(address_arg(input, 0)?)
+        .or_default()
+        .insert(abi_bytes(input, 1)?, abi_bytes(input, 2)?);
+    Ok(Vec::new())
+}
+
+fn clear_mocked_calls<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    executor.mocked_calls.clear();
+    Ok(Vec::new())
+}
+
+fn etch<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    executor.set_code(address_arg(input, 0)?, abi_bytes(input, 1)?);
+    Ok(Vec::new())
+}
+
+/// Creation code of the artifact named in `input`.
+fn artifact<B: Backend>(
+    executor: &StackExecutor<'_, '_, B>,
+    input: &[u8],
+) -> Result<(String, Vec<u8>), (ExitReason, Vec<u8>)> {
+    let name = String::from_utf8_lossy(&abi_bytes(input, 0)?).into_owned();
+    match executor.artifacts.get(&name) {
+        Some(code) => Ok((name, code.clone())),
+        None => Err(revert(&format!("unknown artifact {}", name))),
+    }
+}
+
+fn get_code<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let (_, code) = artifact(executor, input)?;
+    Ok(abi_encode_bytes(&code))
+}
+
+fn deploy_code<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let (name, code) = artifact(executor, input)?;
+    match executor.deploy(context.caller, code) {
+        (ExitReason::Succeed(_), Some(address)) => Ok(H256::from(address).as_bytes().to_vec()),
+        (ExitReason::Fatal(e), _) => Err((ExitReason::Fatal(e), Vec::new())),
+        _ => Err(revert(&format!("deploying {} failed", name))),
+    }
+}
+
+fn snapshot<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    Ok(word(U256::from(executor.snapshot())))
+}
+
+fn revert_to<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let reverted = executor.revert_to_snapshot(word_arg(input, 0)?);
+    Ok(word(U256::from(reverted as u8)))
+}
+
+fn record<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    executor.recorded_accesses = Some(RecordedAccesses::default());
+    Ok(Vec::new())
+}
+
+fn accesses<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let accesses = executor.recorded_accesses.clone().unwrap_or_default();
+    Ok(accesses.encode(address_arg(input, 0)?))
+}
+
+/// Address controlled by the secp256k1 private key `key`.
+fn key_address(key: &secp256k1::SecretKey) -> H160 {
+    let public = secp256k1::PublicKey::from_secret_key(key);
+    H256::from_slice(Keccak256::digest(&public.serialize()[1..65]).as_slice()).into()
+}
+
+/// The secp256k1 private key argument at position 0 of `input`.
+fn private_key(input: &[u8]) -> Result<secp256k1::SecretKey, (ExitReason, Vec<u8>)> {
+    secp256k1::SecretKey::parse_slice(slice(input, 4, 32)?)
+        .map_err(|_| revert("invalid private key"))
+}
+
+fn addr<B: Backend>(
+    _executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let key = private_key(input)?;
+    Ok(H256::from(key_address(&key)).as_bytes().to_vec())
+}
+
+fn sign<B: Backend>(
+    _executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let key = private_key(input)?;
+    let digest = bytes32_arg(input, 1)?;
+    let (signature, recovery_id) =
+        secp256k1::sign(&secp256k1::Message::parse(digest.as_fixed_bytes()), &key);
+    let mut out = word(U256::from(27 + recovery_id.serialize() as u64));
+    out.extend_from_slice(&signature.serialize());
+    Ok(out)
+}
+
+fn label<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    let label = String::from_utf8_lossy(&abi_bytes(input, 1)?).into_owned();
+    executor.labels.insert(address_arg(input, 0)?, label);
+    Ok(Vec::new())
+}
+
+/// Expect a call to the callee in `input`, with the data at position
+/// `data_arg` and an optional value and count.
+fn expect_call_of<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    input: &[u8],
+    with_value: bool,
+    with_count: bool,
+) -> CheatcodeResult {
+    let data_arg = if with_value { 2 } else { 1 };
+    let expected = ExpectedCall {
+        target: address_arg(input, 0)?,
+        data: abi_bytes(input, data_arg)?,
+        value: if with_value {
+            Some(word_arg(input, 1)?)
+        } else {
+            None
+        },
+        count: if with_count {
+            Some(word_arg(input, data_arg + 1)?.low_u64())
+        } else {
+            None
+        },
+        skip: executor.call_trace.len(),
+    };
+    executor.expected_calls.push(expected);
+    Ok(Vec::new())
+}
+
+fn expect_call<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    expect_call_of(executor, input, false, false)
+}
+
+fn expect_call_with_value<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    expect_call_of(executor, input, true, false)
+}
+
+fn expect_call_with_count<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    expect_call_of(executor, input, false, true)
+}
+
+fn expect_call_with_value_and_count<B: Backend>(
+    executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    expect_call_of(executor, input, true, true)
+}
+
+fn assume<B: Backend>(
+    _executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    input: &[u8],
+) -> CheatcodeResult {
+    if word_arg(input, 0)?.is_zero() {
+        return Err((ExitRevert::Reverted.into(), ASSUME_MAGIC.to_vec()));
+    }
+    Ok(Vec::new())
+}
@@ -0,0 +1,30 @@
+use crate::{ExitReason, ExternalOpcode, Memory, Opcode, Stack};
+use primitive_types::H160;
+
+/// Machine state around a single opcode, as seen by an `Inspector`.
+#[derive(Clone, Debug)]
+pub struct StepInfo<'a> {
+    /// Call depth, 0 for the outermost frame of a transaction.
+    pub depth: usize,
+    /// Address whose code is executing.
+    pub address: H160,
+    /// Program counter of the opcode.
+    pub pc: usize,
+    /// The opcode.
+    pub opcode: Result<Opcode, ExternalOpcode>,
+    /// Machine stack.
+    pub stack: &'a Stack,
+    /// Machine memory.
+    pub memory: &'a Memory,
+    /// Gas remaining in the frame.
+    pub gas: usize,
+}
+
+/// Observer of every opcode the executor runs.
+pub trait Inspector {
+    /// Called before the opcode executes; `gas` is not yet charged for it.
+    fn step(&mut self, _info: &StepInfo) {}
+    /// Called after the opcode executed, with the exit reason if the frame
+    /// stopped on it.
+    fn step_end(&mut self, _info: &StepInfo, _exit: Option<ExitReason>) {}
+}
@@ -3,8 +3,18 @@
 //! Executors are structs that hook gasometer and the EVM core together. It
 //! also handles the call stacks in EVM.
 
+mod cheatcodes;
+mod inspector;
 mod stack;
 // mod stack_owned;
 
-pub use self::stack::{CallTrace, StackAccount, StackExecutor};
+pub use self::cheatcodes::{
+    abi_bytes, abi_encode_bytes, revert_message, selector, signature, Cheatcode, CheatcodeHandler,
+    CheatcodeResult, Cheatcodes, ASSUME_MAGIC, CHEATCODE_ADDRESS,
+};
+pub use self::inspector::{Inspector, StepInfo};
+pub use self::stack::{
+    CallTrace, ExpectedCall, ExpectedEmit, ExpectedRevert, Prank, RecordedAccesses, RevertData,
+    StackAccount, StackExecutor,
+};
 // pub use self::stack_owned::StackExecutorOwned;
@@ -1,14 +1,22 @@
+use super::cheatcodes::{
+    abi_encode_bytes, revert_message, selector, Cheatcodes, CHEATCODE_ADDRESS,
+};
+use super::inspector::{Inspector, StepInfo};
 use crate::backend::{memory::TxReceipt, Apply, Backend, Basic, Log};
-use crate::gasometer::{self, Gasometer};
+use crate::gasometer::{self, Gasometer, StorageTarget};
+use crate::precompiles::{self, PrecompileFn};
 use crate::{
-    Capture, Config, Context, CreateScheme, ExitError, ExitReason, ExitSucceed, ExternalOpcode,
-    Handler, Opcode, Runtime, Stack, Transfer,
+    Capture, Config, Context, CreateScheme, ExitError, ExitFatal, ExitReason, ExitRevert,
+    ExitSucceed, ExternalOpcode, Handler, Machine, Opcode, Resolve, Runtime, Stack, Transfer,
 };
 use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::cmp::min;
+use core::cell::RefCell;
+use core::cmp::{min, Reverse};
 use core::convert::Infallible;
+use core::mem;
 
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
@@ -36,6 +44,389 @@ pub struct StackAccount {
     pub reset_storage_backend: bool,
 }
 
+/// A state change, holding what is needed to undo it.
+#[derive(Clone, Debug)]
+enum JournalEntry {
+    /// Balance and nonce before they changed
+    Basic { address: H160, basic: Basic },
+    /// Storage value before a write, `None` if it was not loaded
+    Storage {
+        address: H160,
+        index: H256,
+        value: Option<H256>,
+    },
+    /// Storage before it was reset for a new contract
+    ResetStorage {
+        address: H160,
+        storage: BTreeMap<H256, H256>,
+        reset_storage: bool,
+    },
+    /// Code before a contract was deployed
+    Code {
+        address: H160,
+        code: Option<Vec<u8>>,
+        original_code: Option<Vec<u8>>,
+    },
+    /// Transient storage value before a write
+    TransientStorage {
+        address: H160,
+        index: H256,
+        value: Option<H256>,
+    },
+    /// Address marked for deletion
+    Deleted(H160),
+    /// Address of a created contract
+    Created(H160),
+    /// Address warmed
+    AccessedAddress(H160),
+    /// Storage slot warmed
+    AccessedStorage(H160, H256),
+}
+
+/// Executor state captured by the `snapshot` cheatcode.
+#[derive(Clone, Debug)]
+struct Snapshot {
+    state: BTreeMap<H160, StackAccount>,
+    deleted: BTreeSet<H160>,
+    created_contracts: BTreeSet<H160>,
+    tmp_bn: Option<U256>,
+    tmp_timestamp: Option<U256>,
+    logs: Vec<Log>,
+    owned_logs: Vec<Log>,
+    accessed_addresses: BTreeSet<H160>,
+    accessed_storage: BTreeSet<(H160, H256)>,
+    transient_storage: BTreeMap<(H160, H256), H256>,
+    journal: Vec<JournalEntry>,
+}
+
+/// A call or create frame that has yet to run, returned by the executor's
+/// `CALL` and `CREATE` interrupts.
+pub struct StackFrame<'backend, 'config, B> {
+    executor: StackExecutor<'backend, 'config, B>,
+    runtime: Runtime<'config>,
+    calltrace: CallTrace,
+    /// Address being created, `None` for calls
+    address: Option<H160>,
+    /// Revert the call is expected to end with
+    expected_revert: Option<ExpectedRevert>,
+    /// Log the call is expected to emit
+    expected_emit: Option<ExpectedEmit>,
+}
+
+/// Result of a finished frame, handed back to its parent runtime.
+enum FrameExit {
+    Call(ExitReason, Vec<u8>),
+    Create(ExitReason, Option<H160>, Vec<u8>),
+}
+
+impl FrameExit {
+    fn resume(self, runtime: &mut Runtime) -> Result<(), ExitReason> {
+        match self {
+            FrameExit::Call(reason, out) => runtime.finish_call(reason, out),
+            FrameExit::Create(reason, address, out) => runtime.finish_create(reason, address, out),
+        }
+    }
+}
+
+/// A `msg.sender` override set by the `prank` cheatcodes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Prank {
+    /// Contract whose calls are pranked
+    pub caller: H160,
+    /// Sender the pranked calls come from
+    pub sender: H160,
+    /// `tx.origin` inside the pranked calls
+    pub origin: Option<H160>,
+    /// Whether only the next call is pranked
+    pub single: bool,
+}
+
+/// Revert data accepted by an `expectRevert` cheatcode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RevertData {
+    /// Any revert
+    Any,
+    /// Revert data, or the reason of an `Error(string)` revert
+    Exact(Vec<u8>),
+    /// Revert data starting with a custom error selector
+    Selector([u8; 4]),
+}
+
+/// Address returned by a create that reverted as expected, so the creating
+/// contract carries on as if it had succeeded.
+const DUMMY_CREATE_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x01,
+]);
+
+/// Revert expected of the next call by the `expectRevert` cheatcodes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedRevert {
+    /// Contract whose next call must revert
+    pub caller: H160,
+    /// Revert data the call must return
+    pub data: RevertData,
+}
+
+impl ExpectedRevert {
+    /// Check the result of the expected call, turning a matching revert into a
+    /// success and anything else into a revert explaining the mismatch.
+    fn check(&self, reason: ExitReason, out: Vec<u8>) -> (ExitReason, Vec<u8>) {
+        let out = match reason {
+            ExitReason::Revert(_) => out,
+            ExitReason::Error(_) => Vec::new(),
+            ExitReason::Succeed(_) => {
+                return (
+                    ExitRevert::Reverted.into(),
+                    revert_message("Call did not revert as expected"),
+                )
+            }
+            ExitReason::Fatal(_) => return (reason, out),
+        };
+        let matches = match &self.data {
+            RevertData::Any => true,
+            RevertData::Exact(data) => {
+                *data == out
+                    || matches!(revert_reason(&out), Some(r) if r.as_bytes() == data.as_slice())
+            }
+            RevertData::Selector(selector) => out.starts_with(selector),
+        };
+        if matches {
+            return (ExitSucceed::Returned.into(), Vec::new());
+        }
+        let expected = match &self.data {
+            RevertData::Exact(data) => describe_revert(data),
+            RevertData::Selector(selector) => describe_revert(selector),
+            RevertData::Any => String::new(),
+        };
+        (
+            ExitRevert::Reverted.into(),
+            revert_message(&format!(
+                "Error != expected error: {} != {}",
+                describe_revert(&out),
+                expected
+            )),
+        )
+    }
+
+    /// Check the result of the expected create like `check`, returning a dummy
+    /// address for a matching revert.
+    fn check_create(
+        &self,
+        reason: ExitReason,
+        out: Vec<u8>,
+    ) -> (ExitReason, Option<H160>, Vec<u8>) {
+        let (reason, out) = self.check(reason, out);
+        let address = if reason.is_succeed() {
+            Some(DUMMY_CREATE_ADDRESS)
+        } else {
+            None
+        };
+        (reason, address, out)
+    }
+}
+
+/// Log expected of the next call by the `expectEmit` cheatcode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedEmit {
+    /// Contract whose next call must emit the log
+    pub caller: H160,
+    /// Whether topics 1 to 3 and the data are compared
+    pub checks: [bool; 4],
+    /// Contract that must emit the log, any if `None`
+    pub emitter: Option<H160>,
+    /// Expected log, `None` until the contract emits it
+    pub log: Option<Log>,
+}
+
+impl ExpectedEmit {
+    /// Whether `log` matches the expected log on the checked fields.
+    fn matches(&self, log: &Log) -> bool {
+        let expected = match &self.log {
+            Some(expected) => expected,
+            None => return false,
+        };
+        !matches!(self.emitter, Some(emitter) if emitter != log.address)
+            && expected.topics.len() == log.topics.len()
+            && expected.topics.first() == log.topics.first()
+            && (1..expected.topics.len())
+                .all(|i| !self.checks[i - 1] || expected.topics[i] == log.topics[i])
+            && (!self.checks[3] || expected.data == log.data)
+    }
+
+    /// Check the result of the expected call against the logs it emitted,
+    /// turning a success without a matching log into a `LogNotEmitted` revert.
+    fn check(&self, logs: &[Log], reason: ExitReason, out: Vec<u8>) -> (ExitReason, Vec<u8>) {
+        if !reason.is_succeed() || logs.iter().any(|log| self.matches(log)) {
+            return (reason, out);
+        }
+        let expected = match &self.log {
+            Some(expected) => expected,
+            None => return (reason, out),
+        };
+        let emitter = self.emitter.unwrap_or(expected.address);
+        let mut out =
+            selector("LogNotEmitted(address emitter, bytes32[] topics, bytes data)").to_vec();
+        out.extend_from_slice(H256::from(emitter).as_bytes());
+        out.extend_from_slice(H256::from_low_u64_be(96).as_bytes());
+        out.extend_from_slice(
+            H256::from_low_u64_be(128 + 32 * expected.topics.len() as u64).as_bytes(),
+        );
+        out.extend_from_slice(H256::from_low_u64_be(expected.topics.len() as u64).as_bytes());
+        for topic in expected.topics.iter() {
+            out.extend_from_slice(topic.as_bytes());
+        }
+        // The data is the tail of its encoding as a lone `bytes` value, after
+        // the offset.
+        out.extend_from_slice(&abi_encode_bytes(&expected.data)[32..]);
+        (ExitRevert::Reverted.into(), out)
+    }
+}
+
+/// Call expected by the `expectCall` cheatcodes before the frame returns.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedCall {
+    /// Callee of the expected call
+    pub target: H160,
+    /// Calldata the call must start with
+    pub data: Vec<u8>,
+    /// Value the call must send, any if `None`
+    pub value: Option<U256>,
+    /// Number of matching calls, at least one if `None`
+    pub count: Option<u64>,
+    /// Number of calls the frame made before the expectation
+    pub skip: usize,
+}
+
+impl ExpectedCall {
+    /// Whether `trace` is a call matching the expectation.
+    fn matches(&self, trace: &CallTrace) -> bool {
+        trace.addr == self.target
+            && !trace.created
+            && trace.calldata().starts_with(&self.data)
+            && !matches!(self.value, Some(value) if value != trace.value)
+    }
+
+    /// Check the calls traced in `traces`, turning a success without the
+    /// expected calls into a revert listing the closest calls that were made.
+    fn check(
+        &self,
+        traces: &[CallTrace],
+        reason: ExitReason,
+        out: Vec<u8>,
+    ) -> (ExitReason, Vec<u8>) {
+        if !reason.is_succeed() {
+            return (reason, out);
+        }
+        let mut calls = Vec::new();
+        let mut stack: Vec<&CallTrace> = traces.iter().skip(self.skip).rev().collect();
+        while let Some(trace) = stack.pop() {
+            calls.push(trace);
+            stack.extend(trace.inner.iter().rev());
+        }
+        let made = calls.iter().filter(|trace| self.matches(trace)).count() as u64;
+        let fulfilled = match self.count {
+            Some(count) => made == count,
+            None => made > 0,
+        };
+        if fulfilled {
+            return (reason, out);
+        }
+
+        let mut message = format!(
+            "Expected call to 0x{} with data 0x{}",
+            hex::encode(self.target.as_bytes()),
+            hex::encode(&self.data)
+        );
+        if let Some(value) = self.value {
+            message += &format!(" and value {}", value);
+        }
+        match self.count {
+            Some(count) => message += &format!(" {} times, got {}", count, made),
+            None => message += " not made",
+        }
+        // Calls to the target, or with the same selector, sharing the longest
+        // calldata prefix with the expected data are the closest.
+        let shared = |trace: &CallTrace| {
+            trace
+                .calldata()
+                .iter()
+                .zip(self.data.iter())
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+        calls.retain(|trace| !trace.created && (trace.addr == self.target || shared(trace) >= 4));
+        calls.sort_by_key(|trace| (trace.addr != self.target, Reverse(shared(trace))));
+        let closest: Vec<String> = calls
+            .iter()
+            .take(3)
+            .map(|trace| {
+                format!(
+                    "0x{} with data 0x{} and value {}",
+                    hex::encode(trace.addr.as_bytes()),
+                    hex::encode(trace.calldata()),
+                    trace.value
+                )
+            })
+            .collect();
+        if !closest.is_empty() {
+            message += &format!("; closest calls: {}", closest.join(", "));
+        }
+        (ExitRevert::Reverted.into(), revert_message(&message))
+    }
+}
+
+/// Storage slots accessed since the `record` cheatcode.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RecordedAccesses {
+    /// Slots read, by contract
+    pub reads: BTreeMap<H160, Vec<H256>>,
+    /// Slots written, by contract
+    pub writes: BTreeMap<H160, Vec<H256>>,
+}
+
+impl RecordedAccesses {
+    /// ABI encode the slots accessed on `address` as the
+    /// `(bytes32[] reads, bytes32[] writes)` returned by `accesses`.
+    pub fn encode(&self, address: H160) -> Vec<u8> {
+        let empty = Vec::new();
+        let reads = self.reads.get(&address).unwrap_or(&empty);
+        let writes = self.writes.get(&address).unwrap_or(&empty);
+        let mut out = H256::from_low_u64_be(64).as_bytes().to_vec();
+        out.extend_from_slice(H256::from_low_u64_be(96 + 32 * reads.len() as u64).as_bytes());
+        for slots in [reads, writes].iter() {
+            out.extend_from_slice(H256::from_low_u64_be(slots.len() as u64).as_bytes());
+            for slot in slots.iter() {
+                out.extend_from_slice(slot.as_bytes());
+            }
+        }
+        out
+    }
+}
+
+/// Reason of an `Error(string)` revert.
+fn revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 68 || data[..4] != [0x08, 0xc3, 0x79, 0xa0] {
+        return None;
+    }
+    let len = U256::from_big_endian(&data[36..68]);
+    if len > U256::from(data.len() - 68) {
+        return None;
+    }
+    String::from_utf8(data[68..68 + len.as_usize()].to_vec()).ok()
+}
+
+/// Human readable revert data, its reason if it is printable.
+fn describe_revert(data: &[u8]) -> String {
+    match revert_reason(data) {
+        Some(reason) => reason,
+        None => match String::from_utf8(data.to_vec()) {
+            Ok(s) if !s.is_empty() && !s.chars().any(|c| c.is_control()) => s,
+            _ => format!("0x{}", hex::encode(data)),
+        },
+    }
+}
+
 /// Call trace of a tx
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
@@ -58,6 +449,19 @@ pub struct CallTrace {
     pub logs: Vec<Log>,
     /// inner calls
     pub inner: Vec<CallTrace>,
+    /// Whether the call returned mocked data
+    pub mocked: bool,
+    /// Value sent with the call
+    pub value: U256,
+    /// Name given to the callee by the `label` cheatcode
+    pub label: Option<String>,
+}
+
+impl CallTrace {
+    /// Calldata of the call, the function selector followed by its input.
+    pub fn calldata(&self) -> Vec<u8> {
+        hex::decode(format!("{}{}", self.function, self.input)).unwrap_or_default()
+    }
 }
 
 /// Stack-based executor.
@@ -78,8 +482,7 @@ pub struct StackExecutor<'backend, 'config, B> {
     /// Precompile Map
     pub precompiles: BTreeMap<H160, fn(&[u8], Option<usize>)>,
     /// Precompiles
-    pub precompile:
-        fn(H160, &[u8], Option<usize>) -> Option<Result<(ExitSucceed, Vec<u8>, usize), ExitError>>,
+    pub precompile: PrecompileFn,
     /// is static flag
     pub is_static: bool,
     /// Recursion depth
@@ -90,26 +493,52 @@ pub struct StackExecutor<'backend, 'config, B> {
     pub tmp_bn: Option<U256>,
     /// Txs that are pending
     pub tmp_timestamp: Option<U256>,
+    /// `tx.origin` override of a pranked call
+    pub tmp_origin: Option<H160>,
+    /// Active prank of this frame
+    pub prank: Option<Prank>,
+    /// Revert expected of the next call of this frame
+    pub expected_revert: Option<ExpectedRevert>,
+    /// Log expected of the next call of this frame
+    pub expected_emit: Option<ExpectedEmit>,
+    /// Calls this frame is expected to make before returning
+    pub expected_calls: Vec<ExpectedCall>,
+    /// Storage accesses recorded since the `record` cheatcode
+    pub recorded_accesses: Option<RecordedAccesses>,
+    /// Mocked return data, by callee and calldata prefix
+    pub mocked_calls: BTreeMap<H160, BTreeMap<Vec<u8>, Vec<u8>>>,
+    /// Names given to addresses by the `label` cheatcode
+    pub labels: BTreeMap<H160, String>,
+    /// Creation code of compiled contracts, by artifact name
+    pub artifacts: Arc<BTreeMap<String, Vec<u8>>>,
+    /// Cheatcodes callable on `CHEATCODE_ADDRESS`
+    pub cheatcodes: Arc<Cheatcodes<B>>,
     /// created contracts
     pub created_contracts: BTreeSet<H160>,
     /// Call trace
     pub call_trace: Vec<CallTrace>,
     /// Owned Logs
     pub owned_logs: Vec<Log>,
-}
-
-fn precompiles(
-    _address: H160,
-    _input: &[u8],
-    _target_gas: Option<usize>,
-) -> Option<Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
-    None
+    /// Step inspector, shared with substates
+    pub inspector: Option<Rc<RefCell<dyn Inspector>>>,
+    /// Addresses accessed in the transaction (EIP-2929)
+    pub accessed_addresses: BTreeSet<H160>,
+    /// Storage slots accessed in the transaction (EIP-2929)
+    pub accessed_storage: BTreeSet<(H160, H256)>,
+    /// Transient storage of the transaction (EIP-1153)
+    pub transient_storage: BTreeMap<(H160, H256), H256>,
+    /// Changes made to the state, undone when a call frame reverts
+    journal: Vec<JournalEntry>,
+    /// Journal length when this substate was created
+    checkpoint: usize,
+    /// States captured by the `snapshot` cheatcode, by id
+    snapshots: Vec<Snapshot>,
 }
 
 impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
-    /// Create a new stack-based executor.
+    /// Create a new stack-based executor with the standard precompiles.
     pub fn new(backend: &'backend B, gas_limit: usize, config: &'config Config) -> Self {
-        Self::new_with_precompile(backend, gas_limit, config, precompiles)
+        Self::new_with_precompile(backend, gas_limit, config, precompiles::standard)
     }
 
     /// Create a new stack-based executor with given precompiles.
@@ -117,11 +546,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         backend: &'backend B,
         gas_limit: usize,
         config: &'config Config,
-        precompiles: fn(
-            H160,
-            &[u8],
-            Option<usize>,
-        ) -> Option<Result<(ExitSucceed, Vec<u8>, usize), ExitError>>,
+        precompiles: PrecompileFn,
     ) -> Self {
         Self {
             backend,
@@ -136,16 +561,35 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             pending_txs: Vec::new(),
             tmp_bn: None,
             tmp_timestamp: None,
+            tmp_origin: None,
+            prank: None,
+            expected_revert: None,
+            expected_emit: None,
+            expected_calls: Vec::new(),
+            recorded_accesses: None,
+            mocked_calls: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            artifacts: Arc::new(BTreeMap::new()),
+            cheatcodes: Arc::new(Cheatcodes::standard()),
             created_contracts: BTreeSet::new(),
             call_trace: Vec::new(),
             precompiles: BTreeMap::new(),
             owned_logs: Vec::new(),
+            inspector: None,
+            accessed_addresses: BTreeSet::new(),
+            accessed_storage: BTreeSet::new(),
+            transient_storage: BTreeMap::new(),
+            journal: Vec::new(),
+            checkpoint: 0,
+            snapshots: Vec::new(),
         }
     }
 
     /// Create a substate executor from the current executor.
+    ///
+    /// The state moves into the substate until it is merged back.
     pub fn substate(
-        &self,
+        &mut self,
         gas_limit: usize,
         is_static: bool,
     ) -> StackExecutor<'backend, 'config, B> {
@@ -153,8 +597,8 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             backend: self.backend,
             gasometer: Gasometer::new(gas_limit, self.gasometer.config()),
             config: self.config,
-            state: self.state.clone(),
-            deleted: self.deleted.clone(),
+            state: mem::take(&mut self.state),
+            deleted: mem::take(&mut self.deleted),
             logs: Vec::new(),
             precompile: self.precompile,
             is_static: is_static || self.is_static,
@@ -165,18 +609,182 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             pending_txs: Vec::new(),
             tmp_bn: self.tmp_bn,
             tmp_timestamp: self.tmp_timestamp,
-            created_contracts: self.created_contracts.clone(),
+            tmp_origin: self.tmp_origin,
+            prank: None,
+            expected_revert: None,
+            expected_emit: None,
+            expected_calls: Vec::new(),
+            recorded_accesses: mem::take(&mut self.recorded_accesses),
+            mocked_calls: mem::take(&mut self.mocked_calls),
+            labels: mem::take(&mut self.labels),
+            artifacts: self.artifacts.clone(),
+            cheatcodes: self.cheatcodes.clone(),
+            created_contracts: mem::take(&mut self.created_contracts),
             call_trace: Vec::new(),
             precompiles: self.precompiles.clone(),
             owned_logs: Vec::new(),
+            inspector: self.inspector.clone(),
+            accessed_addresses: mem::take(&mut self.accessed_addresses),
+            accessed_storage: mem::take(&mut self.accessed_storage),
+            transient_storage: mem::take(&mut self.transient_storage),
+            checkpoint: self.journal.len(),
+            journal: mem::take(&mut self.journal),
+            snapshots: mem::take(&mut self.snapshots),
+        }
+    }
+
+    /// Take back the state of a finished substate, undoing its changes unless
+    /// it succeeded.
+    fn exit_substate<OB>(&mut self, substate: &mut StackExecutor<'_, '_, OB>, succeed: bool) {
+        self.state = mem::take(&mut substate.state);
+        self.deleted = mem::take(&mut substate.deleted);
+        self.created_contracts = mem::take(&mut substate.created_contracts);
+        self.accessed_addresses = mem::take(&mut substate.accessed_addresses);
+        self.accessed_storage = mem::take(&mut substate.accessed_storage);
+        self.transient_storage = mem::take(&mut substate.transient_storage);
+        self.recorded_accesses = mem::take(&mut substate.recorded_accesses);
+        self.mocked_calls = mem::take(&mut substate.mocked_calls);
+        self.labels = mem::take(&mut substate.labels);
+        self.snapshots = mem::take(&mut substate.snapshots);
+        self.journal = mem::take(&mut substate.journal);
+        if !succeed {
+            self.revert_to(substate.checkpoint);
+        }
+        // Nothing can revert changes of the outermost frame.
+        if self.depth.is_none() {
+            self.journal.clear();
         }
     }
 
+    /// Undo the changes journaled after `checkpoint`.
+    fn revert_to(&mut self, checkpoint: usize) {
+        // Restoring a snapshot taken by an outer frame shortens the journal.
+        let checkpoint = min(checkpoint, self.journal.len());
+        for entry in self.journal.split_off(checkpoint).into_iter().rev() {
+            match entry {
+                JournalEntry::Basic { address, basic } => {
+                    self.account_mut(address).basic = basic;
+                }
+                JournalEntry::Storage {
+                    address,
+                    index,
+                    value,
+                } => {
+                    let storage = &mut self.account_mut(address).storage;
+                    match value {
+                        Some(value) => storage.insert(index, value),
+                        None => storage.remove(&index),
+                    };
+                }
+                JournalEntry::ResetStorage {
+                    address,
+                    storage,
+                    reset_storage,
+                } => {
+                    let account = self.account_mut(address);
+                    account.storage = storage;
+                    account.reset_storage = reset_storage;
+                }
+                JournalEntry::Code {
+                    address,
+                    code,
+                    original_code,
+                } => {
+                    let account = self.account_mut(address);
+                    account.code = code;
+                    account.original_code = original_code;
+                }
+                JournalEntry::TransientStorage {
+                    address,
+                    index,
+                    value,
+                } => {
+                    match value {
+                        Some(value) => self.transient_storage.insert((address, index), value),
+                        None => self.transient_storage.remove(&(address, index)),
+                    };
+                }
+                JournalEntry::Deleted(address) => {
+                    self.deleted.remove(&address);
+                }
+                JournalEntry::Created(address) => {
+                    self.created_contracts.remove(&address);
+                }
+                JournalEntry::AccessedAddress(address) => {
+                    self.accessed_addresses.remove(&address);
+                }
+                JournalEntry::AccessedStorage(address, index) => {
+                    self.accessed_storage.remove(&(address, index));
+                }
+            }
+        }
+    }
+
+    /// Set the inspector called around every opcode.
+    pub fn set_inspector(&mut self, inspector: Rc<RefCell<dyn Inspector>>) {
+        self.inspector = Some(inspector);
+    }
+
     /// Execute the runtime until it returns.
+    ///
+    /// Nested calls and creates run on a heap-allocated frame stack, so deep
+    /// call chains don't grow the native stack.
     pub fn execute(&mut self, runtime: &mut Runtime) -> ExitReason {
-        match runtime.run(self) {
-            Capture::Exit(s) => s,
-            Capture::Trap(_) => unreachable!("Trap is Infallible"),
+        loop {
+            let frame = match self.run(runtime) {
+                Capture::Exit(reason) => return reason,
+                Capture::Trap(frame) => frame,
+            };
+            if let Err(reason) = self.run_frames(frame).resume(runtime) {
+                return reason;
+            }
+        }
+    }
+
+    /// Run the runtime until it exits or interrupts on a call or create.
+    fn run(
+        &mut self,
+        runtime: &mut Runtime,
+    ) -> Capture<ExitReason, StackFrame<'backend, 'config, B>> {
+        let reason = match runtime.run(self) {
+            Capture::Exit(reason) => reason,
+            Capture::Trap(Resolve::Call(frame, _)) | Capture::Trap(Resolve::Create(frame, _)) => {
+                return Capture::Trap(frame)
+            }
+        };
+        match self.backend.error() {
+            Some(e) => Capture::Exit(ExitFatal::ProviderError(e.method()).into()),
+            None => Capture::Exit(reason),
+        }
+    }
+
+    /// Run `frame` and every frame it enters, then merge it into `self`.
+    fn run_frames(&mut self, frame: StackFrame<'backend, 'config, B>) -> FrameExit {
+        let mut frames = Vec::new();
+        frames.push(frame);
+        loop {
+            let top = frames.last_mut().expect("frame stack is never empty; qed");
+            let mut reason = match top.executor.run(&mut top.runtime) {
+                Capture::Exit(reason) => reason,
+                Capture::Trap(frame) => {
+                    frames.push(frame);
+                    continue;
+                }
+            };
+
+            // Hand the result down until a parent frame can resume.
+            loop {
+                let frame = frames.pop().expect("frame stack is never empty; qed");
+                let parent = match frames.last_mut() {
+                    Some(parent) => parent,
+                    None => return self.exit_frame(frame, reason),
+                };
+                let exit = parent.executor.exit_frame(frame, reason);
+                match exit.resume(&mut parent.runtime) {
+                    Ok(()) => break,
+                    Err(e) => reason = e,
+                }
+            }
         }
     }
 
@@ -194,11 +802,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         calltrace.logs = substate.owned_logs.clone();
         self.call_trace.push(calltrace);
         self.logs.append(&mut substate.logs);
-        self.deleted.append(&mut substate.deleted);
-        for cc in substate.created_contracts.into_iter() {
-            self.created_contracts.insert(cc);
-        }
-        self.state = substate.state;
+        self.exit_substate(&mut substate, true);
         self.tmp_bn = substate.tmp_bn;
         self.tmp_timestamp = substate.tmp_timestamp;
         self.gasometer.record_stipend(substate.gasometer.gas())?;
@@ -207,7 +811,8 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         Ok(())
     }
 
-    /// Merge a substate executor that reverted.
+    /// Merge a substate executor that reverted. Its logs only remain in the
+    /// call trace.
     pub fn merge_revert<OB>(
         &mut self,
         mut substate: StackExecutor<'_, '_, OB>,
@@ -215,15 +820,15 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
     ) -> Result<(), ExitError> {
         calltrace.logs = substate.owned_logs.clone();
         self.call_trace.push(calltrace);
-        self.logs.append(&mut substate.logs);
-        self.state = substate.state;
+        self.exit_substate(&mut substate, false);
         self.tmp_bn = substate.tmp_bn;
         self.tmp_timestamp = substate.tmp_timestamp;
         self.gasometer.record_stipend(substate.gasometer.gas())?;
         Ok(())
     }
 
-    /// Merge a substate executor that failed.
+    /// Merge a substate executor that failed. Its logs only remain in the
+    /// call trace.
     pub fn merge_fail<OB>(
         &mut self,
         mut substate: StackExecutor<'_, '_, OB>,
@@ -231,10 +836,9 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
     ) -> Result<(), ExitError> {
         calltrace.logs = substate.owned_logs.clone();
         self.call_trace.push(calltrace);
-        self.state = substate.state;
+        self.exit_substate(&mut substate, false);
         self.tmp_bn = substate.tmp_bn;
         self.tmp_timestamp = substate.tmp_timestamp;
-        self.logs.append(&mut substate.logs);
 
         Ok(())
     }
@@ -247,12 +851,14 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         value: U256,
         init_code: Vec<u8>,
         gas_limit: usize,
+        access_list: Vec<(H160, Vec<H256>)>,
     ) -> (ExitReason, Option<H160>, Vec<CallTrace>) {
-        let transaction_cost = gasometer::create_transaction_cost(&init_code);
+        let transaction_cost = gasometer::create_transaction_cost(&init_code, &access_list);
         match self.gasometer.record_transaction(transaction_cost) {
             Ok(()) => (),
             Err(e) => return (e.into(), None, Vec::new()),
         }
+        self.warm_transaction(caller, None, access_list);
 
         let exit = self.create_inner(
             caller,
@@ -262,18 +868,16 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             Some(gas_limit),
             false,
         );
+        let (reason, address) = self.create_result(exit);
 
         let status;
-        match exit {
-            Capture::Exit((s, _a, _)) => match s {
-                ExitReason::Succeed(_) => {
-                    status = 1;
-                }
-                _ => {
-                    status = 0;
-                }
-            },
-            Capture::Trap(_) => unreachable!(),
+        match reason {
+            ExitReason::Succeed(_) => {
+                status = 1;
+            }
+            _ => {
+                status = 0;
+            }
         };
 
         self.pending_txs.push(TxReceipt {
@@ -291,10 +895,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         self.tmp_bn = None;
         self.tmp_timestamp = None;
 
-        match exit {
-            Capture::Exit((s, a, _)) => (s, a, self.call_trace.clone()),
-            Capture::Trap(_) => unreachable!(),
-        }
+        (reason, address, self.labelled_call_trace())
     }
 
     /// Execute a `CREATE2` transaction.
@@ -305,12 +906,14 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         init_code: Vec<u8>,
         salt: H256,
         gas_limit: usize,
+        access_list: Vec<(H160, Vec<H256>)>,
     ) -> (ExitReason, Option<H160>, Vec<CallTrace>) {
-        let transaction_cost = gasometer::create_transaction_cost(&init_code);
+        let transaction_cost = gasometer::create_transaction_cost(&init_code, &access_list);
         match self.gasometer.record_transaction(transaction_cost) {
             Ok(()) => (),
             Err(e) => return (e.into(), None, Vec::new()),
         }
+        self.warm_transaction(caller, None, access_list);
         let code_hash = H256::from_slice(Keccak256::digest(&init_code).as_slice());
 
         let exit = self.create_inner(
@@ -325,13 +928,11 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             Some(gas_limit),
             false,
         );
+        let (reason, address) = self.create_result(exit);
 
         // self.call_trace = self.call_trace.inner.clone();
 
-        match exit {
-            Capture::Exit((s, a, _)) => (s, a, self.call_trace.clone()),
-            Capture::Trap(_) => unreachable!(),
-        }
+        (reason, address, self.labelled_call_trace())
     }
 
     /// Execute a `CALL` transaction.
@@ -343,14 +944,16 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         value: U256,
         data: Vec<u8>,
         gas_limit: usize,
+        access_list: Vec<(H160, Vec<H256>)>,
     ) -> (ExitReason, Vec<u8>, Vec<CallTrace>) {
-        let transaction_cost = gasometer::call_transaction_cost(&data);
+        let transaction_cost = gasometer::call_transaction_cost(&data, &access_list);
         match self.gasometer.record_transaction(transaction_cost) {
             Ok(()) => (),
             Err(e) => return (e.into(), Vec::new(), Vec::new()),
         }
+        self.warm_transaction(caller, Some(address), access_list);
 
-        self.account_mut(caller).basic.nonce += U256::one();
+        self.basic_mut(caller).nonce += U256::one();
 
         let context = Context {
             caller,
@@ -372,18 +975,16 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             false,
             context,
         );
+        let (reason, out) = self.call_result(exit);
 
         let status;
-        match exit {
-            Capture::Exit((s, ref _v)) => match s {
-                ExitReason::Succeed(_) => {
-                    status = 1;
-                }
-                _ => {
-                    status = 0;
-                }
-            },
-            Capture::Trap(_) => unreachable!(),
+        match reason {
+            ExitReason::Succeed(_) => {
+                status = 1;
+            }
+            _ => {
+                status = 0;
+            }
         };
 
         self.pending_txs.push(TxReceipt {
@@ -401,9 +1002,89 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         self.tmp_bn = None;
         self.tmp_timestamp = None;
 
+        (reason, out, self.labelled_call_trace())
+    }
+
+    /// The call trace of the transaction, with the names given by the `label`
+    /// cheatcode.
+    fn labelled_call_trace(&self) -> Vec<CallTrace> {
+        let mut traces = self.call_trace.clone();
+        let mut stack: Vec<&mut CallTrace> = traces.iter_mut().collect();
+        while let Some(trace) = stack.pop() {
+            trace.label = self.labels.get(&trace.addr).cloned();
+            stack.extend(trace.inner.iter_mut());
+        }
+        traces
+    }
+
+    /// Run a call started by a transaction to completion.
+    fn call_result(
+        &mut self,
+        exit: Capture<(ExitReason, Vec<u8>), StackFrame<'backend, 'config, B>>,
+    ) -> (ExitReason, Vec<u8>) {
         match exit {
-            Capture::Exit((s, v)) => (s, v, self.call_trace.clone()),
-            Capture::Trap(_) => unreachable!(),
+            Capture::Exit(exit) => exit,
+            Capture::Trap(frame) => match self.run_frames(frame) {
+                FrameExit::Call(reason, out) => (reason, out),
+                FrameExit::Create(..) => unreachable!("call frames exit as calls"),
+            },
+        }
+    }
+
+    /// Run a create started by a transaction to completion.
+    fn create_result(
+        &mut self,
+        exit: Capture<(ExitReason, Option<H160>, Vec<u8>), StackFrame<'backend, 'config, B>>,
+    ) -> (ExitReason, Option<H160>) {
+        match exit {
+            Capture::Exit((reason, address, _)) => (reason, address),
+            Capture::Trap(frame) => match self.run_frames(frame) {
+                FrameExit::Create(reason, address, _) => (reason, address),
+                FrameExit::Call(..) => unreachable!("create frames exit as creates"),
+            },
+        }
+    }
+
+    /// Deploy `init_code` from `caller` in the current frame, returning the
+    /// exit reason and the address of the created contract.
+    pub fn deploy(&mut self, caller: H160, init_code: Vec<u8>) -> (ExitReason, Option<H160>) {
+        let scheme = CreateScheme::Legacy { caller };
+        let exit = self.create_inner(caller, scheme, U256::zero(), init_code, None, true);
+        self.create_result(exit)
+    }
+
+    /// Reset per-transaction state, then mark the sender, target, coinbase,
+    /// precompiles and access list of a transaction as warm (EIP-2929,
+    /// EIP-2930, EIP-3651).
+    fn warm_transaction(
+        &mut self,
+        caller: H160,
+        address: Option<H160>,
+        access_list: Vec<(H160, Vec<H256>)>,
+    ) {
+        self.accessed_addresses.clear();
+        self.accessed_storage.clear();
+        self.transient_storage.clear();
+        let _ = self.backend.take_error();
+
+        self.accessed_addresses.insert(caller);
+        if self.config.warm_coinbase_address {
+            self.accessed_addresses.insert(self.block_coinbase());
+        }
+        if let Some(address) = address {
+            self.accessed_addresses.insert(address);
+        }
+        for i in 1..=9 {
+            let precompile = H160::from_low_u64_be(i);
+            if precompiles::is_standard(precompile, self.config) {
+                self.accessed_addresses.insert(precompile);
+            }
+        }
+        for (address, keys) in access_list {
+            self.accessed_addresses.insert(address);
+            for key in keys {
+                self.accessed_storage.insert((address, key));
+            }
         }
     }
 
@@ -411,7 +1092,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
     pub fn used_gas(&self) -> usize {
         self.gasometer.total_used_gas()
             - min(
-                self.gasometer.total_used_gas() / 2,
+                self.gasometer.total_used_gas() / self.config.max_refund_quotient,
                 self.gasometer.refunded_gas() as usize,
             )
     }
@@ -509,7 +1190,8 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         (applies, logs, txs, self.created_contracts)
     }
 
-    /// Get mutable account reference.
+    /// Get mutable account reference. Changes made through it are not
+    /// journaled.
     pub fn account_mut(&mut self, address: H160) -> &mut StackAccount {
         if self.state.contains_key(&address) {
             self.state.get_mut(&address).unwrap()
@@ -530,6 +1212,52 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         }
     }
 
+    /// Get mutable balance and nonce of an account, restored if the call frame
+    /// reverts.
+    fn basic_mut(&mut self, address: H160) -> &mut Basic {
+        let basic = self.account_mut(address).basic.clone();
+        self.journal.push(JournalEntry::Basic { address, basic });
+        &mut self.account_mut(address).basic
+    }
+
+    /// Clear the storage of an account about to be created.
+    fn reset_storage(&mut self, address: H160) {
+        let account = self.account_mut(address);
+        let storage = mem::take(&mut account.storage);
+        let reset_storage = mem::replace(&mut account.reset_storage, true);
+        self.journal.push(JournalEntry::ResetStorage {
+            address,
+            storage,
+            reset_storage,
+        });
+    }
+
+    /// Set the code of an account.
+    pub fn set_code(&mut self, address: H160, code: Vec<u8>) {
+        let account = self.account_mut(address);
+        let entry = JournalEntry::Code {
+            address,
+            code: account.code.replace(code.clone()),
+            original_code: account.original_code.replace(code),
+        };
+        self.journal.push(entry);
+    }
+
+    /// Mark an address as accessed (EIP-2929).
+    fn access_address(&mut self, address: H160) {
+        if self.accessed_addresses.insert(address) {
+            self.journal.push(JournalEntry::AccessedAddress(address));
+        }
+    }
+
+    /// Mark a storage slot as accessed (EIP-2929).
+    fn access_storage(&mut self, address: H160, index: H256) {
+        if self.accessed_storage.insert((address, index)) {
+            self.journal
+                .push(JournalEntry::AccessedStorage(address, index));
+        }
+    }
+
     /// Get account nonce.
     pub fn nonce(&mut self, address: H160) -> U256 {
         if self.state.contains_key(&address) {
@@ -553,19 +1281,17 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
 
     /// Withdraw balance from address.
     pub fn withdraw(&mut self, address: H160, balance: U256) -> Result<(), ExitError> {
-        let source = self.account_mut(address);
-        if source.basic.balance < balance {
+        if self.account_mut(address).basic.balance < balance {
             return Err(ExitError::OutOfFund);
         }
-        source.basic.balance -= balance;
+        self.basic_mut(address).balance -= balance;
 
         Ok(())
     }
 
     /// Deposit balance to address.
     pub fn deposit(&mut self, address: H160, balance: U256) {
-        let target = self.account_mut(address);
-        target.basic.balance += balance;
+        self.basic_mut(address).balance += balance;
     }
 
     /// Transfer balance with the given struct.
@@ -602,6 +1328,68 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         }
     }
 
+    /// Capture the state for the `snapshot` cheatcode, returning its id.
+    pub fn snapshot(&mut self) -> usize {
+        self.snapshots.push(Snapshot {
+            state: self.state.clone(),
+            deleted: self.deleted.clone(),
+            created_contracts: self.created_contracts.clone(),
+            tmp_bn: self.tmp_bn,
+            tmp_timestamp: self.tmp_timestamp,
+            logs: self.logs.clone(),
+            owned_logs: self.owned_logs.clone(),
+            accessed_addresses: self.accessed_addresses.clone(),
+            accessed_storage: self.accessed_storage.clone(),
+            transient_storage: self.transient_storage.clone(),
+            journal: self.journal.clone(),
+        });
+        self.snapshots.len() - 1
+    }
+
+    /// Restore the state captured by the `snapshot` cheatcode with id `id`,
+    /// returning whether it exists. The snapshot can be restored again.
+    pub fn revert_to_snapshot(&mut self, id: U256) -> bool {
+        if id >= U256::from(self.snapshots.len()) {
+            return false;
+        }
+        let snapshot = self.snapshots[id.as_usize()].clone();
+        self.state = snapshot.state;
+        self.deleted = snapshot.deleted;
+        self.created_contracts = snapshot.created_contracts;
+        self.tmp_bn = snapshot.tmp_bn;
+        self.tmp_timestamp = snapshot.tmp_timestamp;
+        self.logs = snapshot.logs;
+        self.owned_logs = snapshot.owned_logs;
+        self.accessed_addresses = snapshot.accessed_addresses;
+        self.accessed_storage = snapshot.accessed_storage;
+        self.transient_storage = snapshot.transient_storage;
+        self.journal = snapshot.journal;
+        true
+    }
+
+    /// Mocked return data for a call to `address` with `input`, from the
+    /// mock with the longest matching calldata prefix.
+    fn mocked_call(&self, address: H160, input: &[u8]) -> Option<Vec<u8>> {
+        self.mocked_calls
+            .get(&address)?
+            .iter()
+            .filter(|(calldata, _)| input.starts_with(calldata))
+            .max_by_key(|(calldata, _)| calldata.len())
+            .map(|(_, ret)| ret.clone())
+    }
+
+    /// The prank applying to a call or create made by `caller`. A single-use
+    /// prank is used up.
+    fn use_prank(&mut self, caller: H160) -> Option<Prank> {
+        let prank = self.prank.clone().filter(|p| p.caller == caller)?;
+        if prank.single {
+            self.prank = None;
+        }
+        Some(prank)
+    }
+
+    /// Start a create, returning the frame that runs the init code unless the
+    /// create ends early.
     fn create_inner(
         &mut self,
         caller: H160,
@@ -610,8 +1398,27 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         init_code: Vec<u8>,
         target_gas: Option<usize>,
         take_l64: bool,
-    ) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Infallible> {
+    ) -> Capture<(ExitReason, Option<H160>, Vec<u8>), StackFrame<'backend, 'config, B>> {
         let mut calltrace = CallTrace::default();
+
+        let mut caller = caller;
+        let mut scheme = scheme;
+        let mut tmp_origin = self.tmp_origin;
+        if let Some(prank) = self.use_prank(caller) {
+            caller = prank.sender;
+            scheme = match scheme {
+                CreateScheme::Legacy { .. } => CreateScheme::Legacy { caller },
+                CreateScheme::Create2 {
+                    code_hash, salt, ..
+                } => CreateScheme::Create2 {
+                    caller,
+                    code_hash,
+                    salt,
+                },
+                CreateScheme::Fixed(address) => CreateScheme::Fixed(address),
+            };
+            tmp_origin = prank.origin.or(tmp_origin);
+        }
         macro_rules! try_or_fail {
             ( $e:expr ) => {
                 match $e {
@@ -646,23 +1453,25 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         try_or_fail!(self.gasometer.record_cost(gas_limit));
 
         let address = self.create_address(scheme);
+        calltrace.addr = address;
+        calltrace.created = true;
+        calltrace.input = hex::encode(&init_code);
 
-        self.created_contracts.insert(address);
-
-        println!("Created address: {:?}", address);
+        if self.created_contracts.insert(address) {
+            self.journal.push(JournalEntry::Created(address));
+        }
+        self.access_address(address);
 
-        self.account_mut(caller).basic.nonce += U256::one();
+        self.basic_mut(caller).nonce += U256::one();
 
         let mut substate = self.substate(gas_limit, false);
+        substate.tmp_origin = tmp_origin;
         {
             // already exists
             if let Some(code) = substate.account_mut(address).code.as_ref() {
                 if !code.is_empty() {
                     calltrace.success = false;
-                    calltrace.addr = address;
-                    calltrace.created = true;
                     calltrace.cost = substate.used_gas();
-                    calltrace.input = hex::encode(init_code);
                     calltrace.inner.append(&mut substate.call_trace);
                     let _ = self.merge_fail(substate, calltrace);
                     return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
@@ -674,10 +1483,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
 
                 if !code.is_empty() {
                     calltrace.success = false;
-                    calltrace.addr = address;
-                    calltrace.created = true;
                     calltrace.cost = substate.used_gas();
-                    calltrace.input = hex::encode(init_code);
                     calltrace.inner.append(&mut substate.call_trace);
                     let _ = self.merge_fail(substate, calltrace);
                     return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
@@ -686,17 +1492,13 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             // is a wallet
             if substate.account_mut(address).basic.nonce > U256::zero() {
                 calltrace.success = false;
-                calltrace.addr = address;
-                calltrace.created = true;
                 calltrace.cost = substate.used_gas();
-                calltrace.input = hex::encode(init_code);
                 calltrace.inner.append(&mut substate.call_trace);
                 let _ = self.merge_fail(substate, calltrace);
                 return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
             }
 
-            substate.account_mut(address).reset_storage = true;
-            substate.account_mut(address).storage = BTreeMap::new();
+            substate.reset_storage(address);
         }
 
         let context = Context {
@@ -713,10 +1515,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             Ok(()) => (),
             Err(e) => {
                 calltrace.success = false;
-                calltrace.addr = address;
-                calltrace.created = true;
                 calltrace.cost = substate.used_gas();
-                calltrace.input = hex::encode(init_code);
                 calltrace.inner.append(&mut substate.call_trace);
                 let _ = self.merge_revert(substate, calltrace);
                 return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
@@ -724,121 +1523,28 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         }
 
         if self.config.create_increase_nonce {
-            substate.account_mut(address).basic.nonce += U256::one();
+            substate.basic_mut(address).nonce += U256::one();
         }
 
-        let mut runtime = Runtime::new(
-            Rc::new(init_code.clone()),
+        let runtime = Runtime::new(
+            Rc::new(init_code),
             Rc::new(Vec::new()),
             context,
             self.config,
         );
 
-        let reason = substate.execute(&mut runtime);
-
-        match reason {
-            ExitReason::Succeed(s) => {
-                let out = runtime.machine().return_value();
-
-                if let Some(limit) = self.config.create_contract_limit {
-                    if out.len() > limit {
-                        calltrace.success = false;
-                        calltrace.addr = address;
-                        calltrace.created = true;
-                        substate.gasometer.fail();
-                        calltrace.cost = substate.used_gas();
-                        calltrace.input = hex::encode(init_code);
-                        calltrace.output = hex::encode(runtime.machine.return_value());
-                        calltrace.inner.append(&mut substate.call_trace);
-                        let _ = self.merge_fail(substate, calltrace);
-                        return Capture::Exit((
-                            ExitError::CreateContractLimit.into(),
-                            None,
-                            Vec::new(),
-                        ));
-                    }
-                }
-
-                match substate.gasometer.record_deposit(out.len()) {
-                    Ok(()) => {
-                        calltrace.success = true;
-                        calltrace.addr = address;
-                        calltrace.created = true;
-                        calltrace.cost = substate.used_gas();
-                        calltrace.input = hex::encode(init_code);
-                        calltrace.output = hex::encode(runtime.machine.return_value());
-                        calltrace.inner.append(&mut substate.call_trace);
-                        let e = self.merge_succeed(substate, calltrace);
-
-                        if self.state.contains_key(&address) {
-                            let acct = self.state.get_mut(&address).unwrap();
-                            acct.code = Some(out);
-                            acct.original_code = acct.code.clone();
-                            *acct = acct.clone();
-                        } else {
-                            let b = self.backend.basic(address);
-                            let acct = StackAccount {
-                                basic: b.clone(),
-                                code: Some(out.clone()),
-                                storage: BTreeMap::new(),
-                                original_storage: BTreeMap::new(),
-                                original_code: Some(out),
-                                original_basic: b,
-                                reset_storage: false,
-                                reset_storage_backend: false,
-                            };
-                            self.state.insert(address, acct);
-                        }
-                        try_or_fail!(e);
-                        Capture::Exit((ExitReason::Succeed(s), Some(address), Vec::new()))
-                    }
-                    Err(e) => {
-                        calltrace.success = false;
-                        calltrace.addr = address;
-                        calltrace.created = true;
-                        calltrace.cost = substate.used_gas();
-                        calltrace.input = hex::encode(init_code);
-                        calltrace.output = hex::encode(runtime.machine.return_value());
-                        calltrace.inner.append(&mut substate.call_trace);
-                        let _ = self.merge_fail(substate, calltrace);
-                        Capture::Exit((ExitReason::Error(e), None, Vec::new()))
-                    }
-                }
-            }
-            ExitReason::Error(e) => {
-                calltrace.success = false;
-                calltrace.addr = address;
-                calltrace.created = true;
-                substate.gasometer.fail();
-                calltrace.cost = substate.used_gas();
-                calltrace.input = hex::encode(init_code);
-                calltrace.output = hex::encode(runtime.machine.return_value());
-                calltrace.inner.append(&mut substate.call_trace);
-                let _ = self.merge_fail(substate, calltrace);
-                Capture::Exit((ExitReason::Error(e), None, Vec::new()))
-            }
-            ExitReason::Revert(e) => {
-                calltrace.success = false;
-                calltrace.addr = address;
-                calltrace.created = true;
-                calltrace.cost = substate.used_gas();
-                calltrace.input = hex::encode(init_code);
-                calltrace.output = hex::encode(runtime.machine.return_value());
-                calltrace.inner.append(&mut substate.call_trace);
-                let _ = self.merge_revert(substate, calltrace);
-                Capture::Exit((
-                    ExitReason::Revert(e),
-                    None,
-                    runtime.machine().return_value(),
-                ))
-            }
-            ExitReason::Fatal(e) => {
-                self.gasometer.fail();
-                Capture::Exit((ExitReason::Fatal(e), None, Vec::new()))
-            }
-        }
+        Capture::Trap(StackFrame {
+            executor: substate,
+            runtime,
+            calltrace,
+            address: Some(address),
+            expected_revert: None,
+            expected_emit: None,
+        })
     }
 
+    /// Start a call, returning the frame that runs the callee's code unless
+    /// the call ends early.
     fn call_inner(
         &mut self,
         code_address: H160,
@@ -849,83 +1555,38 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         take_l64: bool,
         take_stipend: bool,
         context: Context,
-    ) -> Capture<(ExitReason, Vec<u8>), Infallible> {
+    ) -> Capture<(ExitReason, Vec<u8>), StackFrame<'backend, 'config, B>> {
         let mut calltrace = CallTrace::default();
 
-        let mut forced_ret = H256::zero();
-        let mut is_forced_ret = false;
-        if code_address == "7109709ECfa91a80626fF3989D68f67F5b1DD12D".parse().unwrap() {
-            let sig = hex::encode([input[0], input[1], input[2], input[3]]);
-            match sig {
-                // roll
-                _ if sig == *"1f7b4f30" => {
-                    let amount = U256::from_big_endian(&input[4..]);
-                    self.tmp_bn = Some(amount);
-                }
-                // warp
-                _ if sig == *"e5d6bf02" => {
-                    let timestamp = U256::from_big_endian(&input[4..]);
-                    self.tmp_timestamp = Some(timestamp);
+        let is_cheatcode = code_address == CHEATCODE_ADDRESS;
+        let mut context = context;
+        let mut transfer = transfer;
+        let mut tmp_origin = self.tmp_origin;
+        if !is_cheatcode {
+            if let Some(prank) = self.use_prank(context.caller) {
+                context.caller = prank.sender;
+                if let Some(transfer) = transfer.as_mut() {
+                    transfer.source = prank.sender;
                 }
-                // store
-                _ if sig == *"70ca10bb" => {
-                    let who = H160::from_slice(&input[16..36]);
-                    let slot = H256::from_slice(&input[36..68]);
-                    let val = H256::from_slice(&input[68..]);
-                    if self.state.contains_key(&who) {
-                        let acct = self.state.get_mut(&who).unwrap();
-                        acct.storage.insert(slot, val);
-                        acct.reset_storage_backend = false;
-                        *acct = acct.clone();
-                    } else {
-                        let b = self.backend.basic(who);
-                        let code = self.backend.code(who);
-                        let mut acct = StackAccount {
-                            basic: b.clone(),
-                            code: Some(code.clone()),
-                            storage: BTreeMap::new(),
-                            original_storage: BTreeMap::new(),
-                            original_code: Some(code),
-                            original_basic: b,
-                            reset_storage: false,
-                            reset_storage_backend: false,
-                        };
-                        acct.storage.insert(slot, val);
-                        self.state.insert(who, acct);
-                    }
-                }
-                // load
-                _ if sig == *"667f9d70" => {
-                    let who = H160::from_slice(&input[16..36]);
-                    let slot = H256::from_slice(&input[36..68]);
-                    forced_ret = self.storage(who, slot);
-                    is_forced_ret = true;
-                }
-                // load
-                _ if sig == *"47e7ef24" => {
-                    let who = H160::from_slice(&input[16..36]);
-                    let amount = U256::from_big_endian(&input[36..68]);
-                    if self.state.contains_key(&who) {
-                        let acct = self.state.get_mut(&who).unwrap();
-                        acct.basic.balance = acct.basic.balance + amount;
-                        *acct = acct.clone();
-                    } else {
-                        let mut b = self.backend.basic(who);
-                        b.balance = b.balance + amount;
-                        let acct = StackAccount {
-                            basic: b.clone(),
-                            code: None,
-                            storage: BTreeMap::new(),
-                            original_storage: BTreeMap::new(),
-                            original_code: None,
-                            original_basic: b,
-                            reset_storage: false,
-                            reset_storage_backend: false,
-                        };
-                        self.state.insert(who, acct);
-                    }
+                tmp_origin = prank.origin.or(tmp_origin);
+            }
+        }
+
+        let mut forced_ret: Option<Vec<u8>> = None;
+        if is_cheatcode {
+            let handler = match self.cheatcodes.get(&input) {
+                Some(cheatcode) => cheatcode.handler,
+                None => {
+                    let selector = hex::encode(&input[..min(input.len(), 4)]);
+                    return Capture::Exit((
+                        ExitRevert::Reverted.into(),
+                        revert_message(&format!("unknown cheatcode {}", selector)),
+                    ));
                 }
-                _ => {}
+            };
+            match handler(self, &context, &input) {
+                Ok(ret) => forced_ret = Some(ret),
+                Err(exit) => return Capture::Exit(exit),
             }
         }
 
@@ -961,6 +1622,7 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         let code = self.code(code_address);
 
         let mut substate = self.substate(gas_limit, is_static);
+        substate.tmp_origin = tmp_origin;
         substate.account_mut(context.address);
 
         let mut sig: [u8; 4] = Default::default();
@@ -974,14 +1636,14 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
         } else {
             i = hex::encode(Vec::new());
         }
+        calltrace.addr = code_address;
+        calltrace.value = transfer.as_ref().map(|t| t.value).unwrap_or_default();
+        calltrace.function = hex::encode(sig);
+        calltrace.input = i;
 
         if let Some(depth) = self.depth {
             if depth + 1 > self.config.call_stack_limit {
                 calltrace.success = false;
-                calltrace.addr = code_address;
-                calltrace.created = false;
-                calltrace.function = hex::encode(sig);
-                calltrace.input = i;
                 calltrace.cost = substate.used_gas();
                 calltrace.inner.append(&mut substate.call_trace);
                 let _ = self.merge_revert(substate, calltrace);
@@ -994,10 +1656,6 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
                 Ok(()) => (),
                 Err(e) => {
                     calltrace.success = false;
-                    calltrace.addr = code_address;
-                    calltrace.created = false;
-                    calltrace.function = hex::encode(sig);
-                    calltrace.input = i;
                     calltrace.cost = substate.used_gas();
                     calltrace.inner.append(&mut substate.call_trace);
                     let _ = self.merge_revert(substate, calltrace);
@@ -1006,26 +1664,37 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
             }
         }
 
-        if let Some(ret) = (substate.precompile)(code_address, &input, Some(gas_limit)) {
+        // Mocked calls and precompiles exit without running any code, so the
+        // reads above are the only chance to see the provider fail.
+        if let Some(e) = self.backend.error() {
+            calltrace.success = false;
+            let _ = self.merge_fail(substate, calltrace);
+            return Capture::Exit((ExitFatal::ProviderError(e.method()).into(), Vec::new()));
+        }
+
+        if let Some(out) = substate.mocked_call(code_address, &input) {
+            calltrace.success = true;
+            calltrace.mocked = true;
+            calltrace.cost = substate.used_gas();
+            calltrace.output = hex::encode(&out);
+            let _ = self.merge_succeed(substate, calltrace);
+            return Capture::Exit((ExitSucceed::Returned.into(), out));
+        }
+
+        if let Some(ret) = (substate.precompile)(code_address, &input, Some(gas_limit), self.config)
+        {
             return match ret {
                 Ok((s, out, cost)) => {
+                    let _ = substate.gasometer.record_cost(cost);
                     calltrace.success = true;
-                    calltrace.addr = code_address;
-                    calltrace.created = false;
-                    calltrace.function = hex::encode(sig);
-                    calltrace.input = i;
                     calltrace.cost = substate.used_gas();
+                    calltrace.output = hex::encode(&out);
                     calltrace.inner.append(&mut substate.call_trace);
-                    let _ = substate.gasometer.record_cost(cost);
                     let _ = self.merge_succeed(substate, calltrace);
                     Capture::Exit((ExitReason::Succeed(s), out))
                 }
                 Err(e) => {
                     calltrace.success = false;
-                    calltrace.addr = code_address;
-                    calltrace.created = false;
-                    calltrace.function = hex::encode(sig);
-                    calltrace.input = i;
                     calltrace.cost = substate.used_gas();
                     calltrace.inner.append(&mut substate.call_trace);
                     let _ = self.merge_fail(substate, calltrace);
@@ -1036,68 +1705,195 @@ impl<'backend, 'config, B: Backend> StackExecutor<'backend, 'config, B> {
 
         let mut runtime = Runtime::new(Rc::new(code), Rc::new(input), context, self.config);
 
-        if code_address == "7109709ECfa91a80626fF3989D68f67F5b1DD12D".parse().unwrap()
-            && is_forced_ret
-        {
-            runtime.machine.return_range = U256::zero()..U256::from(32);
-            runtime
-                .machine
-                .memory
-                .set(0, forced_ret.as_bytes(), None)
-                .unwrap();
+        if let Some(ret) = forced_ret {
+            runtime.machine.return_range = U256::zero()..U256::from(ret.len());
+            runtime.machine.memory.set(0, &ret, None).unwrap();
         }
 
-        let reason = substate.execute(&mut runtime);
+        Capture::Trap(StackFrame {
+            executor: substate,
+            runtime,
+            calltrace,
+            address: None,
+            expected_revert: None,
+            expected_emit: None,
+        })
+    }
 
+    /// Finish a frame that exited with `reason`, merging its substate into
+    /// `self`.
+    fn exit_frame(
+        &mut self,
+        frame: StackFrame<'backend, 'config, B>,
+        reason: ExitReason,
+    ) -> FrameExit {
+        let StackFrame {
+            executor: mut substate,
+            runtime,
+            mut calltrace,
+            address,
+            expected_revert,
+            expected_emit,
+        } = frame;
+        if !matches!(reason, ExitReason::Fatal(_)) {
+            calltrace.cost = substate.used_gas();
+            calltrace.output = hex::encode(runtime.machine.return_value());
+        }
+
+        let address = match address {
+            Some(address) => address,
+            None => {
+                let mut reason = reason;
+                let mut out = runtime.machine().return_value();
+                // An expectation still pending when its frame exits was never
+                // followed by a call.
+                let unused = if substate.expected_revert.is_some() {
+                    Some("expectRevert was not followed by a call")
+                } else if substate.expected_emit.is_some() {
+                    Some("expectEmit was not followed by a call")
+                } else {
+                    None
+                };
+                if let (Some(message), true) = (unused, reason.is_succeed()) {
+                    reason = ExitRevert::Reverted.into();
+                    out = revert_message(message);
+                }
+                for expected in mem::take(&mut substate.expected_calls) {
+                    let checked = expected.check(&substate.call_trace, reason, out);
+                    reason = checked.0;
+                    out = checked.1;
+                }
+                let (reason, out) = match expected_emit {
+                    Some(expected) => expected.check(&substate.logs, reason, out),
+                    None => (reason, out),
+                };
+                if !matches!(reason, ExitReason::Fatal(_)) {
+                    calltrace.output = hex::encode(&out);
+                }
+                let (reason, out) = match reason {
+                    ExitReason::Succeed(s) => {
+                        calltrace.success = true;
+                        calltrace.inner.append(&mut substate.call_trace);
+                        let _ = self.merge_succeed(substate, calltrace);
+                        (ExitReason::Succeed(s), out)
+                    }
+                    ExitReason::Error(e) => {
+                        calltrace.inner.append(&mut substate.call_trace);
+                        let _ = self.merge_fail(substate, calltrace);
+                        (ExitReason::Error(e), Vec::new())
+                    }
+                    ExitReason::Revert(e) => {
+                        calltrace.inner.append(&mut substate.call_trace);
+                        let _ = self.merge_revert(substate, calltrace);
+                        (ExitReason::Revert(e), out)
+                    }
+                    ExitReason::Fatal(e) => {
+                        self.exit_substate(&mut substate, false);
+                        self.gasometer.fail();
+                        (ExitReason::Fatal(e), Vec::new())
+                    }
+                };
+                return match expected_revert {
+                    Some(expected) => {
+                        let (reason, out) = expected.check(reason, out);
+                        FrameExit::Call(reason, out)
+                    }
+                    None => FrameExit::Call(reason, out),
+                };
+            }
+        };
+
+        let out = runtime.machine().return_value();
+        let (reason, out) = match expected_emit {
+            Some(expected) => expected.check(&substate.logs, reason, out),
+            None => (reason, out),
+        };
+        if !matches!(reason, ExitReason::Fatal(_)) {
+            calltrace.output = hex::encode(&out);
+        }
+        let (reason, address, out) = self.exit_create(substate, calltrace, address, reason, out);
+        match expected_revert {
+            Some(expected) => {
+                let (reason, address, out) = expected.check_create(reason, out);
+                FrameExit::Create(reason, address, out)
+            }
+            None => FrameExit::Create(reason, address, out),
+        }
+    }
+
+    /// Finish a create frame that exited with `reason` and returned `out`,
+    /// deploying the code on success.
+    fn exit_create(
+        &mut self,
+        mut substate: StackExecutor<'backend, 'config, B>,
+        mut calltrace: CallTrace,
+        address: H160,
+        reason: ExitReason,
+        out: Vec<u8>,
+    ) -> (ExitReason, Option<H160>, Vec<u8>) {
         match reason {
             ExitReason::Succeed(s) => {
-                calltrace.success = true;
-                calltrace.addr = code_address;
-                calltrace.created = false;
-                calltrace.function = hex::encode(sig);
-                calltrace.input = i;
-                calltrace.cost = substate.used_gas();
-                calltrace.output = hex::encode(runtime.machine.return_value());
-                calltrace.inner.append(&mut substate.call_trace);
-                let _ = self.merge_succeed(substate, calltrace);
-                Capture::Exit((ExitReason::Succeed(s), runtime.machine().return_value()))
+                let error = if matches!(self.config.create_contract_limit, Some(limit) if out.len() > limit)
+                {
+                    Some(ExitError::CreateContractLimit)
+                } else if self.config.disallow_executable_format && out.first() == Some(&0xef) {
+                    Some(ExitError::InvalidCode)
+                } else {
+                    None
+                };
+                if let Some(e) = error {
+                    substate.gasometer.fail();
+                    calltrace.cost = substate.used_gas();
+                    calltrace.inner.append(&mut substate.call_trace);
+                    let _ = self.merge_fail(substate, calltrace);
+                    return (e.into(), None, Vec::new());
+                }
+
+                match substate.gasometer.record_deposit(out.len()) {
+                    Ok(()) => {
+                        calltrace.success = true;
+                        calltrace.cost = substate.used_gas();
+                        calltrace.inner.append(&mut substate.call_trace);
+                        let e = self.merge_succeed(substate, calltrace);
+                        self.set_code(address, out);
+                        match e {
+                            Ok(()) => (ExitReason::Succeed(s), Some(address), Vec::new()),
+                            Err(e) => (e.into(), None, Vec::new()),
+                        }
+                    }
+                    Err(e) => {
+                        calltrace.cost = substate.used_gas();
+                        calltrace.inner.append(&mut substate.call_trace);
+                        let _ = self.merge_fail(substate, calltrace);
+                        (ExitReason::Error(e), None, Vec::new())
+                    }
+                }
             }
             ExitReason::Error(e) => {
-                calltrace.success = false;
-                calltrace.addr = code_address;
-                calltrace.created = false;
-                calltrace.function = hex::encode(sig);
-                calltrace.input = i;
+                substate.gasometer.fail();
                 calltrace.cost = substate.used_gas();
-                calltrace.output = hex::encode(runtime.machine.return_value());
                 calltrace.inner.append(&mut substate.call_trace);
                 let _ = self.merge_fail(substate, calltrace);
-                Capture::Exit((ExitReason::Error(e), Vec::new()))
+                (ExitReason::Error(e), None, Vec::new())
             }
             ExitReason::Revert(e) => {
-                calltrace.success = false;
-                calltrace.addr = code_address;
-                calltrace.created = false;
-                calltrace.function = hex::encode(sig);
-                calltrace.input = i;
-                calltrace.cost = substate.used_gas();
-                calltrace.output = hex::encode(runtime.machine.return_value());
                 calltrace.inner.append(&mut substate.call_trace);
                 let _ = self.merge_revert(substate, calltrace);
-                Capture::Exit((ExitReason::Revert(e), runtime.machine().return_value()))
+                (ExitReason::Revert(e), None, out)
             }
             ExitReason::Fatal(e) => {
+                self.exit_substate(&mut substate, false);
                 self.gasometer.fail();
-                Capture::Exit((ExitReason::Fatal(e), Vec::new()))
+                (ExitReason::Fatal(e), None, Vec::new())
             }
         }
     }
 }
 
 impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config, B> {
-    type CreateInterrupt = Infallible;
+    type CreateInterrupt = StackFrame<'backend, 'config, B>;
     type CreateFeedback = Infallible;
-    type CallInterrupt = Infallible;
+    type CallInterrupt = StackFrame<'backend, 'config, B>;
     type CallFeedback = Infallible;
 
     fn balance(&mut self, address: H160) -> U256 {
@@ -1122,7 +1918,7 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
 
     #[allow(clippy::map_entry)]
     fn code_size(&mut self, address: H160) -> U256 {
-        if address == "7109709ECfa91a80626fF3989D68f67F5b1DD12D".parse().unwrap() {
+        if address == CHEATCODE_ADDRESS {
             return U256::from(100);
         }
         if self.state.contains_key(&address) {
@@ -1219,6 +2015,9 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
 
     #[allow(clippy::map_entry)]
     fn storage(&mut self, address: H160, index: H256) -> H256 {
+        if let Some(recorded) = &mut self.recorded_accesses {
+            recorded.reads.entry(address).or_default().push(index);
+        }
         if self.state.contains_key(&address) {
             let acct = self.state.get_mut(&address).unwrap();
             if let Some(storage_data) = acct.storage.get(&index) {
@@ -1256,6 +2055,13 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         }
     }
 
+    fn transient_storage(&self, address: H160, index: H256) -> H256 {
+        self.transient_storage
+            .get(&(address, index))
+            .cloned()
+            .unwrap_or_default()
+    }
+
     fn original_storage(&mut self, address: H160, index: H256) -> H256 {
         if let Some(account) = self.state.get_mut(&address) {
             if account.reset_storage {
@@ -1298,7 +2104,11 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         } else if let Some(account) = self.state.get(&address) {
             account.basic.nonce != U256::zero()
                 || account.basic.balance != U256::zero()
-                || account.code.as_ref().map(|c| !c.is_empty()).unwrap_or(false)
+                || account
+                    .code
+                    .as_ref()
+                    .map(|c| !c.is_empty())
+                    .unwrap_or(false)
                 || !self.backend.code(address).is_empty()
         } else {
             self.backend.basic(address).nonce != U256::zero()
@@ -1315,7 +2125,7 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         self.backend.gas_price()
     }
     fn origin(&self) -> H160 {
-        self.backend.origin()
+        self.tmp_origin.unwrap_or_else(|| self.backend.origin())
     }
     fn block_hash(&self, number: U256) -> H256 {
         self.backend.block_hash(number)
@@ -1343,6 +2153,12 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
     fn block_gas_limit(&self) -> U256 {
         self.backend.block_gas_limit()
     }
+    fn block_base_fee_per_gas(&self) -> U256 {
+        self.backend.block_base_fee_per_gas()
+    }
+    fn block_randomness(&self) -> Option<H256> {
+        self.backend.block_randomness()
+    }
     fn chain_id(&self) -> U256 {
         self.backend.chain_id()
     }
@@ -1351,13 +2167,56 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         self.deleted.contains(&address)
     }
 
+    fn is_cold(&self, address: H160, index: Option<H256>) -> bool {
+        match index {
+            Some(index) => !self.accessed_storage.contains(&(address, index)),
+            None => !self.accessed_addresses.contains(&address),
+        }
+    }
+
     fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
-        self.account_mut(address).storage.insert(index, value);
+        if let Some(recorded) = &mut self.recorded_accesses {
+            recorded.writes.entry(address).or_default().push(index);
+        }
+        let value = self.account_mut(address).storage.insert(index, value);
+        self.journal.push(JournalEntry::Storage {
+            address,
+            index,
+            value,
+        });
+
+        Ok(())
+    }
+
+    fn set_transient_storage(
+        &mut self,
+        address: H160,
+        index: H256,
+        value: H256,
+    ) -> Result<(), ExitError> {
+        let value = self.transient_storage.insert((address, index), value);
+        self.journal.push(JournalEntry::TransientStorage {
+            address,
+            index,
+            value,
+        });
 
         Ok(())
     }
 
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        // The first log after `expectEmit` is the expected log, not a real one.
+        if let Some(expected) = self.expected_emit.as_mut() {
+            if expected.caller == address && expected.log.is_none() {
+                expected.log = Some(Log {
+                    address,
+                    topics,
+                    data,
+                });
+                return Ok(());
+            }
+        }
+
         self.owned_logs.push(Log {
             address: address.clone(),
             topics: topics.clone(),
@@ -1380,9 +2239,11 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
             target,
             value: balance,
         })?;
-        self.account_mut(address).basic.balance = U256::zero();
+        self.basic_mut(address).balance = U256::zero();
 
-        self.deleted.insert(address);
+        if self.deleted.insert(address) {
+            self.journal.push(JournalEntry::Deleted(address));
+        }
 
         Ok(())
     }
@@ -1395,7 +2256,33 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         init_code: Vec<u8>,
         target_gas: Option<usize>,
     ) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Self::CreateInterrupt> {
-        self.create_inner(caller, scheme, value, init_code, target_gas, true)
+        let expected_revert = match &self.expected_revert {
+            Some(expected) if expected.caller == caller => self.expected_revert.take(),
+            _ => None,
+        };
+        let expected_emit = match &self.expected_emit {
+            Some(expected) if expected.caller == caller && expected.log.is_some() => {
+                self.expected_emit.take()
+            }
+            _ => None,
+        };
+        match self.create_inner(caller, scheme, value, init_code, target_gas, true) {
+            Capture::Exit((reason, address, out)) => {
+                let (reason, out) = match expected_emit {
+                    Some(expected) => expected.check(&[], reason, out),
+                    None => (reason, out),
+                };
+                match expected_revert {
+                    Some(expected) => Capture::Exit(expected.check_create(reason, out)),
+                    None => Capture::Exit((reason, address, out)),
+                }
+            }
+            Capture::Trap(mut frame) => {
+                frame.expected_revert = expected_revert;
+                frame.expected_emit = expected_emit;
+                Capture::Trap(frame)
+            }
+        }
     }
 
     fn call(
@@ -1407,7 +2294,22 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         is_static: bool,
         context: Context,
     ) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
-        self.call_inner(
+        let is_cheatcode = code_address == CHEATCODE_ADDRESS;
+        let expected_revert = match &self.expected_revert {
+            Some(expected) if !is_cheatcode && expected.caller == context.caller => {
+                self.expected_revert.take()
+            }
+            _ => None,
+        };
+        let expected_emit = match &self.expected_emit {
+            Some(expected)
+                if !is_cheatcode && expected.caller == context.caller && expected.log.is_some() =>
+            {
+                self.expected_emit.take()
+            }
+            _ => None,
+        };
+        match self.call_inner(
             code_address,
             transfer,
             input,
@@ -1416,7 +2318,67 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
             true,
             true,
             context,
-        )
+        ) {
+            Capture::Exit((reason, out)) => {
+                let (reason, out) = match expected_emit {
+                    Some(expected) => expected.check(&[], reason, out),
+                    None => (reason, out),
+                };
+                match expected_revert {
+                    Some(expected) => Capture::Exit(expected.check(reason, out)),
+                    None => Capture::Exit((reason, out)),
+                }
+            }
+            Capture::Trap(mut frame) => {
+                frame.expected_revert = expected_revert;
+                frame.expected_emit = expected_emit;
+                Capture::Trap(frame)
+            }
+        }
+    }
+
+    fn step_begin(
+        &mut self,
+        context: &Context,
+        pc: usize,
+        opcode: Result<Opcode, ExternalOpcode>,
+        machine: &Machine,
+    ) {
+        if let Some(inspector) = self.inspector.as_ref() {
+            inspector.borrow_mut().step(&StepInfo {
+                depth: self.depth.unwrap_or_default(),
+                address: context.address,
+                pc,
+                opcode,
+                stack: machine.stack(),
+                memory: machine.memory(),
+                gas: self.gasometer.gas(),
+            });
+        }
+    }
+
+    fn step_end(
+        &mut self,
+        context: &Context,
+        pc: usize,
+        opcode: Result<Opcode, ExternalOpcode>,
+        machine: &Machine,
+        exit: Option<ExitReason>,
+    ) {
+        if let Some(inspector) = self.inspector.as_ref() {
+            inspector.borrow_mut().step_end(
+                &StepInfo {
+                    depth: self.depth.unwrap_or_default(),
+                    address: context.address,
+                    pc,
+                    opcode,
+                    stack: machine.stack(),
+                    memory: machine.memory(),
+                    gas: self.gasometer.gas(),
+                },
+                exit,
+            );
+        }
     }
 
     fn pre_validate(
@@ -1425,7 +2387,13 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         opcode: Result<Opcode, ExternalOpcode>,
         stack: &Stack,
     ) -> Result<(), ExitError> {
-        let (gas_cost, memory_cost) = gasometer::opcode_cost(
+        // A failed backend read leaves the state invalid; stop the frame and
+        // let `execute` report it.
+        if self.backend.error().is_some() {
+            return Err(ExitError::Other("backend read failed"));
+        }
+
+        let (gas_cost, storage_target, memory_cost) = gasometer::opcode_cost(
             context.address,
             opcode,
             stack,
@@ -1435,6 +2403,12 @@ impl<'backend, 'config, B: Backend> Handler for StackExecutor<'backend, 'config,
         )?;
         self.gasometer.record_opcode(gas_cost, memory_cost)?;
 
+        match storage_target {
+            StorageTarget::Address(address) => self.access_address(address),
+            StorageTarget::Slot(address, index) => self.access_storage(address, index),
+            StorageTarget::None => (),
+        }
+
         Ok(())
     }
 }
@@ -12,4 +12,5 @@ pub use evm_runtime::*;
 
 pub mod backend;
 pub mod executor;
+pub mod precompiles;
 pub mod provider;
@@ -0,0 +1,107 @@
+use super::{check_gas, read_input, PrecompileResult};
+use crate::{Config, ExitError, ExitSucceed};
+use alloc::vec::Vec;
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+fn read_fq(input: &[u8], offset: usize) -> Result<Fq, ExitError> {
+    Fq::from_slice(&read_input(input, offset, 32))
+        .map_err(|_| ExitError::Other("invalid alt_bn128 field element"))
+}
+
+fn read_fr(input: &[u8], offset: usize) -> Result<Fr, ExitError> {
+    Fr::from_slice(&read_input(input, offset, 32))
+        .map_err(|_| ExitError::Other("invalid alt_bn128 scalar"))
+}
+
+fn read_point(input: &[u8], offset: usize) -> Result<G1, ExitError> {
+    let x = read_fq(input, offset)?;
+    let y = read_fq(input, offset + 32)?;
+
+    if x.is_zero() && y.is_zero() {
+        Ok(G1::zero())
+    } else {
+        Ok(AffineG1::new(x, y)
+            .map_err(|_| ExitError::Other("invalid alt_bn128 point"))?
+            .into())
+    }
+}
+
+fn encode_point(point: G1) -> Vec<u8> {
+    let mut out = vec![0u8; 64];
+    // Point at infinity is encoded as zeros.
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point
+            .x()
+            .to_big_endian(&mut out[0..32])
+            .expect("output slice is 32 bytes; qed");
+        point
+            .y()
+            .to_big_endian(&mut out[32..64])
+            .expect("output slice is 32 bytes; qed");
+    }
+    out
+}
+
+pub fn add(input: &[u8], target_gas: Option<usize>, config: &Config) -> PrecompileResult {
+    let cost = config.gas_bn128_add;
+    check_gas(cost, target_gas)?;
+
+    let p1 = read_point(input, 0)?;
+    let p2 = read_point(input, 64)?;
+
+    Ok((ExitSucceed::Returned, encode_point(p1 + p2), cost))
+}
+
+pub fn mul(input: &[u8], target_gas: Option<usize>, config: &Config) -> PrecompileResult {
+    let cost = config.gas_bn128_mul;
+    check_gas(cost, target_gas)?;
+
+    let p = read_point(input, 0)?;
+    let fr = read_fr(input, 64)?;
+
+    Ok((ExitSucceed::Returned, encode_point(p * fr), cost))
+}
+
+pub fn pairing(input: &[u8], target_gas: Option<usize>, config: &Config) -> PrecompileResult {
+    if input.len() % 192 != 0 {
+        return Err(ExitError::Other("invalid alt_bn128 pairing input length"));
+    }
+
+    let points = input.len() / 192;
+    let cost = config
+        .gas_bn128_pairing_point
+        .saturating_mul(points)
+        .saturating_add(config.gas_bn128_pairing_base);
+    check_gas(cost, target_gas)?;
+
+    let mut pairs = Vec::with_capacity(points);
+    for i in 0..points {
+        let offset = i * 192;
+        let a = read_point(input, offset)?;
+
+        // G2 coordinates are encoded with the imaginary part first.
+        let b_x_i = read_fq(input, offset + 64)?;
+        let b_x_r = read_fq(input, offset + 96)?;
+        let b_y_i = read_fq(input, offset + 128)?;
+        let b_y_r = read_fq(input, offset + 160)?;
+        let b_x = Fq2::new(b_x_r, b_x_i);
+        let b_y = Fq2::new(b_y_r, b_y_i);
+
+        let b = if b_x.is_zero() && b_y.is_zero() {
+            G2::zero()
+        } else {
+            AffineG2::new(b_x, b_y)
+                .map_err(|_| ExitError::Other("invalid alt_bn128 point"))?
+                .into()
+        };
+
+        pairs.push((a, b));
+    }
+
+    let mut out = vec![0u8; 32];
+    if bn::pairing_batch(&pairs) == Gt::one() {
+        out[31] = 1;
+    }
+
+    Ok((ExitSucceed::Returned, out, cost))
+}
@@ -0,0 +1,166 @@
+//! # Precompiled contracts
+//!
+//! The standard set of Ethereum precompiles, priced according to the given
+//! `Config`.
+
+mod blake2f;
+mod bn128;
+mod modexp;
+
+use crate::{Config, ExitError, ExitSucceed};
+use alloc::vec::Vec;
+use core::cmp::min;
+use primitive_types::{H160, H256, U256};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+use sha3::{Digest as _, Keccak256};
+
+/// Result of a precompile call: exit status, output and gas cost.
+pub type PrecompileResult = Result<(ExitSucceed, Vec<u8>, usize), ExitError>;
+
+/// Precompile set. Returns `None` if the address is not a precompile.
+pub type PrecompileFn = fn(H160, &[u8], Option<usize>, &Config) -> Option<PrecompileResult>;
+
+/// Precompiles that do not exist, for chains without any.
+pub fn empty(
+    _address: H160,
+    _input: &[u8],
+    _target_gas: Option<usize>,
+    _config: &Config,
+) -> Option<PrecompileResult> {
+    None
+}
+
+/// Standard precompiles at addresses `0x01` to `0x09`.
+pub fn standard(
+    address: H160,
+    input: &[u8],
+    target_gas: Option<usize>,
+    config: &Config,
+) -> Option<PrecompileResult> {
+    if address[..19] != [0u8; 19] {
+        return None;
+    }
+
+    match address[19] {
+        1 => Some(ecrecover(input, target_gas)),
+        2 => Some(sha256(input, target_gas)),
+        3 => Some(ripemd160(input, target_gas)),
+        4 => Some(identity(input, target_gas)),
+        5 if config.has_modexp => Some(modexp::modexp(input, target_gas, config)),
+        6 if config.has_bn128 => Some(bn128::add(input, target_gas, config)),
+        7 if config.has_bn128 => Some(bn128::mul(input, target_gas, config)),
+        8 if config.has_bn128 => Some(bn128::pairing(input, target_gas, config)),
+        9 if config.has_blake2f => Some(blake2f::blake2f(input, target_gas)),
+        _ => None,
+    }
+}
+
+/// Whether the address is one of the standard precompiles enabled in `config`.
+pub fn is_standard(address: H160, config: &Config) -> bool {
+    if address[..19] != [0u8; 19] {
+        return false;
+    }
+
+    match address[19] {
+        1..=4 => true,
+        5 => config.has_modexp,
+        6..=8 => config.has_bn128,
+        9 => config.has_blake2f,
+        _ => false,
+    }
+}
+
+/// Cost of `base` plus `word` for every 32-byte word of input.
+fn linear_cost(len: usize, base: usize, word: usize) -> usize {
+    base.saturating_add(((len as u64 + 31) / 32) as usize * word)
+}
+
+/// Fail with `OutOfGas` if `cost` exceeds the gas available to the call.
+fn check_gas(cost: usize, target_gas: Option<usize>) -> Result<(), ExitError> {
+    match target_gas {
+        Some(gas) if cost > gas => Err(ExitError::OutOfGas),
+        _ => Ok(()),
+    }
+}
+
+/// Read `len` bytes at `offset`, padding with zeros past the end of input.
+fn read_input(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    if offset < input.len() {
+        let end = min(input.len(), offset.saturating_add(len));
+        out[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+    out
+}
+
+fn ecrecover(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+    let cost = 3000;
+    check_gas(cost, target_gas)?;
+
+    let input = read_input(input, 0, 128);
+    let mut msg = [0u8; 32];
+    let mut sig = [0u8; 64];
+    msg.copy_from_slice(&input[0..32]);
+    sig.copy_from_slice(&input[64..128]);
+
+    // v must be 27 or 28 without any leading garbage.
+    let v = U256::from_big_endian(&input[32..64]);
+    if v != U256::from(27) && v != U256::from(28) {
+        return Ok((ExitSucceed::Returned, Vec::new(), cost));
+    }
+
+    // r and s must be in [1, n), where n is the secp256k1 group order.
+    let order = U256::from_big_endian(&[
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+        0x41, 0x41,
+    ]);
+    let r = U256::from_big_endian(&sig[0..32]);
+    let s = U256::from_big_endian(&sig[32..64]);
+    if r.is_zero() || s.is_zero() || r >= order || s >= order {
+        return Ok((ExitSucceed::Returned, Vec::new(), cost));
+    }
+
+    let recovery_id = match secp256k1::RecoveryId::parse(v.low_u32() as u8 - 27) {
+        Ok(id) => id,
+        Err(_) => return Ok((ExitSucceed::Returned, Vec::new(), cost)),
+    };
+    let public = match secp256k1::recover(
+        &secp256k1::Message::parse(&msg),
+        &secp256k1::Signature::parse(&sig),
+        &recovery_id,
+    ) {
+        Ok(public) => public,
+        Err(_) => return Ok((ExitSucceed::Returned, Vec::new(), cost)),
+    };
+
+    let mut out = H256::from_slice(Keccak256::digest(&public.serialize()[1..65]).as_slice());
+    out.as_bytes_mut()[..12].copy_from_slice(&[0u8; 12]);
+
+    Ok((ExitSucceed::Returned, out.as_bytes().to_vec(), cost))
+}
+
+fn sha256(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+    let cost = linear_cost(input.len(), 60, 12);
+    check_gas(cost, target_gas)?;
+
+    Ok((ExitSucceed::Returned, Sha256::digest(input).to_vec(), cost))
+}
+
+fn ripemd160(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+    let cost = linear_cost(input.len(), 600, 120);
+    check_gas(cost, target_gas)?;
+
+    let mut out = vec![0u8; 32];
+    out[12..].copy_from_slice(&Ripemd160::digest(input));
+
+    Ok((ExitSucceed::Returned, out, cost))
+}
+
+fn identity(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+    let cost = linear_cost(input.len(), 15, 3);
+    check_gas(cost, target_gas)?;
+
+    Ok((ExitSucceed::Returned, input.to_vec(), cost))
+}
@@ -0,0 +1,97 @@
+use super::{check_gas, read_input, PrecompileResult};
+use crate::{Config, ExitError, ExitSucceed};
+use alloc::vec::Vec;
+use core::cmp::max;
+use num_bigint::BigUint;
+use primitive_types::U256;
+
+/// Multiplication complexity from EIP-198.
+fn mult_complexity(x: U256) -> U256 {
+    if x <= U256::from(64) {
+        x * x
+    } else if x <= U256::from(1024) {
+        x * x / 4 + x * 96 - 3072
+    } else {
+        x * x / 16 + x * 480 - 199_680
+    }
+}
+
+/// Multiplication complexity from EIP-2565.
+fn mult_complexity_eip2565(x: U256) -> U256 {
+    let words = (x + 7) / 8;
+    words * words
+}
+
+/// Bit length of the exponent minus one, as defined by EIP-198.
+fn adjusted_exp_len(exp_len: U256, exp_head: U256) -> U256 {
+    let head_bits = if exp_head.is_zero() {
+        U256::zero()
+    } else {
+        U256::from(exp_head.bits() - 1)
+    };
+
+    if exp_len <= U256::from(32) {
+        head_bits
+    } else {
+        (exp_len - 32)
+            .saturating_mul(U256::from(8))
+            .saturating_add(head_bits)
+    }
+}
+
+pub fn modexp(input: &[u8], target_gas: Option<usize>, config: &Config) -> PrecompileResult {
+    let base_len = U256::from_big_endian(&read_input(input, 0, 32));
+    let exp_len = U256::from_big_endian(&read_input(input, 32, 32));
+    let mod_len = U256::from_big_endian(&read_input(input, 64, 32));
+
+    // Lengths this large can never be paid for; bail before touching them.
+    let max_len = max(base_len, mod_len);
+    if max_len > U256::from(u32::max_value()) {
+        return Err(ExitError::OutOfGas);
+    }
+    let base_len = base_len.as_usize();
+    let mod_len = mod_len.as_usize();
+
+    let exp_head = if exp_len.is_zero() {
+        U256::zero()
+    } else {
+        let head_len = if exp_len > U256::from(32) {
+            32
+        } else {
+            exp_len.as_usize()
+        };
+        U256::from_big_endian(&read_input(input, 96 + base_len, head_len))
+    };
+
+    let iteration_count = max(adjusted_exp_len(exp_len, exp_head), U256::one());
+    let gas = if config.modexp_eip2565 {
+        max(
+            mult_complexity_eip2565(max_len).saturating_mul(iteration_count) / 3,
+            U256::from(200),
+        )
+    } else {
+        mult_complexity(max_len).saturating_mul(iteration_count) / 20
+    };
+    if gas > U256::from(usize::max_value()) {
+        return Err(ExitError::OutOfGas);
+    }
+    let cost = gas.as_usize();
+    check_gas(cost, target_gas)?;
+
+    if base_len == 0 && mod_len == 0 {
+        return Ok((ExitSucceed::Returned, Vec::new(), cost));
+    }
+    let exp_len = exp_len.as_usize();
+
+    let base = BigUint::from_bytes_be(&read_input(input, 96, base_len));
+    let exp = BigUint::from_bytes_be(&read_input(input, 96 + base_len, exp_len));
+    let modulus = BigUint::from_bytes_be(&read_input(input, 96 + base_len + exp_len, mod_len));
+
+    let mut out = vec![0u8; mod_len];
+    if modulus != BigUint::from(0u8) {
+        let result = base.modpow(&exp, &modulus).to_bytes_be();
+        out[mod_len - result.len()..].copy_from_slice(&result);
+    }
+
+    Ok((ExitSucceed::Returned, out, cost))
+}
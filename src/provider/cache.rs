@@ -0,0 +1,273 @@
+use super::{AccountState, ProviderError};
+use primitive_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use web3::types::Bytes;
+
+/// Forked state read at a single block.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+struct BlockState {
+    balances: BTreeMap<H160, U256>,
+    nonces: BTreeMap<H160, U256>,
+    code: BTreeMap<H160, Bytes>,
+    storage: BTreeMap<H160, BTreeMap<H256, H256>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+struct CacheData {
+    chain_id: U256,
+    blocks: BTreeMap<U256, BlockState>,
+}
+
+#[derive(Debug)]
+struct CacheInner {
+    path: Option<PathBuf>,
+    offline: bool,
+    dirty: AtomicBool,
+    data: RwLock<CacheData>,
+    /// Held while writing to disk, so clones flushing from several threads
+    /// never interleave.
+    writing: Mutex<()>,
+}
+
+impl CacheInner {
+    fn flush(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let _writing = self.writing.lock().expect("rpc cache lock poisoned");
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let data = self.data.read().expect("rpc cache lock poisoned");
+        let json = serde_json::to_vec(&*data)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write to a sibling file first so an interrupted run never leaves a
+        // truncated cache behind. Each process gets its own, as several runs
+        // may share a cache directory.
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+impl Drop for CacheInner {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Cache of forked chain state, keyed by chain id and block number.
+///
+/// Clones share the same underlying data. An on-disk cache is written back
+/// on `flush` and when the last clone is dropped.
+#[derive(Clone, Debug)]
+pub struct RpcCache {
+    inner: Arc<CacheInner>,
+}
+
+impl RpcCache {
+    /// Open the cache for `chain_id` stored in `dir`, creating it if missing.
+    ///
+    /// In `offline` mode a cache miss fails instead of reaching the provider.
+    pub fn open<P: AsRef<Path>>(dir: P, chain_id: U256, offline: bool) -> io::Result<Self> {
+        let path = dir.as_ref().join(format!("{}.json", chain_id));
+        let data = if path.exists() {
+            let data: CacheData = serde_json::from_slice(&fs::read(&path)?)?;
+            if data.chain_id != chain_id {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("rpc cache {:?} is for chain {}", path, data.chain_id),
+                ));
+            }
+            data
+        } else {
+            CacheData {
+                chain_id,
+                blocks: BTreeMap::new(),
+            }
+        };
+
+        Ok(Self {
+            inner: Arc::new(CacheInner {
+                path: Some(path),
+                offline,
+                dirty: AtomicBool::new(false),
+                data: RwLock::new(data),
+                writing: Mutex::new(()),
+            }),
+        })
+    }
+
+    /// Create a cache for `chain_id` that is never written to disk.
+    pub fn in_memory(chain_id: U256) -> Self {
+        Self {
+            inner: Arc::new(CacheInner {
+                path: None,
+                offline: false,
+                dirty: AtomicBool::new(false),
+                data: RwLock::new(CacheData {
+                    chain_id,
+                    blocks: BTreeMap::new(),
+                }),
+                writing: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Chain id the cache was opened for.
+    pub fn chain_id(&self) -> U256 {
+        self.inner
+            .data
+            .read()
+            .expect("rpc cache lock poisoned")
+            .chain_id
+    }
+
+    /// Whether cache misses are refused.
+    pub fn is_offline(&self) -> bool {
+        self.inner.offline
+    }
+
+    /// Write pending entries to disk.
+    pub fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Whether balance, nonce and code of `address` at `block` are cached.
+    pub fn has_account(&self, block: U256, address: H160) -> bool {
+        let data = self.inner.data.read().expect("rpc cache lock poisoned");
+        data.blocks
+            .get(&block)
+            .map(|s| {
+                s.balances.contains_key(&address)
+                    && s.nonces.contains_key(&address)
+                    && s.code.contains_key(&address)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Store an account fetched at `block`.
+    pub fn insert_account(&self, block: U256, address: H160, account: AccountState) {
+        let mut data = self.inner.data.write().expect("rpc cache lock poisoned");
+        let state = data.blocks.entry(block).or_default();
+        state.balances.insert(address, account.balance);
+        state.nonces.insert(address, account.nonce);
+        state.code.insert(address, Bytes(account.code));
+        let storage = state.storage.entry(address).or_default();
+        for (index, value) in account.storage {
+            storage.insert(index, value);
+        }
+        self.inner.dirty.store(true, Ordering::SeqCst);
+    }
+
+    fn get_or_fetch<T, G, I, F>(
+        &self,
+        method: &'static str,
+        block: U256,
+        get: G,
+        insert: I,
+        fetch: F,
+    ) -> Result<T, ProviderError>
+    where
+        G: FnOnce(&BlockState) -> Option<T>,
+        I: FnOnce(&mut BlockState, T),
+        F: FnOnce() -> Result<T, ProviderError>,
+        T: Clone,
+    {
+        {
+            let data = self.inner.data.read().expect("rpc cache lock poisoned");
+            if let Some(value) = data.blocks.get(&block).and_then(get) {
+                return Ok(value);
+            }
+        }
+
+        if self.inner.offline {
+            return Err(ProviderError::CacheMiss { method });
+        }
+
+        let value = fetch()?;
+        let mut data = self.inner.data.write().expect("rpc cache lock poisoned");
+        insert(data.blocks.entry(block).or_default(), value.clone());
+        self.inner.dirty.store(true, Ordering::SeqCst);
+        Ok(value)
+    }
+
+    /// Balance of `address` at `block`, calling `fetch` on a miss.
+    pub fn balance<F>(&self, block: U256, address: H160, fetch: F) -> Result<U256, ProviderError>
+    where
+        F: FnOnce() -> Result<U256, ProviderError>,
+    {
+        self.get_or_fetch(
+            "eth_getBalance",
+            block,
+            |s| s.balances.get(&address).cloned(),
+            |s, v| {
+                s.balances.insert(address, v);
+            },
+            fetch,
+        )
+    }
+
+    /// Nonce of `address` at `block`, calling `fetch` on a miss.
+    pub fn nonce<F>(&self, block: U256, address: H160, fetch: F) -> Result<U256, ProviderError>
+    where
+        F: FnOnce() -> Result<U256, ProviderError>,
+    {
+        self.get_or_fetch(
+            "eth_getTransactionCount",
+            block,
+            |s| s.nonces.get(&address).cloned(),
+            |s, v| {
+                s.nonces.insert(address, v);
+            },
+            fetch,
+        )
+    }
+
+    /// Code of `address` at `block`, calling `fetch` on a miss.
+    pub fn code<F>(&self, block: U256, address: H160, fetch: F) -> Result<Vec<u8>, ProviderError>
+    where
+        F: FnOnce() -> Result<Vec<u8>, ProviderError>,
+    {
+        self.get_or_fetch(
+            "eth_getCode",
+            block,
+            |s| s.code.get(&address).map(|c| c.0.clone()),
+            |s, v| {
+                s.code.insert(address, Bytes(v));
+            },
+            fetch,
+        )
+    }
+
+    /// Storage of `address` at `index` and `block`, calling `fetch` on a miss.
+    pub fn storage<F>(
+        &self,
+        block: U256,
+        address: H160,
+        index: H256,
+        fetch: F,
+    ) -> Result<H256, ProviderError>
+    where
+        F: FnOnce() -> Result<H256, ProviderError>,
+    {
+        self.get_or_fetch(
+            "eth_getStorageAt",
+            block,
+            |s| s.storage.get(&address).and_then(|s| s.get(&index)).cloned(),
+            |s, v| {
+                s.storage.entry(address).or_default().insert(index, v);
+            },
+            fetch,
+        )
+    }
+}
@@ -1,12 +1,15 @@
+use super::{AccountState, ProviderError, StateProvider};
 use crate::backend::memory::TxReceipt;
 use ethers_core::types::*;
 use jsonrpc_core as rpc;
 use primitive_types::{H160, H256, U256};
 use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeSet;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::{thread, time};
 use thiserror::Error;
 use url::Url;
@@ -123,273 +126,301 @@ pub struct Provider {
     pub url: Url,
     /// last call
     pub last_call: u128,
+    /// Set once the node rejected `eth_getProof`
+    proof_unsupported: Arc<AtomicBool>,
+}
+
+/// Account proof from `eth_getProof`, without the proofs
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AccountProof {
+    balance: U256,
+    nonce: U256,
+    storage_proof: Vec<StorageProof>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StorageProof {
+    value: U256,
 }
 
 impl Provider {
     /// Create new provider
-    pub fn new(src: String) -> Self {
-        Self {
+    pub fn new(src: String) -> Result<Self, ProviderError> {
+        let url = Url::parse(&src).map_err(|e| ProviderError::InvalidUrl {
+            url: src.clone(),
+            message: e.to_string(),
+        })?;
+        Ok(Self {
             client: Client::new(),
-            url: Url::parse(&src).unwrap(),
+            url,
             last_call: 0,
-        }
+            proof_unsupported: Arc::new(AtomicBool::new(false)),
+        })
     }
 
     fn check_delay(&self) {
         thread::sleep(time::Duration::from_millis(DELAY));
     }
 
-    /// Get storage for a particular index at an address
-    pub fn get_block_number(&self) -> U256 {
+    /// Send a request and decode its result
+    fn request<T: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        params: Vec<rpc::Value>,
+    ) -> Result<T, ProviderError> {
         self.check_delay();
-        //println!("eth_blockNumber");
-        let request = build_request(0, "eth_blockNumber", vec![]);
+        let request = build_request(0, method, params);
         let res = self
             .client
             .post(self.url.clone())
             .json(&request)
             .send()
-            .expect("provider error, get_storage_at");
-        let res = res.json::<Response<U256>>().unwrap();
-        res.data.into_result().unwrap()
+            .map_err(|e| ProviderError::Transport {
+                method,
+                message: e.to_string(),
+            })?;
+        let res = res
+            .json::<Response<Option<T>>>()
+            .map_err(|e| ProviderError::Decode {
+                method,
+                message: e.to_string(),
+            })?;
+        match res.data.into_result() {
+            Ok(Some(result)) => Ok(result),
+            Ok(None) => Err(ProviderError::NotFound { method }),
+            Err(e) => Err(ProviderError::JsonRpc {
+                method,
+                code: e.code,
+                message: e.message,
+            }),
+        }
     }
 
-    /// Get storage for a particular index at an address
-    pub fn get_block(&self) -> Block<H256> {
+    /// Send several requests as one JSON-RPC batch, results in request order
+    pub fn batch(
+        &self,
+        calls: Vec<(&'static str, Vec<rpc::Value>)>,
+    ) -> Result<Vec<Result<Value, ProviderError>>, ProviderError> {
+        let method = calls.first().map(|(m, _)| *m).unwrap_or("batch");
         self.check_delay();
-        //println!("eth_getBlockByNumber");
-        let index = serialize(&"latest".to_string());
-        let txs = serialize(&false);
-        let request = build_request(0, "eth_getBlockByNumber", vec![index, txs]);
+        let request: Vec<rpc::Call> = calls
+            .iter()
+            .enumerate()
+            .map(|(id, (method, params))| build_request(id, method, params.clone()))
+            .collect();
         let res = self
             .client
             .post(self.url.clone())
             .json(&request)
             .send()
-            .expect("provider error, get_storage_at");
-        let res = res.json::<Response<Block<H256>>>().unwrap();
-        res.data.into_result().unwrap()
+            .map_err(|e| ProviderError::Transport {
+                method,
+                message: e.to_string(),
+            })?;
+        let mut res = res
+            .json::<Vec<Response<Value>>>()
+            .map_err(|e| ProviderError::Decode {
+                method,
+                message: e.to_string(),
+            })?;
+        if res.len() != calls.len() {
+            return Err(ProviderError::Decode {
+                method,
+                message: format!("{} responses to {} requests", res.len(), calls.len()),
+            });
+        }
+        res.sort_by_key(|r| r.id);
+        Ok(res
+            .into_iter()
+            .zip(calls)
+            .map(|(r, (method, _))| {
+                r.data.into_result().map_err(|e| ProviderError::JsonRpc {
+                    method,
+                    code: e.code,
+                    message: e.message,
+                })
+            })
+            .collect())
+    }
+
+    /// Gets balance, nonce, code and the given storage slots of an address in
+    /// one batch, from `eth_getProof` if the node supports it
+    pub fn get_account(
+        &self,
+        address: H160,
+        slots: &[H256],
+        block: Option<U256>,
+    ) -> Result<AccountState, ProviderError> {
+        let addr = serialize(&address);
+        let b = block_param(block);
+
+        if !self.proof_unsupported.load(Ordering::Relaxed) {
+            let mut res = self
+                .batch(vec![
+                    (
+                        "eth_getProof",
+                        vec![addr.clone(), serialize(&slots), b.clone()],
+                    ),
+                    ("eth_getCode", vec![addr.clone(), b.clone()]),
+                ])?
+                .into_iter();
+            let proof = res.next().expect("one result per request; qed");
+            let code = res.next().expect("one result per request; qed");
+            match proof {
+                Ok(proof) => {
+                    let proof: AccountProof = decode("eth_getProof", proof)?;
+                    let code: Bytes = decode("eth_getCode", code?)?;
+                    if proof.storage_proof.len() != slots.len() {
+                        return Err(ProviderError::Decode {
+                            method: "eth_getProof",
+                            message: "storage proof doesn't match the requested slots".to_string(),
+                        });
+                    }
+                    return Ok(AccountState {
+                        balance: proof.balance,
+                        nonce: proof.nonce,
+                        code: code.as_ref().to_vec(),
+                        storage: slots
+                            .iter()
+                            .zip(proof.storage_proof)
+                            .map(|(slot, p)| (*slot, u256_to_h256(p.value)))
+                            .collect(),
+                    });
+                }
+                // Not every node serves proofs, answering with method not
+                // found or not supported; ask for the values directly.
+                Err(ProviderError::JsonRpc { code: -32601, .. })
+                | Err(ProviderError::JsonRpc { code: -32004, .. }) => {
+                    self.proof_unsupported.store(true, Ordering::Relaxed)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut calls = vec![
+            ("eth_getBalance", vec![addr.clone(), b.clone()]),
+            ("eth_getTransactionCount", vec![addr.clone(), b.clone()]),
+            ("eth_getCode", vec![addr.clone(), b.clone()]),
+        ];
+        for slot in slots {
+            calls.push((
+                "eth_getStorageAt",
+                vec![addr.clone(), serialize(slot), b.clone()],
+            ));
+        }
+        let mut res = self.batch(calls)?.into_iter();
+        let mut next = || res.next().expect("one result per request; qed");
+        let balance = decode("eth_getBalance", next()?)?;
+        let nonce = decode("eth_getTransactionCount", next()?)?;
+        let code: Bytes = decode("eth_getCode", next()?)?;
+        let mut storage = Vec::with_capacity(slots.len());
+        for slot in slots {
+            storage.push((*slot, decode("eth_getStorageAt", next()?)?));
+        }
+        Ok(AccountState {
+            balance,
+            nonce,
+            code: code.as_ref().to_vec(),
+            storage,
+        })
+    }
+
+    /// Get the chain id
+    pub fn get_chain_id(&self) -> Result<U256, ProviderError> {
+        self.request("eth_chainId", vec![])
+    }
+
+    /// Get storage for a particular index at an address
+    pub fn get_block_number(&self) -> Result<U256, ProviderError> {
+        self.request("eth_blockNumber", vec![])
+    }
+
+    /// Get storage for a particular index at an address
+    pub fn get_block(&self) -> Result<Block<H256>, ProviderError> {
+        let index = serialize(&"latest".to_string());
+        let txs = serialize(&false);
+        self.request("eth_getBlockByNumber", vec![index, txs])
     }
 
     /// Get storage for a particular index at an address
     pub fn get_block_by_number_txs(
         &self,
         bn: U256,
-    ) -> web3::types::Block<web3::types::Transaction> {
-        self.check_delay();
-        //println!("eth_getBlockByNumber");
+    ) -> Result<web3::types::Block<web3::types::Transaction>, ProviderError> {
         let index = serialize(&bn);
         let t = serialize(&true);
-        let request = build_request(0, "eth_getBlockByNumber", vec![index, t]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_storage_at");
-
-        let res = res
-            .json::<Response<web3::types::Block<web3::types::Transaction>>>()
-            .unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getBlockByNumber", vec![index, t])
     }
 
     /// Get storage for a particular index at an address
-    pub fn get_block_by_number(&self, bn: U256) -> web3::types::Block<web3::types::H256> {
-        self.check_delay();
-        //println!("eth_getBlockByNumber");
+    pub fn get_block_by_number(
+        &self,
+        bn: U256,
+    ) -> Result<web3::types::Block<web3::types::H256>, ProviderError> {
         let index = serialize(&bn);
         let t = serialize(&false);
-        let request = build_request(0, "eth_getBlockByNumber", vec![index, t]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_storage_at");
-
-        let res = res
-            .json::<Response<web3::types::Block<web3::types::H256>>>()
-            .unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getBlockByNumber", vec![index, t])
     }
 
     /// Get storage for a particular index at an address
     pub fn get_block_by_hash_txs(
         &self,
         bh: web3::types::H256,
-    ) -> web3::types::Block<web3::types::Transaction> {
-        self.check_delay();
-        //println!("eth_getBlockByHashTxs");
+    ) -> Result<web3::types::Block<web3::types::Transaction>, ProviderError> {
         let index = serialize(&bh);
         let t = serialize(&true);
-        let request = build_request(0, "eth_getBlockByHash", vec![index, t]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_storage_at");
-
-        let res = res
-            .json::<Response<web3::types::Block<web3::types::Transaction>>>()
-            .unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getBlockByHash", vec![index, t])
     }
 
     /// Get storage for a particular index at an address
-    pub fn get_block_by_hash(&self, bh: H256) -> web3::types::Block<web3::types::H256> {
-        self.check_delay();
-        //println!("eth_getBlockByHash");
+    pub fn get_block_by_hash(
+        &self,
+        bh: H256,
+    ) -> Result<web3::types::Block<web3::types::H256>, ProviderError> {
         let index = serialize(&bh);
         let t = serialize(&false);
-        let request = build_request(0, "eth_getBlockByHash", vec![index, t]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_storage_at");
-
-        let res = res
-            .json::<Response<web3::types::Block<web3::types::H256>>>()
-            .unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getBlockByHash", vec![index, t])
     }
 
     /// Get storage for a particular index at an address
-    pub fn get_storage_at(&self, address: H160, index: H256, block: Option<U256>) -> H256 {
-        self.check_delay();
-        //println!("eth_getStorageAt, {:?}, {:?}", address, index);
+    pub fn get_storage_at(
+        &self,
+        address: H160,
+        index: H256,
+        block: Option<U256>,
+    ) -> Result<H256, ProviderError> {
         let address = serialize(&address);
         let index = serialize(&index);
-        let b;
-        match block {
-            Some(bn) => {
-                b = serialize(&bn);
-            }
-            _ => {
-                b = serialize(&BlockNumber::Latest);
-            }
-        }
-
-        let request = build_request(0, "eth_getStorageAt", vec![address, index, b]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_storage_at");
-        let res = res.json::<Response<H256>>().unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getStorageAt", vec![address, index, block_param(block)])
     }
 
     /// Gets the bytecode for an address
-    pub fn get_code(&self, address: H160, block: Option<U256>) -> Bytes {
-        if address == "7109709ecfa91a80626ff3989d68f67f5b1dd12d".parse().unwrap() {
-            return Bytes::new();
-        }
-        self.check_delay();
-        //println!("eth_getCode, {:?}", address);
+    pub fn get_code(&self, address: H160, block: Option<U256>) -> Result<Bytes, ProviderError> {
         let address = serialize(&address);
-        let b;
-        match block {
-            Some(bn) => {
-                b = serialize(&bn);
-            }
-            _ => {
-                b = serialize(&BlockNumber::Latest);
-            }
-        }
-
-        let request = build_request(0, "eth_getCode", vec![address, b]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_code");
-        let res = res.json::<Response<Bytes>>().unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getCode", vec![address, block_param(block)])
     }
 
     /// Gets the balance of an address
-    pub fn get_balance(&self, address: H160, block: Option<U256>) -> U256 {
-        self.check_delay();
-        //println!("eth_getBalance, {:?}", address);
-        // //println!("balance block: {:?}", block);
+    pub fn get_balance(&self, address: H160, block: Option<U256>) -> Result<U256, ProviderError> {
         let address = serialize(&address);
-        let b;
-        match block {
-            Some(bn) => {
-                b = serialize(&bn);
-            }
-            _ => {
-                b = serialize(&BlockNumber::Latest);
-            }
-        }
-
-        let request = build_request(0, "eth_getBalance", vec![address, b]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_balance");
-        let res = res.json::<Response<U256>>().unwrap_or({
-            let res = self
-                .client
-                .post(self.url.clone())
-                .json(&request)
-                .send()
-                .expect("provider error, get_balance");
-            res.json::<Response<U256>>()
-                .expect("I retried, but couldn't unwrap response")
-        });
-        res.data.into_result().unwrap()
+        self.request("eth_getBalance", vec![address, block_param(block)])
     }
 
     /// Gets the tx count for an address
-    pub fn get_transaction_count(&self, address: H160, block: Option<U256>) -> U256 {
-        self.check_delay();
-        //println!("eth_getTransactionCount: {:?}", address);
+    pub fn get_transaction_count(
+        &self,
+        address: H160,
+        block: Option<U256>,
+    ) -> Result<U256, ProviderError> {
         let address = serialize(&address);
-        let b;
-        match block {
-            Some(bn) => {
-                b = serialize(&bn);
-            }
-            _ => {
-                b = serialize(&BlockNumber::Latest);
-            }
-        }
-
-        let request = build_request(0, "eth_getTransactionCount", vec![address, b]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_tx_count");
-        let res = res.json::<Response<U256>>().unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getTransactionCount", vec![address, block_param(block)])
     }
 
     /// Gets Tx from hash
-    pub fn get_transaction(&self, hash: H256) -> web3::types::Transaction {
-        self.check_delay();
-        //println!("eth_getTransactionByHash: {:?}", hash);
+    pub fn get_transaction(&self, hash: H256) -> Result<web3::types::Transaction, ProviderError> {
         let h = serialize(&hash);
-
-        let request = build_request(0, "eth_getTransactionByHash", vec![h]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_tx");
-        let res = res.json::<Response<web3::types::Transaction>>().unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getTransactionByHash", vec![h])
     }
 
     /// Gets Tx from hash
@@ -399,8 +430,7 @@ impl Provider {
         to: U256,
         addrs: Vec<H160>,
         topics: Vec<H256>,
-    ) -> Vec<web3::types::Log> {
-        self.check_delay();
+    ) -> Result<Vec<web3::types::Log>, ProviderError> {
         // TODO: Transforming individual backend::H160 into web3::types::H160 - not efficient
         let new_addrs: Vec<web3::types::H160> = addrs
             .clone()
@@ -413,6 +443,13 @@ impl Provider {
             .iter()
             .map(|e| web3::types::H256::from_slice(e.as_bytes()))
             .collect();
+        // No block number that high exists.
+        let max_block = U256::from(u64::max_value());
+        if from > max_block || to > max_block {
+            return Err(ProviderError::NotFound {
+                method: "eth_getLogs",
+            });
+        }
         let filter = serialize(
             &web3::types::FilterBuilder::default()
                 .from_block(web3::types::BlockNumber::Number(web3::types::U64::from(
@@ -425,32 +462,18 @@ impl Provider {
                 .topics(Some(new_topics), None, None, None)
                 .build(),
         );
-        let request = build_request(0, "eth_getLogs", vec![filter]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_logs");
-        let res = res.json::<Response<Vec<web3::types::Log>>>().unwrap();
-        res.data.into_result().unwrap()
+        self.request("eth_getLogs", vec![filter])
     }
 
     /// Gets the tx count for an address
-    pub fn get_transaction_receipt(&self, hash: H256) -> TxReceipt {
-        self.check_delay();
-        //println!("eth_getTransactionReceipt: {:?}", hash);
+    pub fn get_transaction_receipt(&self, hash: H256) -> Result<TxReceipt, ProviderError> {
+        let method = "eth_getTransactionReceipt";
         let h = serialize(&hash);
-
-        let request = build_request(0, "eth_getTransactionReceipt", vec![h]);
-        let res = self
-            .client
-            .post(self.url.clone())
-            .json(&request)
-            .send()
-            .expect("provider error, get_tx_count");
-        let res = res.json::<Response<TransactionReceiptExtended>>().unwrap();
-        let res = res.data.into_result().unwrap();
+        let res: TransactionReceiptExtended = self.request(method, vec![h])?;
+        let pending = || ProviderError::Decode {
+            method,
+            message: "receipt has no block number, gas used or status".to_string(),
+        };
         let mut logs = Vec::with_capacity(res.logs.len());
         for log in res.logs.iter() {
             let web3::types::Bytes(raw) = log.data.clone();
@@ -467,34 +490,54 @@ impl Provider {
                 data: raw,
             });
         }
-        match res.contract_address {
-            Some(addr) => {
-                let mut addrs = BTreeSet::new();
-                addrs.insert(addr);
-                TxReceipt {
-                    hash: res.transaction_hash,
-                    caller: res.from,
-                    to: res.to,
-                    block_number: U256::from(res.block_number.unwrap().as_u64()),
-                    cumulative_gas_used: res.cumulative_gas_used.as_usize(),
-                    gas_used: res.gas_used.unwrap().as_usize(),
-                    contract_addresses: addrs,
-                    logs,
-                    status: res.status.unwrap().as_usize(),
-                }
-            }
-            _ => TxReceipt {
-                hash: res.transaction_hash,
-                caller: res.from,
-                to: res.to,
-                block_number: U256::from(res.block_number.unwrap().as_u64()),
-                cumulative_gas_used: res.cumulative_gas_used.as_usize(),
-                gas_used: res.gas_used.unwrap().as_usize(),
-                contract_addresses: BTreeSet::new(),
-                logs,
-                status: res.status.unwrap().as_usize(),
-            },
+        let mut contract_addresses = BTreeSet::new();
+        if let Some(addr) = res.contract_address {
+            contract_addresses.insert(addr);
         }
+        Ok(TxReceipt {
+            hash: res.transaction_hash,
+            caller: res.from,
+            to: res.to,
+            block_number: U256::from(res.block_number.ok_or_else(pending)?.as_u64()),
+            cumulative_gas_used: to_usize(method, res.cumulative_gas_used)?,
+            gas_used: to_usize(method, res.gas_used.ok_or_else(pending)?)?,
+            contract_addresses,
+            logs,
+            status: to_usize(method, U256::from(res.status.ok_or_else(pending)?.as_u64()))?,
+        })
+    }
+}
+
+/// Convert a receipt quantity, failing on values that don't fit a `usize`
+fn to_usize(method: &'static str, value: U256) -> Result<usize, ProviderError> {
+    if value > U256::from(usize::max_value()) {
+        return Err(ProviderError::Decode {
+            method,
+            message: format!("{} does not fit in a usize", value),
+        });
+    }
+    Ok(value.as_usize())
+}
+
+/// Decode one result of a batch
+fn decode<T: DeserializeOwned>(method: &'static str, value: Value) -> Result<T, ProviderError> {
+    serde_json::from_value(value).map_err(|e| ProviderError::Decode {
+        method,
+        message: e.to_string(),
+    })
+}
+
+fn u256_to_h256(value: U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    H256::from(bytes)
+}
+
+/// Block parameter for state queries, the latest block if none is given
+fn block_param(block: Option<U256>) -> rpc::Value {
+    match block {
+        Some(bn) => serialize(&bn),
+        None => serialize(&BlockNumber::Latest),
     }
 }
 
@@ -512,3 +555,89 @@ pub fn build_request(id: usize, method: &str, params: Vec<rpc::Value>) -> rpc::C
         id: rpc::Id::Num(id as u64),
     })
 }
+
+impl StateProvider for Provider {
+    fn get_block_number(&self) -> Result<U256, ProviderError> {
+        Provider::get_block_number(self)
+    }
+
+    fn get_block_by_number(
+        &self,
+        bn: U256,
+    ) -> Result<web3::types::Block<web3::types::H256>, ProviderError> {
+        Provider::get_block_by_number(self, bn)
+    }
+
+    fn get_block_by_number_txs(
+        &self,
+        bn: U256,
+    ) -> Result<web3::types::Block<web3::types::Transaction>, ProviderError> {
+        Provider::get_block_by_number_txs(self, bn)
+    }
+
+    fn get_block_by_hash(
+        &self,
+        bh: H256,
+    ) -> Result<web3::types::Block<web3::types::H256>, ProviderError> {
+        Provider::get_block_by_hash(self, bh)
+    }
+
+    fn get_block_by_hash_txs(
+        &self,
+        bh: web3::types::H256,
+    ) -> Result<web3::types::Block<web3::types::Transaction>, ProviderError> {
+        Provider::get_block_by_hash_txs(self, bh)
+    }
+
+    fn get_storage_at(
+        &self,
+        address: H160,
+        index: H256,
+        block: Option<U256>,
+    ) -> Result<H256, ProviderError> {
+        Provider::get_storage_at(self, address, index, block)
+    }
+
+    fn get_code(&self, address: H160, block: Option<U256>) -> Result<Vec<u8>, ProviderError> {
+        Provider::get_code(self, address, block).map(|code| code.as_ref().to_vec())
+    }
+
+    fn get_balance(&self, address: H160, block: Option<U256>) -> Result<U256, ProviderError> {
+        Provider::get_balance(self, address, block)
+    }
+
+    fn get_transaction_count(
+        &self,
+        address: H160,
+        block: Option<U256>,
+    ) -> Result<U256, ProviderError> {
+        Provider::get_transaction_count(self, address, block)
+    }
+
+    fn get_account(
+        &self,
+        address: H160,
+        slots: &[H256],
+        block: Option<U256>,
+    ) -> Result<AccountState, ProviderError> {
+        Provider::get_account(self, address, slots, block)
+    }
+
+    fn get_transaction(&self, hash: H256) -> Result<web3::types::Transaction, ProviderError> {
+        Provider::get_transaction(self, hash)
+    }
+
+    fn get_transaction_receipt(&self, hash: H256) -> Result<TxReceipt, ProviderError> {
+        Provider::get_transaction_receipt(self, hash)
+    }
+
+    fn get_logs(
+        &self,
+        from: U256,
+        to: U256,
+        addrs: Vec<H160>,
+        topics: Vec<H256>,
+    ) -> Result<Vec<web3::types::Log>, ProviderError> {
+        Provider::get_logs(self, from, to, addrs, topics)
+    }
+}
@@ -0,0 +1,167 @@
+use super::{ProviderError, StateProvider};
+use crate::backend::{MemoryAccount, TxReceipt};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use web3::types::{Block, Log, Transaction};
+
+/// A provider answering from fixed in-memory state, for tests and recorded
+/// fixtures. State is the same at every block.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryProvider {
+    /// Accounts of the forked chain
+    pub accounts: BTreeMap<H160, MemoryAccount>,
+    /// Blocks of the forked chain, in any order
+    pub blocks: Vec<Block<Transaction>>,
+    /// Transactions by hash
+    pub transactions: BTreeMap<H256, Transaction>,
+    /// Transaction receipts by hash
+    pub receipts: BTreeMap<H256, TxReceipt>,
+    /// Logs of the forked chain
+    pub logs: Vec<Log>,
+}
+
+impl MemoryProvider {
+    /// Create a provider serving `accounts`.
+    pub fn new(accounts: BTreeMap<H160, MemoryAccount>) -> Self {
+        Self {
+            accounts,
+            ..Default::default()
+        }
+    }
+
+    fn block_where<F: Fn(&Block<Transaction>) -> bool>(
+        &self,
+        method: &'static str,
+        f: F,
+    ) -> Result<Block<Transaction>, ProviderError> {
+        self.blocks
+            .iter()
+            .find(|b| f(b))
+            .cloned()
+            .ok_or(ProviderError::NotFound { method })
+    }
+}
+
+/// Replace the full transactions of a block with their hashes.
+fn hashes_only(block: Block<Transaction>) -> Block<web3::types::H256> {
+    let hashes: Vec<web3::types::H256> = block.transactions.iter().map(|tx| tx.hash).collect();
+    let mut value = serde_json::to_value(&block).expect("Types never fail to serialize.");
+    value["transactions"] = serde_json::to_value(hashes).expect("Types never fail to serialize.");
+    serde_json::from_value(value).expect("block only differs in transactions; qed")
+}
+
+impl StateProvider for MemoryProvider {
+    fn get_block_number(&self) -> Result<U256, ProviderError> {
+        Ok(self
+            .blocks
+            .iter()
+            .filter_map(|b| b.number)
+            .max()
+            .map(|n| U256::from(n.as_u64()))
+            .unwrap_or_default())
+    }
+
+    fn get_block_by_number(&self, bn: U256) -> Result<Block<web3::types::H256>, ProviderError> {
+        self.get_block_by_number_txs(bn).map(hashes_only)
+    }
+
+    fn get_block_by_number_txs(&self, bn: U256) -> Result<Block<Transaction>, ProviderError> {
+        self.block_where("eth_getBlockByNumber", |b| {
+            b.number.map(|n| U256::from(n.as_u64())) == Some(bn)
+        })
+    }
+
+    fn get_block_by_hash(&self, bh: H256) -> Result<Block<web3::types::H256>, ProviderError> {
+        self.get_block_by_hash_txs(web3::types::H256::from_slice(bh.as_bytes()))
+            .map(hashes_only)
+    }
+
+    fn get_block_by_hash_txs(
+        &self,
+        bh: web3::types::H256,
+    ) -> Result<Block<Transaction>, ProviderError> {
+        self.block_where("eth_getBlockByHash", |b| b.hash == Some(bh))
+    }
+
+    fn get_storage_at(
+        &self,
+        address: H160,
+        index: H256,
+        _block: Option<U256>,
+    ) -> Result<H256, ProviderError> {
+        Ok(self
+            .accounts
+            .get(&address)
+            .and_then(|a| a.storage.get(&index).cloned())
+            .unwrap_or_default())
+    }
+
+    fn get_code(&self, address: H160, _block: Option<U256>) -> Result<Vec<u8>, ProviderError> {
+        Ok(self
+            .accounts
+            .get(&address)
+            .map(|a| a.code.clone())
+            .unwrap_or_default())
+    }
+
+    fn get_balance(&self, address: H160, _block: Option<U256>) -> Result<U256, ProviderError> {
+        Ok(self
+            .accounts
+            .get(&address)
+            .map(|a| a.balance)
+            .unwrap_or_default())
+    }
+
+    fn get_transaction_count(
+        &self,
+        address: H160,
+        _block: Option<U256>,
+    ) -> Result<U256, ProviderError> {
+        Ok(self
+            .accounts
+            .get(&address)
+            .map(|a| a.nonce)
+            .unwrap_or_default())
+    }
+
+    fn get_transaction(&self, hash: H256) -> Result<Transaction, ProviderError> {
+        self.transactions
+            .get(&hash)
+            .cloned()
+            .ok_or(ProviderError::NotFound {
+                method: "eth_getTransactionByHash",
+            })
+    }
+
+    fn get_transaction_receipt(&self, hash: H256) -> Result<TxReceipt, ProviderError> {
+        self.receipts
+            .get(&hash)
+            .cloned()
+            .ok_or(ProviderError::NotFound {
+                method: "eth_getTransactionReceipt",
+            })
+    }
+
+    fn get_logs(
+        &self,
+        from: U256,
+        to: U256,
+        addrs: Vec<H160>,
+        topics: Vec<H256>,
+    ) -> Result<Vec<Log>, ProviderError> {
+        Ok(self
+            .logs
+            .iter()
+            .filter(|l| {
+                let bn = l.block_number.map(|n| U256::from(n.as_u64()));
+                bn.map(|bn| bn >= from && bn <= to).unwrap_or(false)
+                    && addrs.iter().any(|a| a.as_bytes() == l.address.as_bytes())
+                    && topics
+                        .iter()
+                        .any(|t| l.topics.first().map(|x| x.as_bytes()) == Some(t.as_bytes()))
+            })
+            .cloned()
+            .collect())
+    }
+}
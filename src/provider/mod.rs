@@ -2,6 +2,12 @@
 //!
 //! Providers interact with the to-be-forked chain via http
 
+use crate::backend::TxReceipt;
+use alloc::string::String;
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use thiserror::Error;
+
 /// module for webpage
 #[cfg(feature = "web")]
 pub mod webprovider;
@@ -9,3 +15,162 @@ pub mod webprovider;
 /// A module for provider if running locally
 #[cfg(feature = "local")]
 pub mod localprovider;
+
+/// On-disk cache of forked state
+pub mod cache;
+
+/// A provider serving fixed state from memory
+pub mod memoryprovider;
+
+/// Error of a failed request to a state provider.
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum ProviderError {
+    /// The request could not be sent, or no response came back.
+    #[error("{method}: transport error: {message}")]
+    Transport {
+        /// RPC method.
+        method: &'static str,
+        /// Transport error message.
+        message: String,
+    },
+    /// The node answered with a JSON-RPC error.
+    #[error("{method}: JSON-RPC error {code}: {message}")]
+    JsonRpc {
+        /// RPC method.
+        method: &'static str,
+        /// JSON-RPC error code.
+        code: i64,
+        /// JSON-RPC error message.
+        message: String,
+    },
+    /// The response could not be decoded.
+    #[error("{method}: decode error: {message}")]
+    Decode {
+        /// RPC method.
+        method: &'static str,
+        /// Decode error message.
+        message: String,
+    },
+    /// The provider knows no such block, transaction or receipt.
+    #[error("{method}: not found")]
+    NotFound {
+        /// RPC method.
+        method: &'static str,
+    },
+    /// The request missed an offline cache.
+    #[error("{method}: not in the offline cache")]
+    CacheMiss {
+        /// RPC method.
+        method: &'static str,
+    },
+    /// The provider url could not be parsed.
+    #[error("invalid provider url {url}: {message}")]
+    InvalidUrl {
+        /// The url as given.
+        url: String,
+        /// Parse error message.
+        message: String,
+    },
+}
+
+impl ProviderError {
+    /// The RPC method that failed, empty if no request was made.
+    pub fn method(&self) -> &'static str {
+        match self {
+            ProviderError::Transport { method, .. }
+            | ProviderError::JsonRpc { method, .. }
+            | ProviderError::Decode { method, .. }
+            | ProviderError::NotFound { method }
+            | ProviderError::CacheMiss { method } => method,
+            ProviderError::InvalidUrl { .. } => "",
+        }
+    }
+}
+
+/// State of one account, as fetched in a single request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountState {
+    /// Account balance.
+    pub balance: U256,
+    /// Account nonce.
+    pub nonce: U256,
+    /// Account code.
+    pub code: Vec<u8>,
+    /// Values of the requested storage slots.
+    pub storage: Vec<(H256, H256)>,
+}
+
+/// Source of the forked chain's state and history.
+pub trait StateProvider {
+    /// Latest block number.
+    fn get_block_number(&self) -> Result<U256, ProviderError>;
+    /// Block by number, with transaction hashes.
+    fn get_block_by_number(
+        &self,
+        bn: U256,
+    ) -> Result<web3::types::Block<web3::types::H256>, ProviderError>;
+    /// Block by number, with full transactions.
+    fn get_block_by_number_txs(
+        &self,
+        bn: U256,
+    ) -> Result<web3::types::Block<web3::types::Transaction>, ProviderError>;
+    /// Block by hash, with transaction hashes.
+    fn get_block_by_hash(
+        &self,
+        bh: H256,
+    ) -> Result<web3::types::Block<web3::types::H256>, ProviderError>;
+    /// Block by hash, with full transactions.
+    fn get_block_by_hash_txs(
+        &self,
+        bh: web3::types::H256,
+    ) -> Result<web3::types::Block<web3::types::Transaction>, ProviderError>;
+    /// Storage of `address` at `index`, at `block` or the latest one.
+    fn get_storage_at(
+        &self,
+        address: H160,
+        index: H256,
+        block: Option<U256>,
+    ) -> Result<H256, ProviderError>;
+    /// Code of `address`, at `block` or the latest one.
+    fn get_code(&self, address: H160, block: Option<U256>) -> Result<Vec<u8>, ProviderError>;
+    /// Balance of `address`, at `block` or the latest one.
+    fn get_balance(&self, address: H160, block: Option<U256>) -> Result<U256, ProviderError>;
+    /// Nonce of `address`, at `block` or the latest one.
+    fn get_transaction_count(
+        &self,
+        address: H160,
+        block: Option<U256>,
+    ) -> Result<U256, ProviderError>;
+    /// Balance, nonce, code and the given storage `slots` of `address`, at
+    /// `block` or the latest one. Providers should fetch them in one round
+    /// trip where they can.
+    fn get_account(
+        &self,
+        address: H160,
+        slots: &[H256],
+        block: Option<U256>,
+    ) -> Result<AccountState, ProviderError> {
+        let mut storage = Vec::with_capacity(slots.len());
+        for slot in slots {
+            storage.push((*slot, self.get_storage_at(address, *slot, block)?));
+        }
+        Ok(AccountState {
+            balance: self.get_balance(address, block)?,
+            nonce: self.get_transaction_count(address, block)?,
+            code: self.get_code(address, block)?,
+            storage,
+        })
+    }
+    /// Transaction by hash.
+    fn get_transaction(&self, hash: H256) -> Result<web3::types::Transaction, ProviderError>;
+    /// Receipt of a transaction by hash.
+    fn get_transaction_receipt(&self, hash: H256) -> Result<TxReceipt, ProviderError>;
+    /// Logs emitted between two blocks by `addrs`, matching `topics`.
+    fn get_logs(
+        &self,
+        from: U256,
+        to: U256,
+        addrs: Vec<H160>,
+        topics: Vec<H256>,
+    ) -> Result<Vec<web3::types::Log>, ProviderError>;
+}
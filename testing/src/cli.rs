@@ -0,0 +1,273 @@
+use crate::compiler::Compiler;
+use crate::shared::*;
+use crate::tester::is_fail_test;
+use crate::tester::tester_types::{to_contract_name, TokensOrString};
+use crate::{start_evm, start_tester, DEFAULT_PROVIDER, DEFAULT_WORKERS};
+
+use actix::Addr;
+use evm::backend::ForkMemoryBackendOwned;
+use evm::executor::Cheatcodes;
+use futures::future::join_all;
+use regex::Regex;
+use service::shared::EthRequest;
+use service::EVM::{EVMService, Flush};
+use std::sync::Arc;
+use web3::types::U256;
+
+const USAGE: &str = "usage: cevm test <contracts-dir> [--fork-url <url>] [--fork-block <number>] \
+                     [--match <regex>] [--out <dir>] [--jobs <workers>] [--fuzz-runs <runs>] \
+                     [--fuzz-seed <seed>]";
+
+/// Options of the `cevm test` command.
+pub struct TestArgs {
+    pub contracts: String,
+    pub out: String,
+    pub fork_url: String,
+    pub fork_block: Option<U256>,
+    pub filter: Option<Regex>,
+    pub jobs: usize,
+    pub fuzz_runs: Option<u32>,
+    pub fuzz_seed: Option<u64>,
+}
+
+impl TestArgs {
+    /// Parse the arguments following `cevm test`.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut contracts = None;
+        let mut out = None;
+        let mut fork_url = DEFAULT_PROVIDER.to_string();
+        let mut fork_block = None;
+        let mut filter = None;
+        let mut jobs = DEFAULT_WORKERS;
+        let mut fuzz_runs = None;
+        let mut fuzz_seed = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--fork-url" => fork_url = value()?,
+                "--fork-block" => {
+                    let block = value()?;
+                    fork_block = Some(
+                        U256::from_dec_str(&block)
+                            .map_err(|_| format!("invalid block number {}", block))?,
+                    );
+                }
+                "--match" => {
+                    let pattern = value()?;
+                    filter = Some(Regex::new(&pattern).map_err(|e| e.to_string())?);
+                }
+                "--out" => out = Some(value()?),
+                "--jobs" => {
+                    let workers = value()?;
+                    jobs = match workers.parse() {
+                        Ok(jobs) if jobs > 0 => jobs,
+                        _ => return Err(format!("invalid number of jobs {}", workers)),
+                    };
+                }
+                "--fuzz-runs" => {
+                    let runs = value()?;
+                    fuzz_runs = match runs.parse() {
+                        Ok(runs) if runs > 0 => Some(runs),
+                        _ => return Err(format!("invalid number of fuzz runs {}", runs)),
+                    };
+                }
+                "--fuzz-seed" => {
+                    let seed = value()?;
+                    fuzz_seed = Some(
+                        seed.parse()
+                            .map_err(|_| format!("invalid fuzz seed {}", seed))?,
+                    );
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ if contracts.is_none() => contracts = Some(arg.clone()),
+                _ => return Err(format!("unexpected argument {}", arg)),
+            }
+        }
+        let contracts = contracts.ok_or_else(|| "missing contracts dir".to_string())?;
+        Ok(TestArgs {
+            out: out.unwrap_or_else(|| format!("{}/out", contracts.trim_end_matches('/'))),
+            contracts,
+            fork_url,
+            fork_block,
+            filter,
+            jobs,
+            fuzz_runs,
+            fuzz_seed,
+        })
+    }
+}
+
+/// Outcome of a test: a summary of its fuzz runs if it passed, or the
+/// reason it failed.
+fn outcome(test: &str, resp: TestResponse) -> Result<String, String> {
+    let results = match resp {
+        TestResponse::Test(results) => results,
+        TestResponse::Failure(e) => return Err(e),
+        _ => return Err("unexpected response from the tester".to_string()),
+    };
+    let result = results
+        .last()
+        .ok_or_else(|| "test did not run".to_string())?;
+    let fuzz = result.fuzz.as_ref();
+    if let Some((seed, error)) = fuzz.and_then(|fuzz| fuzz.error.as_ref().map(|e| (fuzz.seed, e))) {
+        return Err(format!("error: {} (seed: {})", error, seed));
+    }
+    let trace = match result.trace.as_ref().and_then(|trace| trace.first()) {
+        Some(trace) => trace,
+        None => return Err("test did not run".to_string()),
+    };
+    if let Some(fuzz) = fuzz.filter(|fuzz| fuzz.runs == 0) {
+        return Err(format!(
+            "all {} inputs were rejected by assume (seed: {})",
+            fuzz.rejected, fuzz.seed
+        ));
+    }
+    let reason = match (trace.success, is_fail_test(test)) {
+        (true, false) | (false, true) => {
+            return Ok(fuzz.map_or(String::new(), |fuzz| format!(" (runs: {})", fuzz.runs)));
+        }
+        (true, true) => "test succeeded, expected a failure".to_string(),
+        (false, false) => match &trace.output {
+            TokensOrString::Tokens(reasons) if !reasons.is_empty() => reasons
+                .iter()
+                .map(|reason| reason.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => "reverted".to_string(),
+        },
+    };
+    match fuzz.and_then(|fuzz| fuzz.counterexample.as_ref().map(|args| (fuzz.seed, args))) {
+        Some((seed, args)) => Err(format!(
+            "{}; counterexample: ({}) (seed: {})",
+            reason,
+            args.iter()
+                .map(|(name, value)| format!("{} = {}", name, value))
+                .collect::<Vec<_>>()
+                .join(", "),
+            seed
+        )),
+        None => Err(reason),
+    }
+}
+
+/// Compile the contracts and run their tests, printing the result of each
+/// test. Returns the exit code of the `cevm test` command.
+pub async fn test(args: &[String]) -> i32 {
+    let args = match TestArgs::parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let cheatcodes = Arc::new(Cheatcodes::standard());
+    let evm = match start_evm(args.fork_url.clone(), cheatcodes.clone()) {
+        Ok(evm) => evm,
+        Err(e) => {
+            eprintln!("couldn't fork {}: {}", args.fork_url, e);
+            return 1;
+        }
+    };
+    let code = run_tests(&args, evm.clone(), cheatcodes).await;
+    // The process exits without dropping the service, so write what was read
+    // from the fork now.
+    match evm.send(Flush).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("couldn't write the rpc cache: {}", e),
+        Err(e) => eprintln!("couldn't write the rpc cache: {}", e),
+    }
+    code
+}
+
+/// Run the tests of `args` on `evm`, returning the exit code.
+async fn run_tests(
+    args: &TestArgs,
+    evm: Addr<EVMService>,
+    cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
+) -> i32 {
+    if let Some(block) = args.fork_block {
+        if let Err(e) = evm.send(EthRequest::set_block(block)).await {
+            eprintln!("couldn't fork at block {}: {}", block, e);
+            return 1;
+        }
+    }
+    let tester = start_tester(evm, args.jobs, cheatcodes.clone());
+    let compiler = Compiler {
+        tester: tester.clone().recipient(),
+        cheatcodes,
+    };
+
+    let compiled = match compiler.compile(args.contracts.clone(), args.out.clone()) {
+        Ok(compiled) => compiled,
+        Err(e) => {
+            eprintln!("compilation failed: {}", e);
+            return 1;
+        }
+    };
+    let _ = tester.send(TestRequest::Solc(compiled)).await;
+    let mut tests = match tester.send(TestRequest::Tests).await {
+        Ok(Ok(TestResponse::Tests(tests))) => tests.into_iter().collect::<Vec<_>>(),
+        _ => {
+            eprintln!("couldn't list the tests");
+            return 1;
+        }
+    };
+    tests.sort();
+
+    let (mut passed, mut failed) = (0, 0);
+    for (src, mut names) in tests {
+        names.sort();
+        names.retain(|name| args.filter.as_ref().map_or(true, |f| f.is_match(name)));
+        if names.is_empty() {
+            continue;
+        }
+        println!("Running {} tests for {}", names.len(), src);
+        // The first test deploys and sets up the contract, the others then
+        // run in parallel on copies of its state.
+        let run = |name: String| {
+            let options = TestOptions {
+                fuzzRuns: args.fuzz_runs,
+                fuzzSeed: args.fuzz_seed,
+                ..Default::default()
+            };
+            let request = tester.send(TestRequest::Test(src.clone(), name.clone(), Some(options)));
+            async move {
+                let resp = match request.await {
+                    Ok(Ok(resp)) => resp,
+                    _ => TestResponse::UnknownError,
+                };
+                (name, resp)
+            }
+        };
+        let mut names = names.into_iter();
+        let mut results = Vec::new();
+        if let Some(first) = names.next() {
+            results.push(run(first).await);
+        }
+        results.extend(join_all(names.map(run)).await);
+        for (name, resp) in results {
+            match outcome(&name, resp) {
+                Ok(summary) => {
+                    passed += 1;
+                    println!("[PASS] {}::{}{}", to_contract_name(&src), name, summary);
+                }
+                Err(reason) => {
+                    failed += 1;
+                    println!("[FAIL] {}::{}: {}", to_contract_name(&src), name, reason);
+                }
+            }
+        }
+    }
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
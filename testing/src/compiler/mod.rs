@@ -13,9 +13,15 @@ use std::fs;
 
 use std::path::Path;
 
+use std::sync::Arc;
+
+use evm::backend::ForkMemoryBackendOwned;
+use evm::executor::Cheatcodes;
+
 pub mod solc_types;
 
 use crate::shared::*;
+use crate::tester::cheatcodes::{is_vm_interface, vm_interface};
 use solc_types::*;
 
 pub enum CompilerError {
@@ -25,6 +31,7 @@ pub enum CompilerError {
 
 pub struct Compiler {
     pub tester: Recipient<TestRequest>,
+    pub cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
 }
 
 impl Actor for Compiler {
@@ -41,12 +48,11 @@ impl Handler<CompilerRequest> for Compiler {
 
                 match solc_output {
                     Ok(solc_out) => {
-                        println!("compiled");
                         self.tester.do_send(TestRequest::Solc(solc_out));
                         CompilerResponse::Success
                     }
                     Err(e) => {
-                        println!("compilation failed: {:?}", e);
+                        eprintln!("compilation failed: {:?}", e);
                         CompilerResponse::Failure(e)
                     }
                 }
@@ -71,11 +77,27 @@ impl Compiler {
         input_dir: String,
         output_dir: String,
     ) -> std::result::Result<SolcOutput, String> {
-        println!("compiling");
         if !Path::new(&output_dir).exists() {
             fs::create_dir(output_dir.clone()).unwrap();
         }
 
+        // Vm.sol declares the registered cheatcodes for the tests to import,
+        // unless the user wrote their own
+        let vm = Path::new(&input_dir).join("Vm.sol");
+        match fs::read_to_string(&vm) {
+            Ok(source) if !is_vm_interface(&source) => {
+                eprintln!(
+                    "{} wasn't generated by cevm, leaving it as is",
+                    vm.display()
+                );
+            }
+            _ => {
+                if let Err(e) = fs::write(&vm, vm_interface(&self.cheatcodes)) {
+                    return Err(format!("Couldn't write {}: {}", vm.display(), e));
+                }
+            }
+        }
+
         match solc::compile_dir(input_dir, output_dir.clone()) {
             Err(e) => match e {
                 solc::error::Error(a, _b) => return Err(a.to_string()),
@@ -1,18 +1,20 @@
 use service::server::*;
 
 #[allow(non_snake_case)]
-use service::{shared::EthRequest, EVM::*};
+use service::EVM::*;
 
 use actix::clock::delay_for;
 use actix::prelude::*;
-use compiler::{solc_types::SolcOutput, Compiler};
+use compiler::Compiler;
 use std::time::Duration;
 use tester::Tester;
 use testing_server::TestingApi;
 
-use std::collections::HashMap;
-use web3::types::H160;
+use evm::backend::ForkMemoryBackendOwned;
+use evm::executor::Cheatcodes;
+use std::sync::{Arc, Mutex};
 
+pub mod cli;
 pub mod compiler;
 pub mod shared;
 pub mod tester;
@@ -20,19 +22,64 @@ pub mod testing_server;
 
 extern crate solc;
 
+const DEFAULT_PROVIDER: &str = "https://fee7372b6e224441b747bf1fde15b2bd.eth.rpc.rivet.cloud/";
+const DEFAULT_WORKERS: usize = 4;
+
 #[actix_rt::main]
 async fn main() {
-    let (evm, _api) = start_blockchain();
-    let (_compiler, _tester, _testing_api) = start_compiler_and_tester(evm.clone().recipient());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("test") {
+        std::process::exit(cli::test(&args[1..]).await);
+    }
+
+    let cheatcodes = Arc::new(Cheatcodes::standard());
+    let (evm, _api) = start_blockchain(cheatcodes.clone());
+    let (_compiler, _tester, _testing_api) = start_compiler_and_tester(evm, cheatcodes);
 
     loop {
         delay_for(Duration::from_secs(1000)).await;
     }
 }
 
-fn start_blockchain() -> (Addr<EVMService>, Addr<Api>) {
-    let provider = "https://fee7372b6e224441b747bf1fde15b2bd.eth.rpc.rivet.cloud/";
-    let evm = SyncArbiter::start(1, move || EVMService::new(provider));
+/// Start the service forking `provider`, or say why the fork couldn't be set
+/// up.
+fn start_evm(
+    provider: String,
+    cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
+) -> Result<Addr<EVMService>, String> {
+    // The provider blocks on its requests, which the runtime's thread can't do.
+    let mut evm = std::thread::spawn(move || EVMService::new(&provider))
+        .join()
+        .map_err(|_| "forking the provider panicked".to_string())??;
+    evm.cheatcodes = cheatcodes;
+    let evm = Mutex::new(evm);
+    Ok(SyncArbiter::start(1, move || {
+        evm.lock().expect("evm service lock poisoned").clone()
+    }))
+}
+
+fn start_tester(
+    evm: Addr<EVMService>,
+    workers: usize,
+    cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
+) -> Addr<Tester> {
+    let workers = SyncArbiter::start(workers, || EVMWorker);
+    Tester::new(
+        evm.clone().recipient(),
+        evm.recipient(),
+        workers.recipient(),
+        cheatcodes,
+    )
+    .start()
+}
+
+fn start_blockchain(
+    cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
+) -> (Addr<EVMService>, Addr<Api>) {
+    let evm = start_evm(DEFAULT_PROVIDER.to_string(), cheatcodes).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
     let api = Api {
         evm: evm.clone().recipient(),
     }
@@ -41,27 +88,19 @@ fn start_blockchain() -> (Addr<EVMService>, Addr<Api>) {
 }
 
 fn start_compiler_and_tester(
-    evm: Recipient<EthRequest>,
+    evm: Addr<EVMService>,
+    cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
 ) -> (Addr<Compiler>, Addr<Tester>, Addr<TestingApi>) {
-    let tester = Tester {
-        evm: evm.clone(),
-        sender: H160::zero(),
-        compiled: SolcOutput::default(),
-        contract_addresses: HashMap::new(),
-        contract_addresses_rev: HashMap::new(),
-        setup_tests: HashMap::new(),
-        sigs: HashMap::new(),
-        resolved: Vec::new(),
-    }
-    .start();
+    let tester = start_tester(evm.clone(), DEFAULT_WORKERS, cheatcodes.clone());
 
     let compiler = Compiler {
         tester: tester.clone().recipient(),
+        cheatcodes,
     }
     .start();
 
     let api = TestingApi {
-        evm,
+        evm: evm.recipient(),
         compiler: compiler.clone().recipient(),
         tester: tester.clone().recipient(),
     }
@@ -20,6 +20,8 @@ pub enum TestRequest {
 pub struct TestOptions {
     pub sender: Option<H160>,
     pub testerIsEOA: Option<bool>,
+    pub fuzzRuns: Option<u32>,
+    pub fuzzSeed: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -29,6 +31,22 @@ pub struct TestEVMResponse {
     pub logs: Option<Vec<SourcedLog>>,
     pub recs: Option<Vec<TxReceipt>>,
     pub trace: Option<Vec<SourceTrace>>,
+    pub fuzz: Option<FuzzResult>,
+}
+
+/// Outcome of fuzzing a test function with parameters.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct FuzzResult {
+    /// Seed the inputs were generated from.
+    pub seed: u64,
+    /// Number of inputs the test ran with.
+    pub runs: u32,
+    /// Number of inputs discarded by `assume`.
+    pub rejected: u32,
+    /// Shrunk arguments of the failing input, by parameter name.
+    pub counterexample: Option<Vec<(String, BetterToken)>>,
+    /// Fatal error fuzzing stopped on, such as the fork provider failing.
+    pub error: Option<String>,
 }
 
 #[derive(MessageResponse, Serialize, Deserialize, Debug)]
@@ -0,0 +1,81 @@
+use ethabi_next::{param_type::Reader, Function, Param, StateMutability};
+use evm::backend::Backend;
+use evm::executor::Cheatcodes;
+
+/// Parameters of a Solidity parameter list, such as `address who, bytes calldata data`.
+fn params(list: &str) -> Option<Vec<Param>> {
+    let mut params = Vec::new();
+    for (i, param) in list.split(',').enumerate() {
+        let words: Vec<&str> = param.split_whitespace().collect();
+        let kind = match words.first() {
+            Some(kind) => Reader::read(kind).ok()?,
+            None => continue,
+        };
+        let name = match words.last() {
+            Some(name) if words.len() > 1 && !is_location(name) => name.to_string(),
+            _ => format!("arg{}", i),
+        };
+        params.push(Param { name, kind });
+    }
+    Some(params)
+}
+
+fn is_location(word: &str) -> bool {
+    word == "memory" || word == "calldata" || word == "storage"
+}
+
+/// ABI of a Solidity function declaration, such as
+/// `function load(address who, bytes32 slot) external returns (bytes32)`.
+pub fn function(declaration: &str) -> Option<Function> {
+    let declaration = declaration.trim();
+    let declaration = declaration.strip_prefix("function ").unwrap_or(declaration);
+    let open = declaration.find('(')?;
+    let close = open + declaration[open..].find(')')?;
+    let modifiers = &declaration[close + 1..];
+    let outputs = match modifiers.find("returns") {
+        Some(returns) => {
+            let returns = &modifiers[returns..];
+            let open = returns.find('(')?;
+            let close = open + returns[open..].find(')')?;
+            params(&returns[open + 1..close])?
+        }
+        None => Vec::new(),
+    };
+    let words: Vec<&str> = modifiers.split_whitespace().collect();
+    let state_mutability = if words.contains(&"pure") {
+        StateMutability::Pure
+    } else if words.contains(&"view") {
+        StateMutability::View
+    } else if words.contains(&"payable") {
+        StateMutability::Payable
+    } else {
+        StateMutability::Nonpayable
+    };
+    Some(Function {
+        name: declaration[..open].trim().to_string(),
+        inputs: params(&declaration[open + 1..close])?,
+        outputs,
+        state_mutability,
+    })
+}
+
+/// Marks a `Vm` interface written by `vm_interface`.
+const GENERATED: &str = "// Generated from the cheatcodes registered in the executor, do not edit.";
+
+/// Source of the `Vm` interface declaring every registered cheatcode.
+pub fn vm_interface<B: Backend>(cheatcodes: &Cheatcodes<B>) -> String {
+    let mut source = format!(
+        "// SPDX-License-Identifier: UNLICENSED\n{}\npragma solidity >=0.5.0;\n\ninterface Vm {{\n",
+        GENERATED
+    );
+    for cheatcode in cheatcodes.iter() {
+        source += &format!("    {};\n", cheatcode.declaration.trim());
+    }
+    source += "}\n";
+    source
+}
+
+/// Whether `source` was written by `vm_interface`, and so can be regenerated.
+pub fn is_vm_interface(source: &str) -> bool {
+    source.lines().nth(1) == Some(GENERATED)
+}
@@ -0,0 +1,421 @@
+use crate::shared::FuzzResult;
+use crate::tester::tester_types::BetterToken;
+use ethabi_next::{Function, ParamType, Token};
+use evm::executor::ASSUME_MAGIC;
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use service::shared::EthResponse;
+use std::future::Future;
+use web3::types::{H160, U256};
+
+/// Number of inputs a test with parameters runs with by default.
+pub const DEFAULT_RUNS: u32 = 256;
+/// Number of runs spent shrinking a failing input.
+const SHRINK_RUNS: u32 = 512;
+/// Inputs `assume` may discard per run before fuzzing gives up.
+const REJECTS_PER_RUN: u32 = 10;
+
+/// How a test ran with one input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    /// The input was discarded by `assume`.
+    Rejected,
+    /// The test couldn't run, such as when the fork provider fails.
+    Error,
+}
+
+/// Status of a test run from its call trace, `expect_fail` being set for
+/// `testFail` tests.
+pub fn status(resp: &EthResponse, expect_fail: bool) -> Status {
+    let root = match resp {
+        EthResponse::eth_sendTransaction { error: Some(_), .. } => None,
+        EthResponse::eth_sendTransaction {
+            trace: Some(trace), ..
+        } => trace.first(),
+        _ => None,
+    };
+    match root {
+        Some(root) if !root.success && root.output == hex::encode(ASSUME_MAGIC) => Status::Rejected,
+        Some(root) if root.success != expect_fail => Status::Pass,
+        Some(_) => Status::Fail,
+        None => Status::Error,
+    }
+}
+
+/// Why a test run with `Status::Error` couldn't run.
+fn error(resp: &EthResponse) -> String {
+    match resp {
+        EthResponse::eth_sendTransaction {
+            error: Some(error), ..
+        } => error.clone(),
+        _ => "test did not run".to_string(),
+    }
+}
+
+/// A seed for tests run without one.
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
+/// A random word of `bits` bits, sign extended if `signed`, biased towards
+/// zero, one and the bounds of the type.
+fn random_word<R: Rng>(rng: &mut R, bits: usize, signed: bool) -> U256 {
+    let max = if bits >= 256 {
+        !U256::zero()
+    } else {
+        (U256::one() << bits) - 1
+    };
+    let value = match rng.gen_range(0, 10) {
+        0 => U256::zero(),
+        1 => U256::one(),
+        2 => max,
+        3 => max >> 1,
+        4 => (max >> 1) + 1,
+        _ => {
+            let mut word = [0u8; 32];
+            rng.fill(&mut word[..]);
+            U256::from_big_endian(&word) & max
+        }
+    };
+    if signed && bits < 256 && value.bit(bits - 1) {
+        value | !max
+    } else {
+        value
+    }
+}
+
+fn random_bytes<R: Rng>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rng.fill(&mut bytes[..]);
+    bytes
+}
+
+/// A random value of `kind`.
+pub fn random_token<R: Rng>(rng: &mut R, kind: &ParamType) -> Token {
+    match kind {
+        ParamType::Address => match rng.gen_range(0, 8) {
+            0 => Token::Address(H160::zero()),
+            _ => Token::Address(H160::from_slice(&random_bytes(rng, 20))),
+        },
+        ParamType::Bytes => {
+            let len = rng.gen_range(0, 65);
+            Token::Bytes(random_bytes(rng, len))
+        }
+        ParamType::FixedBytes(len) => Token::FixedBytes(random_bytes(rng, *len)),
+        ParamType::Int(bits) => Token::Int(random_word(rng, *bits, true)),
+        ParamType::Uint(bits) => Token::Uint(random_word(rng, *bits, false)),
+        ParamType::Bool => Token::Bool(rng.gen()),
+        ParamType::String => {
+            let len = rng.gen_range(0, 33);
+            Token::String((0..len).map(|_| rng.sample(Alphanumeric)).collect())
+        }
+        ParamType::Array(kind) => {
+            let len = rng.gen_range(0, 5);
+            Token::Array((0..len).map(|_| random_token(rng, kind)).collect())
+        }
+        ParamType::FixedArray(kind, len) => {
+            Token::FixedArray((0..*len).map(|_| random_token(rng, kind)).collect())
+        }
+        ParamType::Tuple(kinds) => {
+            Token::Tuple(kinds.iter().map(|kind| random_token(rng, kind)).collect())
+        }
+    }
+}
+
+/// Simpler values of the same type as `token`, simplest first.
+pub fn shrink_token(token: &Token) -> Vec<Token> {
+    match token {
+        Token::Address(address) if !address.is_zero() => vec![Token::Address(H160::zero())],
+        Token::Bytes(bytes) => shrink_vec(bytes).into_iter().map(Token::Bytes).collect(),
+        Token::FixedBytes(bytes) if bytes.iter().any(|b| *b != 0) => {
+            vec![Token::FixedBytes(vec![0; bytes.len()])]
+        }
+        Token::Int(value) if value.bit(255) => towards_zero(negate(*value))
+            .into_iter()
+            .map(|value| Token::Int(negate(value)))
+            .collect(),
+        Token::Int(value) => towards_zero(*value).into_iter().map(Token::Int).collect(),
+        Token::Uint(value) => towards_zero(*value).into_iter().map(Token::Uint).collect(),
+        Token::Bool(true) => vec![Token::Bool(false)],
+        Token::String(string) => {
+            let chars: Vec<char> = string.chars().collect();
+            shrink_vec(&chars)
+                .into_iter()
+                .map(|chars| Token::String(chars.into_iter().collect()))
+                .collect()
+        }
+        Token::Array(tokens) => {
+            let mut shrunk: Vec<Token> = shrink_vec(tokens).into_iter().map(Token::Array).collect();
+            shrunk.extend(shrink_each(tokens).into_iter().map(Token::Array));
+            shrunk
+        }
+        Token::FixedArray(tokens) => shrink_each(tokens)
+            .into_iter()
+            .map(Token::FixedArray)
+            .collect(),
+        Token::Tuple(tokens) => shrink_each(tokens).into_iter().map(Token::Tuple).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Values between zero and `value`, smallest first: zero, then `value`
+/// halved down to one, then `value` less its halves down to `value - 1`.
+fn towards_zero(value: U256) -> Vec<U256> {
+    let mut values = Vec::new();
+    if value.is_zero() {
+        return values;
+    }
+    values.push(U256::zero());
+    let bits = value.bits();
+    for shift in (1..bits).rev() {
+        values.push(value >> shift);
+    }
+    for shift in 2..bits {
+        values.push(value - (value >> shift));
+    }
+    if value > U256::one() {
+        values.push(value - 1);
+    }
+    values.dedup();
+    values
+}
+
+/// Two's complement negation of `value`.
+fn negate(value: U256) -> U256 {
+    (!value).overflowing_add(U256::one()).0
+}
+
+/// Shorter prefixes of `items`.
+fn shrink_vec<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    let mut shrunk = Vec::new();
+    if !items.is_empty() {
+        shrunk.push(Vec::new());
+    }
+    if items.len() > 2 {
+        shrunk.push(items[..items.len() / 2].to_vec());
+    }
+    if items.len() > 1 {
+        shrunk.push(items[..items.len() - 1].to_vec());
+    }
+    shrunk
+}
+
+/// Copies of `tokens` with one of them shrunk.
+fn shrink_each(tokens: &[Token]) -> Vec<Vec<Token>> {
+    let mut shrunk = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        for simpler in shrink_token(token) {
+            let mut tokens = tokens.to_vec();
+            tokens[i] = simpler;
+            shrunk.push(tokens);
+        }
+    }
+    shrunk
+}
+
+/// Run `function` with `runs` random inputs generated from `seed`, stopping
+/// at the first one it fails with. The failing input is then shrunk towards
+/// a minimal counterexample. Fuzzing stops without one if a run hits an
+/// error. Returns the response of the last input run, or of the
+/// counterexample, along with the outcome of fuzzing.
+pub async fn fuzz<F, Fut>(
+    function: &Function,
+    expect_fail: bool,
+    runs: u32,
+    seed: u64,
+    mut run: F,
+) -> (EthResponse, FuzzResult)
+where
+    F: FnMut(Vec<u8>) -> Fut,
+    Fut: Future<Output = EthResponse>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut result = FuzzResult {
+        seed,
+        ..Default::default()
+    };
+    let mut last = EthResponse::eth_unimplemented;
+    while result.runs < runs && result.rejected < runs.saturating_mul(REJECTS_PER_RUN) {
+        let args: Vec<Token> = function
+            .inputs
+            .iter()
+            .map(|param| random_token(&mut rng, &param.kind))
+            .collect();
+        let resp = run(encode(function, &args)).await;
+        match status(&resp, expect_fail) {
+            Status::Rejected => result.rejected += 1,
+            Status::Pass => result.runs += 1,
+            Status::Error => {
+                result.error = Some(error(&resp));
+                return (resp, result);
+            }
+            Status::Fail => {
+                result.runs += 1;
+                let (args, resp) = match shrink(function, expect_fail, args, resp, &mut run).await {
+                    Ok(shrunk) => shrunk,
+                    Err(resp) => {
+                        result.error = Some(error(&resp));
+                        return (resp, result);
+                    }
+                };
+                let counterexample = function
+                    .inputs
+                    .iter()
+                    .zip(args)
+                    .enumerate()
+                    .map(|(i, (param, arg))| {
+                        let name = if param.name.is_empty() {
+                            format!("arg{}", i)
+                        } else {
+                            param.name.clone()
+                        };
+                        (name, BetterToken::from(arg))
+                    })
+                    .collect();
+                result.counterexample = Some(counterexample);
+                return (resp, result);
+            }
+        }
+        last = resp;
+    }
+    (last, result)
+}
+
+/// Shrink the arguments `args` that `function` fails with, one argument at a
+/// time, for as long as it keeps failing. Returns the response of a run that
+/// hit an error instead.
+async fn shrink<F, Fut>(
+    function: &Function,
+    expect_fail: bool,
+    mut args: Vec<Token>,
+    mut resp: EthResponse,
+    run: &mut F,
+) -> Result<(Vec<Token>, EthResponse), EthResponse>
+where
+    F: FnMut(Vec<u8>) -> Fut,
+    Fut: Future<Output = EthResponse>,
+{
+    let mut budget = SHRINK_RUNS;
+    'shrink: loop {
+        for i in 0..args.len() {
+            for simpler in shrink_token(&args[i]) {
+                if budget == 0 {
+                    break 'shrink;
+                }
+                budget -= 1;
+                let mut next = args.clone();
+                next[i] = simpler;
+                let next_resp = run(encode(function, &next)).await;
+                match status(&next_resp, expect_fail) {
+                    Status::Fail => {
+                        args = next;
+                        resp = next_resp;
+                        continue 'shrink;
+                    }
+                    Status::Error => return Err(next_resp),
+                    _ => {}
+                }
+            }
+        }
+        break;
+    }
+    Ok((args, resp))
+}
+
+fn encode(function: &Function, args: &[Token]) -> Vec<u8> {
+    function
+        .encode_input(args)
+        .expect("Generated inputs match the function parameters")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evm::executor::CallTrace;
+    use web3::types::H256;
+
+    fn response(success: bool, output: &[u8], error: Option<&str>) -> EthResponse {
+        EthResponse::eth_sendTransaction {
+            hash: H256::zero(),
+            data: None,
+            logs: None,
+            recs: None,
+            trace: Some(vec![CallTrace {
+                success,
+                output: hex::encode(output),
+                ..Default::default()
+            }]),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn towards_zero_starts_at_zero() {
+        assert!(towards_zero(U256::zero()).is_empty());
+        assert_eq!(towards_zero(U256::one()), vec![U256::zero()]);
+        let values: Vec<u64> = towards_zero(U256::from(100))
+            .iter()
+            .map(U256::as_u64)
+            .collect();
+        assert_eq!(values, vec![0, 1, 3, 6, 12, 25, 50, 75, 88, 94, 97, 99]);
+    }
+
+    #[test]
+    fn negative_ints_shrink_towards_zero() {
+        let shrunk = shrink_token(&Token::Int(negate(U256::from(5))));
+        let expected: Vec<Token> = [0u64, 1, 2, 4]
+            .iter()
+            .map(|v| Token::Int(negate(U256::from(*v))))
+            .collect();
+        assert_eq!(shrunk, expected);
+        // -1 doesn't overflow on its way to zero.
+        assert_eq!(
+            shrink_token(&Token::Int(!U256::zero())),
+            vec![Token::Int(U256::zero())]
+        );
+    }
+
+    #[test]
+    fn random_signed_words_are_sign_extended() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let max = U256::from(0xff);
+        let mut negative = false;
+        for _ in 0..1000 {
+            let value = random_word(&mut rng, 8, true);
+            if value.bit(7) {
+                assert_eq!(value | max, !U256::zero());
+                negative = true;
+            } else {
+                assert!(value <= max);
+            }
+            assert!(random_word(&mut rng, 8, false) <= max);
+        }
+        assert!(negative);
+    }
+
+    #[test]
+    fn status_of_runs() {
+        assert_eq!(status(&response(true, &[], None), false), Status::Pass);
+        assert_eq!(status(&response(false, &[], None), false), Status::Fail);
+        assert_eq!(status(&response(false, &[], None), true), Status::Pass);
+        assert_eq!(
+            status(&response(false, ASSUME_MAGIC, None), false),
+            Status::Rejected
+        );
+        assert_eq!(
+            status(&response(false, ASSUME_MAGIC, None), true),
+            Status::Rejected
+        );
+        assert_eq!(
+            status(&response(false, &[], Some("eth_getProof failed")), true),
+            Status::Error
+        );
+        assert_eq!(
+            status(&EthResponse::eth_unimplemented, false),
+            Status::Error
+        );
+    }
+}
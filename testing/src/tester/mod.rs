@@ -1,19 +1,26 @@
 use crate::compiler::solc_types::*;
 use actix::prelude::*;
 #[allow(non_snake_case)]
-use evm::{backend::memory::TxReceipt, executor::CallTrace};
+use evm::{
+    backend::{memory::TxReceipt, ForkMemoryBackendOwned},
+    executor::{CallTrace, Cheatcodes, CHEATCODE_ADDRESS},
+};
 use service::shared::*;
-use web3::types::{Bytes, TransactionRequest, H160, U256};
+use service::EVM::{EVMService, EVMSnapshot, Isolated, Snapshot};
+use web3::types::{Bytes, TransactionRequest, H160, H256, U256};
 
 use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 use crate::shared::*;
 // use ethabi_next::*;
 use serde_json::Value as JsonValue;
 
+pub mod cheatcodes;
+pub mod fuzz;
 pub mod tester_types;
 
-use ethabi_next::{Contract, Function, Param, ParamType, RawLog, StateMutability};
+use ethabi_next::{Contract, Function, Param, ParamType, RawLog, StateMutability, Token};
 use tester_types::*;
 
 #[derive(Clone)]
@@ -23,9 +30,14 @@ pub struct Tester {
     pub compiled: SolcOutput,
     pub contract_addresses: HashMap<H160, Option<String>>,
     pub contract_addresses_rev: HashMap<String, Option<H160>>,
-    pub setup_tests: HashMap<String, bool>,
+    pub labels: HashMap<H160, String>,
     pub sigs: HashMap<String, String>,
     pub resolved: Vec<EthResponse>,
+    pub cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
+    pub snapshotter: Recipient<Snapshot>,
+    pub workers: Recipient<Isolated>,
+    /// State after deploying and setting up each test contract, by source
+    pub snapshots: HashMap<String, EVMService>,
 }
 
 impl Actor for Tester {
@@ -33,6 +45,28 @@ impl Actor for Tester {
 }
 
 impl Tester {
+    pub fn new(
+        evm: Recipient<EthRequest>,
+        snapshotter: Recipient<Snapshot>,
+        workers: Recipient<Isolated>,
+        cheatcodes: Arc<Cheatcodes<ForkMemoryBackendOwned>>,
+    ) -> Self {
+        Tester {
+            evm,
+            sender: H160::zero(),
+            compiled: SolcOutput::default(),
+            contract_addresses: HashMap::new(),
+            contract_addresses_rev: HashMap::new(),
+            labels: HashMap::new(),
+            sigs: HashMap::new(),
+            resolved: Vec::new(),
+            cheatcodes,
+            snapshotter,
+            workers,
+            snapshots: HashMap::new(),
+        }
+    }
+
     pub fn get_tests(&self) -> HashMap<String, Vec<String>> {
         let mut tests: HashMap<String, Vec<String>> = HashMap::new();
         for (src, contract) in self.compiled.contracts.iter() {
@@ -60,10 +94,7 @@ impl Tester {
     }
 
     pub fn is_setup(&self, src: &str) -> bool {
-        match self.setup_tests.get(src) {
-            Some(b) => *b,
-            _ => false,
-        }
+        self.snapshots.contains_key(src)
     }
 
     pub async fn deploy(
@@ -92,7 +123,7 @@ impl Tester {
             .await;
 
         let eth_resp = result.unwrap_or_else(|e| {
-            println!("Failed to unwrap deploy result, result: {:?}", e);
+            eprintln!("Failed to unwrap deploy result, result: {:?}", e);
             EthResponse::eth_unimplemented
         });
 
@@ -123,7 +154,7 @@ impl Tester {
             .await;
 
         let eth_resp = result.unwrap_or_else(|e| {
-            println!("Failed to unwrap temp deploy result, result: {:?}", e);
+            eprintln!("Failed to unwrap temp deploy result, result: {:?}", e);
             EthResponse::eth_unimplemented
         });
 
@@ -150,18 +181,29 @@ impl Tester {
             .await;
 
         let eth_resp = eth_resp.unwrap_or_else(|e| {
-            println!("Failed to unwrap setup result, result: {:?}", e);
+            eprintln!("Failed to unwrap setup result, result: {:?}", e);
             EthResponse::eth_unimplemented
         });
 
         eth_resp
     }
 
+    /// Copy of the EVM's state, for running tests in isolation.
+    pub async fn snapshot(evm: Recipient<Snapshot>) -> EVMService {
+        let EVMSnapshot(snapshot) = evm
+            .send(Snapshot)
+            .await
+            .expect("Failed to snapshot the evm");
+        snapshot
+    }
+
+    /// Run a test on its own copy of `snapshot`, on one of the `workers`.
     pub async fn test(
         sender: H160,
         input: Vec<u8>,
         contract: H160,
-        evm: Recipient<EthRequest>,
+        snapshot: EVMService,
+        workers: Recipient<Isolated>,
     ) -> EthResponse {
         let tx = TransactionRequest {
             from: sender,
@@ -173,19 +215,18 @@ impl Tester {
             nonce: None,
             condition: None,
         };
-        let eth_resp = evm
-            .send(EthRequest::eth_sendTransaction(
-                tx,
-                Some(vec![
-                    "receipt".to_string(),
-                    "trace".to_string(),
-                    "no_commit".to_string(),
-                ]),
-            ))
-            .await;
+        let request = EthRequest::eth_sendTransaction(
+            tx,
+            Some(vec![
+                "receipt".to_string(),
+                "trace".to_string(),
+                "no_commit".to_string(),
+            ]),
+        );
+        let eth_resp = workers.send(Isolated(snapshot, request)).await;
 
         let eth_resp = eth_resp.unwrap_or_else(|e| {
-            println!("Failed to unwrap test result, result: {:?}", e);
+            eprintln!("Failed to unwrap test result, result: {:?}", e);
             EthResponse::eth_unimplemented
         });
 
@@ -195,12 +236,27 @@ impl Tester {
     pub async fn get_code(address: H160, evm: Recipient<EthRequest>) -> EthResponse {
         let result = evm.send(EthRequest::eth_getCode(address, None)).await;
         let res = result.unwrap_or_else(|e| {
-            println!("Failed to unwrap get_code result, result: {:?}", e);
+            eprintln!("Failed to unwrap get_code result, result: {:?}", e);
             EthResponse::eth_unimplemented
         });
         res
     }
 
+    /// Creation code of every deployable contract, by full source name and
+    /// by contract name, for the `getCode` and `deployCode` cheatcodes.
+    pub fn artifacts(&self) -> BTreeMap<String, Vec<u8>> {
+        let mut artifacts = BTreeMap::new();
+        for (src, contract) in self.compiled.contracts.iter() {
+            let bin = match hex::decode(&contract.bin) {
+                Ok(bin) if !bin.is_empty() => bin,
+                _ => continue,
+            };
+            artifacts.insert(to_contract_name(src).to_string(), bin.clone());
+            artifacts.insert(src.clone(), bin);
+        }
+        artifacts
+    }
+
     fn add_cheat_codes(&mut self) {
         let mut hax = SolcContract {
             bin: String::new(),
@@ -217,76 +273,22 @@ impl Tester {
             },
             ast: None,
         };
-        hax.abi.functions.insert(
-            "roll".to_string(),
-            vec![Function {
-                name: "roll".to_string(),
-                inputs: vec![Param {
-                    name: "time".to_string(),
-                    kind: ParamType::Uint(256),
-                }],
-                outputs: vec![],
-                state_mutability: StateMutability::Nonpayable,
-            }],
-        );
-        hax.abi.functions.insert(
-            "warp".to_string(),
-            vec![Function {
-                name: "warp".to_string(),
-                inputs: vec![Param {
-                    name: "bn".to_string(),
-                    kind: ParamType::Uint(256),
-                }],
-                outputs: vec![],
-                state_mutability: StateMutability::Nonpayable,
-            }],
-        );
-        hax.abi.functions.insert(
-            "store".to_string(),
-            vec![Function {
-                name: "store".to_string(),
-                inputs: vec![
-                    Param {
-                        name: "who".to_string(),
-                        kind: ParamType::Address,
-                    },
-                    Param {
-                        name: "slot".to_string(),
-                        kind: ParamType::FixedBytes(32),
-                    },
-                    Param {
-                        name: "val".to_string(),
-                        kind: ParamType::FixedBytes(32),
-                    },
-                ],
-                outputs: vec![],
-                state_mutability: StateMutability::Nonpayable,
-            }],
-        );
-        hax.abi.functions.insert(
-            "load".to_string(),
-            vec![Function {
-                name: "load".to_string(),
-                inputs: vec![
-                    Param {
-                        name: "who".to_string(),
-                        kind: ParamType::Address,
-                    },
-                    Param {
-                        name: "slot".to_string(),
-                        kind: ParamType::FixedBytes(32),
-                    },
-                ],
-                outputs: vec![],
-                state_mutability: StateMutability::View,
-            }],
-        );
+        for cheatcode in self.cheatcodes.iter() {
+            match cheatcodes::function(cheatcode.declaration) {
+                Some(function) => hax
+                    .abi
+                    .functions
+                    .entry(function.name.clone())
+                    .or_default()
+                    .push(function),
+                None => eprintln!("couldn't parse cheatcode {}", cheatcode.declaration),
+            }
+        }
         self.compiled.contracts.insert("Cheater".to_string(), hax);
-        let addr: H160 = "7109709ECfa91a80626fF3989D68f67F5b1DD12D".parse().unwrap();
         self.contract_addresses
-            .insert(addr, Some("Cheater".to_string()));
+            .insert(CHEATCODE_ADDRESS, Some("Cheater".to_string()));
         self.contract_addresses_rev
-            .insert("Cheater".to_string(), Some(addr));
+            .insert("Cheater".to_string(), Some(CHEATCODE_ADDRESS));
     }
 }
 
@@ -303,8 +305,8 @@ pub struct TestInfo {
     pub is_setup: bool,
     pub contract_addresses: HashMap<H160, Option<String>>,
     pub contract_addresses_rev: HashMap<String, Option<H160>>,
+    pub labels: HashMap<H160, String>,
     pub contracts: HashMap<String, SolcContract>,
-    pub setup_tests: HashMap<String, bool>,
     pub sigs: HashMap<String, String>,
     pub results: Vec<TestEVMResponse>,
 }
@@ -316,6 +318,14 @@ impl TestInfo {
         logs
     }
 
+    /// The label of `address`, or its hex encoding if it has none.
+    fn label_or_hex(&self, address: &H160) -> String {
+        match self.labels.get(address) {
+            Some(label) => label.clone(),
+            None => hex::encode(address.as_bytes()),
+        }
+    }
+
     pub fn parse_events(&self, logs: Vec<evm::backend::Log>) -> Vec<SourcedLog> {
         let mut ls = Vec::new();
         for log in logs.iter() {
@@ -349,14 +359,14 @@ impl TestInfo {
                     }
                 } else {
                     ls.push(SourcedLog {
-                        name: hex::encode(log.address.as_bytes()),
+                        name: self.label_or_hex(&log.address),
                         event: event_name.clone(),
                         log: ParsedOrNormalLog::NotParsed(HexLog::from(log.clone())),
                     });
                 }
             } else {
                 ls.push(SourcedLog {
-                    name: hex::encode(log.address.as_bytes()),
+                    name: self.label_or_hex(&log.address),
                     event: event_name.clone(),
                     log: ParsedOrNormalLog::NotParsed(HexLog::from(log.clone())),
                 });
@@ -365,6 +375,83 @@ impl TestInfo {
         ls
     }
 
+    /// Decode the output of the failed call `trace`. An `expectEmit` failure
+    /// lists the expected log and the logs the call emitted, decoded with the
+    /// contract ABIs.
+    pub fn parse_revert(&self, trace: &CallTrace) -> Vec<Token> {
+        let output = trace.output.clone();
+        if output.len() < 8 || &output[0..8] != "ca7fc3da" {
+            return parse_error(output);
+        }
+        let not_emitted = Function {
+            name: "LogNotEmitted".to_string(),
+            inputs: vec![],
+            outputs: vec![
+                Param {
+                    name: "emitter".to_string(),
+                    kind: ParamType::Address,
+                },
+                Param {
+                    name: "topics".to_string(),
+                    kind: ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+                },
+                Param {
+                    name: "data".to_string(),
+                    kind: ParamType::Bytes,
+                },
+            ],
+            state_mutability: StateMutability::View,
+        };
+        let tokens = match not_emitted.decode_output(&hex::decode(&output[8..]).unwrap()) {
+            Ok(tokens) => tokens,
+            Err(_) => return parse_error(output),
+        };
+        let mut tokens = tokens.into_iter();
+        let log = evm::backend::Log {
+            address: tokens
+                .next()
+                .and_then(Token::into_address)
+                .unwrap_or_default(),
+            topics: tokens
+                .next()
+                .and_then(Token::into_array)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(Token::into_fixed_bytes)
+                .map(|topic| H256::from_slice(&topic))
+                .collect(),
+            data: tokens
+                .next()
+                .and_then(Token::into_bytes)
+                .unwrap_or_default(),
+        };
+        let mut emitted = Vec::new();
+        let mut stack = vec![trace];
+        while let Some(trace) = stack.pop() {
+            emitted.extend(trace.logs.iter().map(|log| self.describe_log(log.clone())));
+            stack.extend(trace.inner.iter().rev());
+        }
+        vec![Token::String(format!(
+            "Expected log not emitted: {}, emitted: [{}]",
+            self.describe_log(log),
+            emitted.join(", ")
+        ))]
+    }
+
+    /// `log` decoded with the ABI of its emitter, or hex encoded if it can't
+    /// be.
+    fn describe_log(&self, log: evm::backend::Log) -> String {
+        let parsed = if log.topics.is_empty() {
+            Vec::new()
+        } else {
+            self.parse_events(vec![log.clone()])
+        };
+        match parsed.first() {
+            Some(parsed) => format!("{}.{} {:?}", parsed.name, parsed.event, parsed.log),
+            None => format!("{:?}", HexLog::from(log)),
+        }
+    }
+
     pub fn parse_call_trace(&self, trace: Vec<CallTrace>) -> Vec<SourceTrace> {
         let mut traces = Vec::with_capacity(trace.len());
         for t in trace.iter() {
@@ -392,7 +479,7 @@ impl TestInfo {
                                 tss.push(BetterToken::from(t.clone()));
                             }
                             if !t.success {
-                                out_tokens = parse_error(t.output.clone());
+                                out_tokens = self.parse_revert(t);
                             } else {
                                 out_tokens = f
                                     .decode_output(&hex::decode(t.output.clone()).unwrap())
@@ -422,7 +509,7 @@ impl TestInfo {
                     if !found {
                         let out;
                         if !t.success {
-                            out_tokens = parse_error(t.output.clone());
+                            out_tokens = self.parse_revert(t);
                             let mut tso = Vec::new();
                             for t in out_tokens.iter() {
                                 tso.push(BetterToken::from(t.clone()));
@@ -448,7 +535,7 @@ impl TestInfo {
                 } else {
                     let out;
                     if !t.success {
-                        out_tokens = parse_error(t.output.clone());
+                        out_tokens = self.parse_revert(t);
                         let mut tso = Vec::new();
                         for t in out_tokens.iter() {
                             tso.push(BetterToken::from(t.clone()));
@@ -458,7 +545,7 @@ impl TestInfo {
                         out = TokensOrString::String(t.output.clone());
                     }
                     traces.push(SourceTrace {
-                        name: String::new(),
+                        name: self.labels.get(&t.addr).cloned().unwrap_or_default(),
                         address: t.addr,
                         success: t.success,
                         created: t.created,
@@ -473,7 +560,7 @@ impl TestInfo {
             } else {
                 let out;
                 if !t.success {
-                    out_tokens = parse_error(t.output.clone());
+                    out_tokens = self.parse_revert(t);
                     let mut tso = Vec::new();
                     for t in out_tokens.iter() {
                         tso.push(BetterToken::from(t.clone()));
@@ -483,7 +570,7 @@ impl TestInfo {
                     out = TokensOrString::String(t.output.clone());
                 }
                 traces.push(SourceTrace {
-                    name: String::new(),
+                    name: self.labels.get(&t.addr).cloned().unwrap_or_default(),
                     address: t.addr,
                     success: t.success,
                     created: t.created,
@@ -499,7 +586,24 @@ impl TestInfo {
         traces
     }
 
-    pub fn from_eth_resp(&self, eth_resp: EthResponse) -> TestEVMResponse {
+    /// Record the names given by the `label` cheatcode to the addresses of
+    /// `traces`.
+    pub fn add_labels(&mut self, traces: &[CallTrace]) {
+        for t in traces.iter() {
+            if let Some(label) = &t.label {
+                self.labels.insert(t.addr, label.clone());
+            }
+            self.add_labels(&t.inner);
+        }
+    }
+
+    pub fn from_eth_resp(&mut self, eth_resp: EthResponse) -> TestEVMResponse {
+        if let EthResponse::eth_sendTransaction {
+            trace: Some(trace), ..
+        } = &eth_resp
+        {
+            self.add_labels(trace);
+        }
         match eth_resp {
             EthResponse::eth_sendTransaction {
                 hash,
@@ -507,6 +611,7 @@ impl TestInfo {
                 logs,
                 recs,
                 trace,
+                error: _,
             } => {
                 let mut d = None;
                 if let Some(da) = data {
@@ -527,6 +632,7 @@ impl TestInfo {
                     logs: l,
                     recs,
                     trace: t,
+                    fuzz: None,
                 }
             }
             _ => TestEVMResponse::default(),
@@ -555,10 +661,14 @@ pub fn flatten_call_addrs(
     addrs
 }
 
+/// Response of a test contract's setUp, if it ran, with the state it left and
+/// what is known of the contract so far.
+type SetUp = (Option<EthResponse>, Option<EVMService>, TestInfo);
+
 impl Handler<TestRequest> for Tester {
     type Result = ResponseActFuture<Self, Result<TestResponse, ()>>;
 
-    fn handle(&mut self, msg: TestRequest, _ctx: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: TestRequest, ctx: &mut Context<Self>) -> Self::Result {
         match msg {
             TestRequest::Tests => Box::pin(
                 async {}
@@ -578,8 +688,8 @@ impl Handler<TestRequest> for Tester {
                     is_setup: true,
                     contract_addresses: self.contract_addresses.clone(),
                     contract_addresses_rev: self.contract_addresses_rev.clone(),
+                    labels: self.labels.clone(),
                     contracts: self.compiled.contracts.clone(),
-                    setup_tests: self.setup_tests.clone(),
                     sigs: self.sigs.clone(),
                     results: Vec::new(),
                 };
@@ -589,7 +699,8 @@ impl Handler<TestRequest> for Tester {
                         .evm
                         .send(EthRequest::eth_sim(hash, in_place, opts))
                         .await;
-                    t_info.results.push(t_info.from_eth_resp(sim_resp.unwrap()));
+                    let result = t_info.from_eth_resp(sim_resp.unwrap());
+                    t_info.results.push(result);
                     Ok(TestResponse::Sim(t_info.results))
                 };
                 let me = e.into_actor(self);
@@ -606,6 +717,8 @@ impl Handler<TestRequest> for Tester {
                 }
 
                 let mut isEOA = true;
+                let mut fuzz_runs = fuzz::DEFAULT_RUNS;
+                let mut fuzz_seed = None;
                 if let Some(ops) = opts {
                     if let Some(sender) = ops.sender {
                         self.sender = sender;
@@ -613,10 +726,14 @@ impl Handler<TestRequest> for Tester {
                     if let Some(EOA) = ops.testerIsEOA {
                         isEOA = EOA;
                     }
+                    if let Some(runs) = ops.fuzzRuns {
+                        fuzz_runs = runs.max(1);
+                    }
+                    fuzz_seed = ops.fuzzSeed;
                 }
-                println!("isEOA {:?}", isEOA);
 
                 let is_deployed = self.is_deployed(&src);
+                let is_setup = self.is_setup(&src);
                 let mut contract = H160::zero();
                 let mut bytecode = None;
                 if !is_deployed {
@@ -637,11 +754,11 @@ impl Handler<TestRequest> for Tester {
                     evm: self.evm.clone(),
                     bytecode,
                     is_deployed,
-                    is_setup: self.is_setup(&src),
+                    is_setup,
                     contract_addresses: self.contract_addresses.clone(),
                     contract_addresses_rev: self.contract_addresses_rev.clone(),
+                    labels: self.labels.clone(),
                     contracts: self.compiled.contracts.clone(),
-                    setup_tests: self.setup_tests.clone(),
                     sigs: self.sigs.clone(),
                     results: Vec::new(),
                 };
@@ -649,7 +766,6 @@ impl Handler<TestRequest> for Tester {
                 let deploy = async move {
                     let mut deployed = None;
                     if !is_deployed {
-                        println!("running deployment");
                         deployed = Some(
                             Self::deploy(
                                 t_info.sender,
@@ -666,6 +782,7 @@ impl Handler<TestRequest> for Tester {
                 let setup = |(maybe_deploy_resp, mut t_info): (Option<EthResponse>, TestInfo),
                              act: &mut Self,
                              _ctx2: &mut Context<Self>| {
+                    let snapshotter = act.snapshotter.clone();
                     async move {
                         if let Some(deploy_resp) = maybe_deploy_resp {
                             let recs = deploy_resp.clone().tx_receipts().unwrap();
@@ -713,11 +830,14 @@ impl Handler<TestRequest> for Tester {
                                 .get(&t_info.src)
                                 .unwrap()
                                 .unwrap();
-                            t_info.results.push(t_info.from_eth_resp(deploy_resp));
+                            let result = t_info.from_eth_resp(deploy_resp);
+                            t_info.results.push(result);
                         }
+                        // setUp runs once, every test then starts from a copy of
+                        // the state it left
                         let mut setup = None;
+                        let mut snapshot = None;
                         if !t_info.is_setup {
-                            t_info.setup_tests.insert(t_info.src.clone(), true);
                             let mut sender = t_info.sender;
                             if t_info.testerIsEOA {
                                 sender = t_info.contract;
@@ -725,17 +845,38 @@ impl Handler<TestRequest> for Tester {
                             setup = Some(
                                 Self::setup(sender, t_info.contract, t_info.evm.clone()).await,
                             );
+                            snapshot = Some(Self::snapshot(snapshotter).await);
                         }
-                        (setup, t_info)
+                        (setup, snapshot, t_info)
                     }
                     .into_actor(act)
                 };
 
                 let f = deploy.then(setup);
+                let f: ResponseActFuture<Self, SetUp> = if is_setup {
+                    Box::pin(f)
+                } else {
+                    // Deploy and run setUp before handling any other message,
+                    // so tests of this contract sent together find its
+                    // snapshot instead of setting up again.
+                    let (tx, rx) = futures::channel::oneshot::channel();
+                    ctx.wait(f.map(|(setup, snapshot, t_info), act, _ctx| {
+                        if let Some(snapshot) = &snapshot {
+                            act.snapshots.insert(t_info.src.clone(), snapshot.clone());
+                        }
+                        act.contract_addresses = t_info.contract_addresses.clone();
+                        act.contract_addresses_rev = t_info.contract_addresses_rev.clone();
+                        let _ = tx.send((setup, snapshot, t_info));
+                    }));
+                    Box::pin(async move { rx.await.expect("setUp was cancelled") }.into_actor(self))
+                };
 
-                let test = |(maybe_setup_resp, mut t_info): (Option<EthResponse>, TestInfo),
+                let test = |(maybe_setup_resp, snapshot, mut t_info): SetUp,
                             act2: &mut Self,
                             _ctx3: &mut Context<Self>| {
+                    let snapshot = snapshot.or_else(|| act2.snapshots.get(&t_info.src).cloned());
+                    let snapshotter = act2.snapshotter.clone();
+                    let workers = act2.workers.clone();
                     async move {
                         if let Some(setup_resp) = maybe_setup_resp {
                             let recs = setup_resp.clone().tx_receipts().unwrap();
@@ -778,28 +919,55 @@ impl Handler<TestRequest> for Tester {
                                     t_info.contract_addresses.insert(*addr, search_src);
                                 }
                             }
-                            t_info.results.push(t_info.from_eth_resp(setup_resp));
+                            let result = t_info.from_eth_resp(setup_resp);
+                            t_info.results.push(result);
                         }
 
-                        let input = t_info
+                        let function = t_info
                             .contracts
                             .get(&src)
                             .unwrap()
                             .abi
                             .function(&test)
                             .unwrap()
-                            .encode_input(&[])
-                            .unwrap();
+                            .clone();
 
                         let mut sender = t_info.sender;
                         if t_info.testerIsEOA {
                             sender = t_info.contract;
                         }
-                        let test_res =
-                            Self::test(sender, input, t_info.contract, t_info.evm.clone()).await;
+                        // restore the state after setUp for every test
+                        let snapshot = match snapshot {
+                            Some(snapshot) => snapshot,
+                            None => Self::snapshot(snapshotter).await,
+                        };
+                        let contract = t_info.contract;
+                        let (test_res, fuzz) = if function.inputs.is_empty() {
+                            let input = function.encode_input(&[]).unwrap();
+                            let test_res =
+                                Self::test(sender, input, contract, snapshot.clone(), workers)
+                                    .await;
+                            (test_res, None)
+                        } else {
+                            // tests with parameters run with random inputs
+                            let run = |input| {
+                                Self::test(
+                                    sender,
+                                    input,
+                                    contract,
+                                    snapshot.clone(),
+                                    workers.clone(),
+                                )
+                            };
+                            let seed = fuzz_seed.unwrap_or_else(fuzz::random_seed);
+                            let expect_fail = is_fail_test(&t_info.test);
+                            let (test_res, result) =
+                                fuzz::fuzz(&function, expect_fail, fuzz_runs, seed, run).await;
+                            (test_res, Some(result))
+                        };
                         let call_addrs = flatten_call_addrs(
                             &t_info.contract_addresses,
-                            test_res.clone().tx_trace().unwrap(),
+                            test_res.clone().tx_trace().unwrap_or_default(),
                         );
                         for (addr, maybe_in_code) in call_addrs.iter() {
                             if !t_info.contract_addresses.contains_key(addr) {
@@ -832,16 +1000,19 @@ impl Handler<TestRequest> for Tester {
                                 t_info.contract_addresses.insert(*addr, search_src);
                             }
                         }
-                        (test_res, t_info)
+                        (test_res, t_info, snapshot, fuzz)
                     }
                     .into_actor(act2)
                     .map(move |res, act, _ctx| {
                         let test_res = res.0;
                         let mut t_info = res.1;
-                        t_info.results.push(t_info.from_eth_resp(test_res));
+                        let mut result = t_info.from_eth_resp(test_res);
+                        result.fuzz = res.3;
+                        t_info.results.push(result);
+                        act.snapshots.insert(t_info.src.clone(), res.2);
                         act.contract_addresses = t_info.contract_addresses;
                         act.contract_addresses_rev = t_info.contract_addresses_rev;
-                        act.setup_tests = t_info.setup_tests;
+                        act.labels = t_info.labels;
                         Ok(TestResponse::Test(t_info.results))
                     })
                 };
@@ -857,7 +1028,8 @@ impl Handler<TestRequest> for Tester {
                     // reset other things
                     act.contract_addresses = HashMap::new();
                     act.contract_addresses_rev = HashMap::new();
-                    act.setup_tests = HashMap::new();
+                    act.labels = HashMap::new();
+                    act.snapshots = HashMap::new();
                     act.sigs = HashMap::new();
                     for (_src, contract) in act.compiled.contracts.iter() {
                         for (_name, funcs) in contract.abi.functions.iter() {
@@ -876,6 +1048,7 @@ impl Handler<TestRequest> for Tester {
                     }
                     act.resolved = Vec::new();
                     act.add_cheat_codes();
+                    act.evm.do_send(EthRequest::set_artifacts(act.artifacts()));
                     Ok(TestResponse::Success)
                 }))
             }
@@ -900,8 +1073,5 @@ pub fn is_test(src: &str) -> bool {
 }
 
 pub fn is_fail_test(src: &str) -> bool {
-    let src_strs: Vec<&str> = src.rsplit(':').collect();
-    let file_name = src_strs.last().unwrap().clone();
-    let src: Vec<&str> = file_name.rsplit('.').collect();
-    src.iter().any(|c| *c == "t")
+    src.starts_with("testFail")
 }
@@ -44,6 +44,31 @@ impl Default for BetterToken {
     }
 }
 
+impl fmt::Display for BetterToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |tokens: &[BetterToken]| {
+            tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match self {
+            BetterToken::Address(a) => write!(f, "0x{}", hex::encode(a.0)),
+            BetterToken::FixedBytes(b) => write!(f, "{:?}", b),
+            BetterToken::Bytes(b) => write!(f, "0x{}", b),
+            BetterToken::Int(i) if i.bit(255) => {
+                write!(f, "-{}", (!*i).overflowing_add(U256::one()).0)
+            }
+            BetterToken::Int(i) | BetterToken::Uint(i) => write!(f, "{}", i),
+            BetterToken::Bool(b) => write!(f, "{}", b),
+            BetterToken::String(s) => write!(f, "{}", s),
+            BetterToken::FixedArray(ts) | BetterToken::Array(ts) => write!(f, "[{}]", join(ts)),
+            BetterToken::Tuple(ts) => write!(f, "({})", join(ts)),
+        }
+    }
+}
+
 impl From<Token> for BetterToken {
     fn from(tkn: Token) -> BetterToken {
         match tkn {
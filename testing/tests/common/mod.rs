@@ -0,0 +1,98 @@
+//! Fixtures shared by the cevm tests.
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// `n` zero bytes, hex encoded with a `0x` prefix.
+fn zeros(n: usize) -> String {
+    format!("0x{}", "00".repeat(n))
+}
+
+/// Answer to `method` on a chain at block one where every account is empty.
+fn answer(method: &str) -> Value {
+    match method {
+        "eth_getBalance" | "eth_getTransactionCount" => json!({ "result": "0x0" }),
+        "eth_getCode" => json!({ "result": "0x" }),
+        "eth_getStorageAt" => json!({ "result": zeros(32) }),
+        "eth_chainId" => json!({ "result": "0x1" }),
+        "eth_getBlockByNumber" => json!({
+            "result": {
+                "number": "0x1",
+                "hash": zeros(32),
+                "parentHash": zeros(32),
+                "sha3Uncles": zeros(32),
+                "miner": zeros(20),
+                "stateRoot": zeros(32),
+                "transactionsRoot": zeros(32),
+                "receiptsRoot": zeros(32),
+                "gasUsed": "0x0",
+                "gasLimit": "0x1c9c380",
+                "baseFeePerGas": "0x0",
+                "extraData": "0x",
+                "logsBloom": zeros(256),
+                "timestamp": "0x1",
+                "difficulty": "0x0",
+                "totalDifficulty": "0x0",
+                "sealFields": [],
+                "uncles": [],
+                "transactions": [],
+                "size": "0x0",
+                "mixHash": zeros(32),
+                "nonce": zeros(8),
+            }
+        }),
+        _ => json!({ "error": { "code": -32601, "message": "method not found" } }),
+    }
+}
+
+/// Serve JSON-RPC for an empty chain over HTTP/1.1 on a local port, one
+/// request per connection, and return its url.
+pub fn mock_node() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                let lower = line.to_ascii_lowercase();
+                if let Some(v) = lower.strip_prefix("content-length:") {
+                    len = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            let respond = |call: &Value| {
+                let mut res = answer(call["method"].as_str().unwrap());
+                res["id"] = call["id"].clone();
+                res["jsonrpc"] = json!("2.0");
+                res
+            };
+            let response = match body {
+                Value::Array(calls) => Value::Array(calls.iter().map(respond).collect()),
+                call => respond(&call),
+            };
+
+            let out = serde_json::to_vec(&response).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                out.len()
+            )
+            .unwrap();
+            stream.write_all(&out).unwrap();
+        }
+    });
+    url
+}
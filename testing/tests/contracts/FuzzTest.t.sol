@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity >=0.6.0;
+
+contract FuzzTest {
+    function testAddCommutes(uint128 a, uint128 b) public pure {
+        require(uint256(a) + b == uint256(b) + a, "addition doesn't commute");
+    }
+
+    function testFailAlwaysReverts(uint256 a) public pure {
+        require(a > a, "a isn't greater than itself");
+    }
+
+    function testSmallInputs(uint256 a) public pure {
+        require(a < 1000, "input too large");
+    }
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+const FUZZ_TEST: &str = include_str!("contracts/FuzzTest.t.sol");
+
+#[test]
+fn cevm_test_fuzzes_parameterised_tests() {
+    // Compiling writes Vm.sol and the build output next to the sources.
+    let dir = std::env::temp_dir().join("cevm-fuzz-test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("FuzzTest.t.sol"), FUZZ_TEST).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cevm"))
+        .arg("test")
+        .arg(&dir)
+        .args(&["--fork-url", &common::mock_node()])
+        .args(&["--fuzz-runs", "64", "--fuzz-seed", "1"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    for line in &[
+        "[PASS] FuzzTest::testAddCommutes (runs: 64)",
+        "[PASS] FuzzTest::testFailAlwaysReverts (runs: 64)",
+        "[FAIL] FuzzTest::testSmallInputs: ",
+        "2 passed, 1 failed",
+    ] {
+        assert!(stdout.contains(line), "missing {:?} in:\n{}", line, stdout);
+    }
+    assert_eq!(output.status.code(), Some(1));
+}
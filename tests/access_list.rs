@@ -0,0 +1,63 @@
+mod common;
+
+use common::vicinity;
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::StackExecutor;
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 0 SLOAD POP PUSH1 0 SLOAD POP STOP
+const SLOAD_TWICE: &str = "6000545060005450";
+
+fn used_gas(config: &Config, access_list: Vec<(H160, Vec<H256>)>) -> usize {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(SLOAD_TWICE).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let mut exec = StackExecutor::new(&backend, 1_000_000, config);
+
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x2000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        access_list,
+    );
+    assert!(reason.is_succeed());
+    exec.used_gas()
+}
+
+#[test]
+fn istanbul_sload_is_flat() {
+    assert_eq!(
+        used_gas(&Config::istanbul(), Vec::new()),
+        21000 + 2 * (3 + 800 + 2)
+    );
+}
+
+#[test]
+fn berlin_sload_cold_then_warm() {
+    assert_eq!(
+        used_gas(&Config::berlin(), Vec::new()),
+        21000 + (3 + 2100 + 2) + (3 + 100 + 2)
+    );
+}
+
+#[test]
+fn berlin_access_list_warms_slot() {
+    let contract = H160::from_low_u64_be(0x1000);
+    assert_eq!(
+        used_gas(&Config::berlin(), vec![(contract, vec![H256::zero()])]),
+        21000 + 2400 + 1900 + 2 * (3 + 100 + 2)
+    );
+}
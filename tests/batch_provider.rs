@@ -0,0 +1,192 @@
+mod common;
+
+use evm::backend::{Backend, ForkMemoryBackendOwned, MemoryVicinity};
+use evm::provider::ProviderError;
+use primitive_types::{H160, H256, U256};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Methods of every HTTP request the mock node received, one entry per round trip.
+type Requests = Arc<Mutex<Vec<Vec<String>>>>;
+
+fn vicinity() -> MemoryVicinity {
+    MemoryVicinity {
+        block_number: U256::from(100),
+        ..common::vicinity()
+    }
+}
+
+fn answer(method: &str, params: &Value, proof_error: Option<i64>) -> Value {
+    match method {
+        "eth_getProof" if proof_error.is_none() => {
+            let storage: Vec<Value> = params[1]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|key| json!({ "key": key, "value": "0x2a", "proof": [] }))
+                .collect();
+            json!({ "result": {
+                "address": params[0],
+                "balance": "0x7",
+                "nonce": "0x3",
+                "codeHash": format!("0x{}", "00".repeat(32)),
+                "storageHash": format!("0x{}", "00".repeat(32)),
+                "accountProof": [],
+                "storageProof": storage,
+            }})
+        }
+        "eth_getProof" => {
+            json!({ "error": { "code": proof_error, "message": "eth_getProof failed" } })
+        }
+        "eth_getBalance" => json!({ "result": "0x7" }),
+        "eth_getTransactionCount" => json!({ "result": "0x3" }),
+        "eth_getCode" => json!({ "result": "0x00" }),
+        "eth_getStorageAt" => json!({ "result": format!("0x{:064x}", 0x2a) }),
+        _ => panic!("unexpected call {}", method),
+    }
+}
+
+/// Serve JSON-RPC over HTTP/1.1 on a local port, one request per connection.
+/// `eth_getProof` fails with `proof_error` if set.
+fn mock_node(proof_error: Option<i64>) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let log = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                let lower = line.to_ascii_lowercase();
+                if let Some(v) = lower.strip_prefix("content-length:") {
+                    len = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            let calls = match body {
+                Value::Array(calls) => calls,
+                call => vec![call],
+            };
+            let mut responses = Vec::new();
+            let mut methods = Vec::new();
+            for call in calls.iter().rev() {
+                let method = call["method"].as_str().unwrap();
+                let mut res = answer(method, &call["params"], proof_error);
+                res["id"] = call["id"].clone();
+                res["jsonrpc"] = json!("2.0");
+                responses.push(res);
+                methods.push(method.to_string());
+            }
+            methods.reverse();
+            log.lock().unwrap().push(methods);
+
+            let out = serde_json::to_vec(&responses).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                out.len()
+            )
+            .unwrap();
+            stream.write_all(&out).unwrap();
+        }
+    });
+    (url, requests)
+}
+
+#[test]
+fn account_is_one_round_trip() {
+    let (url, requests) = mock_node(None);
+    let address = H160::from_low_u64_be(0x1000);
+    let mut backend = ForkMemoryBackendOwned::new(vicinity(), BTreeMap::new(), url).unwrap();
+    backend.prefetch_slots(address, vec![H256::zero()]);
+
+    assert!(backend.exists(address));
+    assert_eq!(backend.basic(address).balance, U256::from(7));
+    assert_eq!(backend.basic(address).nonce, U256::from(3));
+    assert_eq!(backend.code(address), vec![0x00]);
+    assert_eq!(
+        backend.storage(address, H256::zero()),
+        H256::from_low_u64_be(0x2a)
+    );
+    assert_eq!(backend.error(), None);
+
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![vec!["eth_getProof".to_string(), "eth_getCode".to_string()]]
+    );
+}
+
+#[test]
+fn falls_back_without_get_proof() {
+    let (url, requests) = mock_node(Some(-32601));
+    let first = H160::from_low_u64_be(0x1000);
+    let second = H160::from_low_u64_be(0x2000);
+    let mut backend = ForkMemoryBackendOwned::new(vicinity(), BTreeMap::new(), url).unwrap();
+    backend.prefetch_slots(first, vec![H256::zero()]);
+
+    assert_eq!(backend.basic(first).balance, U256::from(7));
+    assert_eq!(
+        backend.storage(first, H256::zero()),
+        H256::from_low_u64_be(0x2a)
+    );
+    assert_eq!(backend.basic(second).nonce, U256::from(3));
+    assert_eq!(backend.error(), None);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0], vec!["eth_getProof", "eth_getCode"]);
+    assert_eq!(
+        requests[1],
+        vec![
+            "eth_getBalance",
+            "eth_getTransactionCount",
+            "eth_getCode",
+            "eth_getStorageAt"
+        ]
+    );
+    // Once rejected, `eth_getProof` is not tried again.
+    assert_eq!(
+        requests[2],
+        vec!["eth_getBalance", "eth_getTransactionCount", "eth_getCode"]
+    );
+}
+
+#[test]
+fn other_get_proof_errors_are_returned() {
+    let (url, requests) = mock_node(Some(-32000));
+    let first = H160::from_low_u64_be(0x1000);
+    let second = H160::from_low_u64_be(0x2000);
+    let backend = ForkMemoryBackendOwned::new(vicinity(), BTreeMap::new(), url).unwrap();
+
+    backend.basic(first);
+    assert_eq!(
+        backend.error(),
+        Some(ProviderError::JsonRpc {
+            method: "eth_getProof",
+            code: -32000,
+            message: "eth_getProof failed".to_string(),
+        })
+    );
+    backend.basic(second);
+
+    // The node does serve proofs, so they are still asked for rather than
+    // falling back to batches of plain reads.
+    let requests = requests.lock().unwrap();
+    assert!(requests
+        .iter()
+        .all(|methods| methods.len() == 1 || methods[0] == "eth_getProof"));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use evm::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::StackExecutor;
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::thread;
+
+// PUSH1 0 SLOAD PUSH1 1 ADD PUSH1 0 SSTORE
+// PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 ADDRESS GAS CALL STOP
+const COUNT_AND_RECURSE: &str = "60005460010160005560006000600060006000305af100";
+
+fn vicinity() -> MemoryVicinity {
+    MemoryVicinity {
+        block_gas_limit: U256::from(100_000_000),
+        ..common::vicinity()
+    }
+}
+
+#[test]
+fn call_stack_limit_without_native_recursion() {
+    // Recursing natively through 1024 frames needs far more than this.
+    let handle = thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(|| {
+            let contract = H160::from_low_u64_be(0x1000);
+            let mut state = BTreeMap::new();
+            state.insert(
+                contract,
+                MemoryAccount {
+                    code: hex::decode(COUNT_AND_RECURSE).unwrap(),
+                    ..Default::default()
+                },
+            );
+            let vicinity = vicinity();
+            let backend = MemoryBackend::new(&vicinity, state);
+            // Without the 63/64 rule there is enough gas to reach the limit.
+            let mut config = Config::istanbul();
+            config.call_l64_after_gas = false;
+            let mut exec = StackExecutor::new(&backend, 50_000_000, &config);
+
+            let (reason, _, _) = exec.transact_call(
+                H256::zero(),
+                H160::from_low_u64_be(0x2000),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                50_000_000,
+                Vec::new(),
+            );
+            (reason.is_succeed(), exec.storage(contract, H256::zero()))
+        })
+        .unwrap();
+
+    let (succeed, frames) = handle.join().unwrap();
+    assert!(succeed);
+    // One frame per depth from 0 to the limit of 1024.
+    assert_eq!(frames, H256::from_low_u64_be(1025));
+}
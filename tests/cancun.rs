@@ -0,0 +1,80 @@
+mod common;
+
+use common::vicinity;
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::StackExecutor;
+use evm::{Config, ExitError, ExitReason};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 0x2a PUSH0 TSTORE PUSH0 TLOAD PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+const TSTORE_TLOAD: &str = "602a5f5d5f5c5f5260205ff3";
+// PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 PUSH1 0x20 MCOPY PUSH1 0x20 PUSH1 0x20 RETURN
+const MCOPY: &str = "602a5f5260205f60205e60206020f3";
+// PUSH1 0xef PUSH0 MSTORE8 PUSH1 1 PUSH0 RETURN
+const DEPLOY_EF: &str = "60ef5f5360015ff3";
+
+fn call(config: &Config, code: &str) -> (ExitReason, Vec<u8>) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let mut exec = StackExecutor::new(&backend, 1_000_000, config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x2000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    (reason, out)
+}
+
+#[test]
+fn cancun_transient_storage() {
+    let (reason, out) = call(&Config::cancun(), TSTORE_TLOAD);
+    assert!(reason.is_succeed());
+    assert_eq!(U256::from_big_endian(&out), U256::from(0x2a));
+}
+
+#[test]
+fn cancun_mcopy() {
+    let (reason, out) = call(&Config::cancun(), MCOPY);
+    assert!(reason.is_succeed());
+    assert_eq!(U256::from_big_endian(&out), U256::from(0x2a));
+}
+
+#[test]
+fn new_opcodes_are_fork_gated() {
+    assert!(!call(&Config::istanbul(), TSTORE_TLOAD).0.is_succeed());
+    assert!(!call(&Config::shanghai(), TSTORE_TLOAD).0.is_succeed());
+    assert!(!call(&Config::shanghai(), MCOPY).0.is_succeed());
+}
+
+#[test]
+fn shanghai_rejects_ef_code() {
+    let config = Config::shanghai();
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, _, _) = exec.transact_create(
+        H256::zero(),
+        H160::from_low_u64_be(0x2000),
+        U256::zero(),
+        hex::decode(DEPLOY_EF).unwrap(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert_eq!(reason, ExitReason::Error(ExitError::InvalidCode));
+}
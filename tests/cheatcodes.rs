@@ -0,0 +1,199 @@
+mod common;
+
+use common::{call, vicinity, RETURN_RETURNDATA};
+use evm::backend::{Backend, MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::{
+    revert_message, selector, signature, Cheatcode, CheatcodeResult, Cheatcodes, StackExecutor,
+    ASSUME_MAGIC, CHEATCODE_ADDRESS,
+};
+use evm::{Config, Context, ExitReason, ExitRevert};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Run `code` with `cheatcodes` and return the result of the transaction.
+fn run<'vicinity>(
+    vicinity: &'vicinity MemoryVicinity,
+    code: String,
+    cheatcodes: Cheatcodes<MemoryBackend<'vicinity>>,
+) -> (ExitReason, Vec<u8>) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code + RETURN_RETURNDATA).unwrap(),
+            ..Default::default()
+        },
+    );
+    let backend = MemoryBackend::new(vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+    exec.cheatcodes = Arc::new(cheatcodes);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    (reason, out)
+}
+
+fn answer<B: Backend>(
+    _executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    Ok(H256::from_low_u64_be(42).as_bytes().to_vec())
+}
+
+fn refuse<B: Backend>(
+    _executor: &mut StackExecutor<'_, '_, B>,
+    _context: &Context,
+    _input: &[u8],
+) -> CheatcodeResult {
+    Err((ExitRevert::Reverted.into(), revert_message("refused")))
+}
+
+#[test]
+fn signature_strips_names_and_locations() {
+    let declaration =
+        "function mockCall(address callee, bytes calldata data, bytes calldata returnData) external";
+    assert_eq!(signature(declaration), "mockCall(address,bytes,bytes)");
+    assert_eq!(signature("function stopPrank() external"), "stopPrank()");
+    assert_eq!(hex::encode(selector(declaration)), "b96213e4");
+}
+
+#[test]
+fn registered_cheatcode_is_called() {
+    let mut cheatcodes = Cheatcodes::standard();
+    cheatcodes.register(Cheatcode {
+        declaration: "function answer() external returns (uint256 value)",
+        handler: answer,
+    });
+
+    let vicinity = vicinity();
+    let code = call(CHEATCODE_ADDRESS, &selector("function answer() external"));
+    let (reason, out) = run(&vicinity, code, cheatcodes);
+    assert!(reason.is_succeed());
+    assert_eq!(out, H256::from_low_u64_be(42).as_bytes().to_vec());
+}
+
+#[test]
+fn registering_replaces_cheatcode_with_same_selector() {
+    let mut cheatcodes = Cheatcodes::standard();
+    let count = cheatcodes.iter().count();
+    cheatcodes.register(Cheatcode {
+        declaration: "function warp(uint256 timestamp) external",
+        handler: refuse,
+    });
+    assert_eq!(cheatcodes.iter().count(), count);
+
+    let warp = [
+        selector("function warp(uint256) external").to_vec(),
+        vec![0; 32],
+    ]
+    .concat();
+    let vicinity = vicinity();
+    let (reason, out) = run(&vicinity, call(CHEATCODE_ADDRESS, &warp), cheatcodes);
+    assert!(reason.is_succeed());
+    assert_eq!(out, revert_message("refused"));
+}
+
+#[test]
+fn assume_rejects_false_conditions() {
+    let assume = |condition: u64| {
+        [
+            selector("function assume(bool) external").to_vec(),
+            H256::from_low_u64_be(condition).as_bytes().to_vec(),
+        ]
+        .concat()
+    };
+    let vicinity = vicinity();
+
+    let (reason, out) = run(
+        &vicinity,
+        call(CHEATCODE_ADDRESS, &assume(1)),
+        Cheatcodes::standard(),
+    );
+    assert!(reason.is_succeed());
+    assert!(out.is_empty());
+
+    let (reason, out) = run(
+        &vicinity,
+        call(CHEATCODE_ADDRESS, &assume(0)),
+        Cheatcodes::standard(),
+    );
+    assert!(reason.is_succeed());
+    assert_eq!(out, ASSUME_MAGIC);
+}
+
+#[test]
+fn malformed_input_reverts() {
+    let with_args = |declaration: &str, args: &[H256]| {
+        let mut input = selector(declaration).to_vec();
+        for arg in args {
+            input.extend_from_slice(arg.as_bytes());
+        }
+        input
+    };
+    let offset = H256::from_low_u64_be(0x40);
+    let huge = H256::repeat_byte(0xff);
+    let inputs = vec![
+        // Too few arguments
+        with_args(
+            "function store(address,bytes32,bytes32) external",
+            &[H256::zero()],
+        ),
+        with_args("function expectRevert(bytes4) external", &[]),
+        with_args(
+            "function expectEmit(bool,bool,bool,bool) external",
+            &[H256::zero(); 3],
+        ),
+        with_args(
+            "function sign(uint256,bytes32) external",
+            &[H256::from_low_u64_be(1)],
+        ),
+        // `bytes` offset out of range
+        with_args(
+            "function etch(address,bytes) external",
+            &[H256::zero(), huge],
+        ),
+        // `bytes` longer than the input
+        with_args(
+            "function etch(address,bytes) external",
+            &[H256::zero(), offset, huge],
+        ),
+        with_args(
+            "function etch(address,bytes) external",
+            &[H256::zero(), offset, H256::from_low_u64_be(33)],
+        ),
+    ];
+
+    let vicinity = vicinity();
+    for input in inputs {
+        let (reason, out) = run(
+            &vicinity,
+            call(CHEATCODE_ADDRESS, &input),
+            Cheatcodes::standard(),
+        );
+        assert!(reason.is_succeed());
+        assert_eq!(out, revert_message("invalid cheatcode input"));
+    }
+}
+
+#[test]
+fn unknown_cheatcode_reverts() {
+    let vicinity = vicinity();
+    let (reason, out) = run(
+        &vicinity,
+        call(CHEATCODE_ADDRESS, &hex::decode("deadbeef").unwrap()),
+        Cheatcodes::standard(),
+    );
+    assert!(reason.is_succeed());
+    assert_eq!(out, revert_message("unknown cheatcode deadbeef"));
+}
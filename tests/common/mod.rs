@@ -0,0 +1,77 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use evm::backend::MemoryVicinity;
+use evm::executor::CHEATCODE_ADDRESS;
+use primitive_types::{H160, H256, U256};
+
+// RETURNDATASIZE PUSH1 0 PUSH1 0 RETURNDATACOPY RETURNDATASIZE PUSH1 0 RETURN
+pub const RETURN_RETURNDATA: &str = "3d600060003e3d6000f3";
+
+/// Vicinity of a block at height zero on chain 1.
+pub fn vicinity() -> MemoryVicinity {
+    MemoryVicinity {
+        gas_price: U256::zero(),
+        origin: H160::zero(),
+        chain_id: U256::one(),
+        block_hashes: Vec::new(),
+        block_number: U256::zero(),
+        block_coinbase: H160::zero(),
+        block_timestamp: U256::zero(),
+        block_difficulty: U256::zero(),
+        block_gas_limit: U256::from(10_000_000),
+        block_base_fee_per_gas: U256::zero(),
+        block_randomness: None,
+    }
+}
+
+/// ABI encoding of a single word.
+pub fn word(value: u64) -> Vec<u8> {
+    H256::from_low_u64_be(value).as_bytes().to_vec()
+}
+
+/// Code that writes `data` to memory from offset 0.
+pub fn mstore(data: &[u8]) -> String {
+    let mut code = String::new();
+    for (i, chunk) in data.chunks(32).enumerate() {
+        // PUSH32 chunk PUSH2 offset MSTORE
+        code += &format!("7f{:0<64}61{:04x}52", hex::encode(chunk), i * 32);
+    }
+    code
+}
+
+/// Code that calls `address` with `calldata` and `value`, leaving whether the
+/// call succeeded on the stack.
+pub fn try_call_value(address: H160, calldata: &[u8], value: u8) -> String {
+    // PUSH1 0 PUSH1 0 PUSH2 len PUSH1 0 PUSH1 value PUSH20 address GAS CALL
+    mstore(calldata)
+        + &format!(
+            "6000600061{:04x}600060{:02x}73{}5af1",
+            calldata.len(),
+            value,
+            hex::encode(address.as_bytes())
+        )
+}
+
+/// Code that calls `address` with `calldata`, leaving whether the call
+/// succeeded on the stack.
+pub fn try_call(address: H160, calldata: &[u8]) -> String {
+    try_call_value(address, calldata, 0)
+}
+
+/// Code that calls `address` with `calldata` and `value` and pops the result.
+pub fn call_value(address: H160, calldata: &[u8], value: u8) -> String {
+    // POP
+    try_call_value(address, calldata, value) + "50"
+}
+
+/// Code that calls `address` with `calldata` and pops the result.
+pub fn call(address: H160, calldata: &[u8]) -> String {
+    call_value(address, calldata, 0)
+}
+
+/// Code that calls the cheatcode address with hex encoded `calldata` and pops
+/// the result.
+pub fn call_cheatcode(calldata: &str) -> String {
+    call(CHEATCODE_ADDRESS, &hex::decode(calldata).unwrap())
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{call, vicinity, RETURN_RETURNDATA};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{abi_encode_bytes, revert_message, StackExecutor, CHEATCODE_ADDRESS};
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// PUSH1 0x2a PUSH1 0 SSTORE PUSH1 0 PUSH1 0 RETURN
+const COUNTER_INIT: &str = "602a60005560006000f3";
+// PUSH1 1 PUSH1 0 SSTORE STOP
+const STORE_ONE: &str = "600160005500";
+// PUSH1 0 PUSH1 0 REVERT
+const REVERT: &str = "60006000fd";
+
+fn cheat(selector: &str, data: &[u8]) -> Vec<u8> {
+    let mut input = hex::decode(selector).unwrap();
+    input.extend_from_slice(&abi_encode_bytes(data));
+    input
+}
+
+/// Run `code` with the `Counter` artifact available. Returns the return data,
+/// the contract's slot 0 and slot 0 of the address held there.
+fn run(code: String, target_code: &str) -> (Vec<u8>, H256, H256) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        H160::from_low_u64_be(0x2000),
+        MemoryAccount {
+            code: hex::decode(target_code).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+    let mut artifacts = BTreeMap::new();
+    artifacts.insert("Counter".to_string(), hex::decode(COUNTER_INIT).unwrap());
+    exec.artifacts = Arc::new(artifacts);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    let slot = exec.storage(contract, H256::zero());
+    (out, slot, exec.storage(H160::from(slot), H256::zero()))
+}
+
+// PUSH1 0 SSTORE
+const STORE: &str = "600055";
+// PUSH1 32 PUSH1 0 PUSH1 0 RETURNDATACOPY PUSH1 0 MLOAD
+const LOAD_RETURN_WORD: &str = "6020600060003e600051";
+
+#[test]
+fn etch_replaces_code() {
+    let target = H160::from_low_u64_be(0x2000);
+    let mut etch = hex::decode("b4d6c782").unwrap();
+    etch.extend_from_slice(H256::from(target).as_bytes());
+    etch.extend_from_slice(H256::from_low_u64_be(64).as_bytes());
+    etch.extend_from_slice(&abi_encode_bytes(&hex::decode(STORE_ONE).unwrap())[32..]);
+    let code = call(CHEATCODE_ADDRESS, &etch)
+        + &call(target, &[])
+        // PUSH20 target PUSH1 0 SSTORE
+        + &format!("73{}{}", hex::encode(target.as_bytes()), STORE);
+
+    let (_, _, etched_slot) = run(code, REVERT);
+    assert_eq!(etched_slot, H256::from_low_u64_be(1));
+}
+
+#[test]
+fn deploy_code_deploys_artifact() {
+    let code = call(CHEATCODE_ADDRESS, &cheat("9a8325a0", b"Counter")) + LOAD_RETURN_WORD + STORE;
+
+    let (_, deployed, counter_slot) = run(code, REVERT);
+    assert_ne!(deployed, H256::zero());
+    assert_eq!(counter_slot, H256::from_low_u64_be(0x2a));
+}
+
+#[test]
+fn get_code_returns_artifact() {
+    let code = call(CHEATCODE_ADDRESS, &cheat("8d1cc925", b"Counter")) + RETURN_RETURNDATA;
+
+    let (out, _, _) = run(code, REVERT);
+    assert_eq!(out, abi_encode_bytes(&hex::decode(COUNTER_INIT).unwrap()));
+}
+
+#[test]
+fn unknown_artifact_reverts() {
+    let code = call(CHEATCODE_ADDRESS, &cheat("8d1cc925", b"Missing")) + RETURN_RETURNDATA;
+
+    let (out, _, _) = run(code, REVERT);
+    assert_eq!(out, revert_message("unknown artifact Missing"));
+}
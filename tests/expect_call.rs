@@ -0,0 +1,129 @@
+mod common;
+
+use common::{call, call_value, vicinity, word, RETURN_RETURNDATA};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{revert_message, StackExecutor, CHEATCODE_ADDRESS};
+use evm::{Config, ExitReason};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+fn target() -> H160 {
+    H160::from_low_u64_be(0x2000)
+}
+
+/// Calldata of `expectCall` on the target, with an optional value and count.
+fn expect_call(data: &[u8], value: Option<u64>, count: Option<u64>) -> Vec<u8> {
+    let selector = match (value, count) {
+        (None, None) => "bd6af434",
+        (Some(_), None) => "f30c7ba3",
+        (None, Some(_)) => "c1adbbff",
+        (Some(_), Some(_)) => "a2b1a1ae",
+    };
+    let head_len = 32 * (2 + value.is_some() as u64 + count.is_some() as u64);
+    let mut input = hex::decode(selector).unwrap();
+    input.extend_from_slice(&[vec![0; 12], target().as_bytes().to_vec()].concat());
+    if let Some(value) = value {
+        input.extend_from_slice(&word(value));
+    }
+    input.extend_from_slice(&word(head_len));
+    if let Some(count) = count {
+        input.extend_from_slice(&word(count));
+    }
+    input.extend_from_slice(&word(data.len() as u64));
+    input.extend_from_slice(data);
+    input.resize(input.len() + (32 - data.len() % 32) % 32, 0);
+    input
+}
+
+/// Run `code` from a funded contract next to an empty target, returning the
+/// result of the transaction.
+fn run(code: String) -> (ExitReason, Vec<u8>) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code + RETURN_RETURNDATA).unwrap(),
+            balance: U256::from(10),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    (reason, out)
+}
+
+fn transfer(amount: u64) -> Vec<u8> {
+    [hex::decode("a9059cbb").unwrap(), word(amount)].concat()
+}
+
+#[test]
+fn expect_call_matches_calldata_prefix() {
+    let code = call(
+        CHEATCODE_ADDRESS,
+        &expect_call(&transfer(1)[..4], None, None),
+    ) + &call(target(), &transfer(1));
+
+    let (reason, _) = run(code);
+    assert!(reason.is_succeed());
+}
+
+#[test]
+fn missing_call_lists_closest_calls() {
+    let code = call(CHEATCODE_ADDRESS, &expect_call(&transfer(1), None, None))
+        + &call(target(), &transfer(2));
+
+    let (reason, out) = run(code);
+    assert!(matches!(reason, ExitReason::Revert(_)));
+    let message = format!(
+        "Expected call to {:?} with data 0x{} not made; closest calls: {:?} with data 0x{} and value 0",
+        target(),
+        hex::encode(transfer(1)),
+        target(),
+        hex::encode(transfer(2))
+    );
+    assert_eq!(out, revert_message(&message));
+}
+
+#[test]
+fn expect_call_counts_calls() {
+    let expect = call(CHEATCODE_ADDRESS, &expect_call(&transfer(1), None, Some(2)));
+    let transfer_call = call(target(), &transfer(1));
+
+    let (reason, _) = run(expect.clone() + &transfer_call + &transfer_call);
+    assert!(reason.is_succeed());
+
+    let (reason, out) = run(expect + &transfer_call);
+    assert!(matches!(reason, ExitReason::Revert(_)));
+    let message = format!(
+        "Expected call to {:?} with data 0x{} 2 times, got 1; closest calls: {:?} with data 0x{} and value 0",
+        target(),
+        hex::encode(transfer(1)),
+        target(),
+        hex::encode(transfer(1))
+    );
+    assert_eq!(out, revert_message(&message));
+}
+
+#[test]
+fn expect_call_checks_value() {
+    let expect = call(CHEATCODE_ADDRESS, &expect_call(&[], Some(1), None));
+
+    let (reason, _) = run(expect.clone() + &call_value(target(), &[], 1));
+    assert!(reason.is_succeed());
+
+    let (reason, _) = run(expect + &call(target(), &[]));
+    assert!(matches!(reason, ExitReason::Revert(_)));
+}
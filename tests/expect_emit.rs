@@ -0,0 +1,218 @@
+mod common;
+
+use common::{call, mstore, try_call, vicinity, RETURN_RETURNDATA};
+use evm::backend::{Log, MemoryAccount, MemoryBackend};
+use evm::executor::{StackExecutor, CHEATCODE_ADDRESS};
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+/// Code that emits a log with two topics.
+fn log2(topics: [H256; 2], data: &[u8]) -> String {
+    // PUSH32 topic1 PUSH32 topic0 PUSH1 len PUSH1 0 LOG2
+    mstore(data)
+        + &format!(
+            "7f{}7f{}60{:02x}6000a2",
+            hex::encode(topics[1]),
+            hex::encode(topics[0]),
+            data.len()
+        )
+}
+
+fn expect_emit(checks: [bool; 4]) -> Vec<u8> {
+    let mut input = hex::decode("491cc7c2").unwrap();
+    for check in checks.iter() {
+        input.extend_from_slice(H256::from_low_u64_be(*check as u64).as_bytes());
+    }
+    input
+}
+
+fn expect_emit_from(checks: [bool; 4], emitter: H160) -> Vec<u8> {
+    let mut input = hex::decode("81bad6f3").unwrap();
+    for check in checks.iter() {
+        input.extend_from_slice(H256::from_low_u64_be(*check as u64).as_bytes());
+    }
+    input.extend_from_slice(H256::from(emitter).as_bytes());
+    input
+}
+
+/// Expect `expected` to be emitted by the next call, to a target emitting
+/// `emitted`. Returns whether that call succeeded, its return data and the
+/// logs of the transaction.
+fn run(checks: [bool; 4], expected: &Log, emitted: &Log) -> (bool, Vec<u8>, Vec<Log>) {
+    run_cheat(expect_emit(checks), expected, emitted)
+}
+
+/// Like `run`, setting up the expectation with the cheatcode call `cheat`.
+fn run_cheat(cheat: Vec<u8>, expected: &Log, emitted: &Log) -> (bool, Vec<u8>, Vec<Log>) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let target = H160::from_low_u64_be(0x2000);
+    let code = [
+        call(CHEATCODE_ADDRESS, &cheat),
+        log2([expected.topics[0], expected.topics[1]], &expected.data),
+        try_call(target, &[]),
+        // PUSH1 0 SSTORE
+        "600055".to_string(),
+        RETURN_RETURNDATA.to_string(),
+    ]
+    .concat();
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        target,
+        MemoryAccount {
+            code: hex::decode(log2([emitted.topics[0], emitted.topics[1]], &emitted.data)).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    (
+        exec.storage(contract, H256::zero()) == H256::from_low_u64_be(1),
+        out,
+        exec.logs.clone(),
+    )
+}
+
+fn transfer(address: u64, to: u64, amount: u64) -> Log {
+    Log {
+        address: H160::from_low_u64_be(address),
+        topics: vec![H256::repeat_byte(0xdd), H256::from_low_u64_be(to)],
+        data: H256::from_low_u64_be(amount).as_bytes().to_vec(),
+    }
+}
+
+#[test]
+fn expect_emit_matches_log() {
+    let expected = transfer(0x1000, 1, 100);
+    let emitted = transfer(0x2000, 1, 100);
+    assert_eq!(
+        run([true, true, true, true], &expected, &emitted),
+        (true, Vec::new(), vec![emitted])
+    );
+}
+
+#[test]
+fn expect_emit_skips_unchecked_fields() {
+    let expected = transfer(0x1000, 1, 100);
+    let emitted = transfer(0x2000, 2, 200);
+    let (succeed, _, _) = run([false, true, true, false], &expected, &emitted);
+    assert!(succeed);
+}
+
+/// `LogNotEmitted(address,bytes32[],bytes)` revert data of `expected`,
+/// expected from `emitter`.
+fn log_not_emitted(emitter: H160, expected: &Log) -> Vec<u8> {
+    let mut not_emitted = hex::decode("ca7fc3da").unwrap();
+    not_emitted.extend_from_slice(H256::from(emitter).as_bytes());
+    not_emitted.extend_from_slice(H256::from_low_u64_be(96).as_bytes());
+    not_emitted.extend_from_slice(H256::from_low_u64_be(192).as_bytes());
+    not_emitted.extend_from_slice(H256::from_low_u64_be(2).as_bytes());
+    for topic in expected.topics.iter() {
+        not_emitted.extend_from_slice(topic.as_bytes());
+    }
+    not_emitted.extend_from_slice(H256::from_low_u64_be(32).as_bytes());
+    not_emitted.extend_from_slice(&expected.data);
+    not_emitted
+}
+
+#[test]
+fn expect_emit_reports_missing_log() {
+    let expected = transfer(0x1000, 1, 100);
+    let emitted = transfer(0x2000, 1, 200);
+    let (succeed, out, _) = run([true, true, true, true], &expected, &emitted);
+    assert_eq!(
+        (succeed, out),
+        (false, log_not_emitted(expected.address, &expected))
+    );
+}
+
+#[test]
+fn expect_emit_matches_emitter() {
+    let expected = transfer(0x1000, 1, 100);
+    let emitted = transfer(0x2000, 1, 100);
+    let cheat = expect_emit_from([true, true, true, true], emitted.address);
+    let (succeed, _, _) = run_cheat(cheat, &expected, &emitted);
+    assert!(succeed);
+}
+
+#[test]
+fn expect_emit_reports_log_from_other_emitter() {
+    let expected = transfer(0x1000, 1, 100);
+    let emitted = transfer(0x2000, 1, 100);
+    let emitter = H160::from_low_u64_be(0x4000);
+    let cheat = expect_emit_from([true, true, true, true], emitter);
+    let (succeed, out, _) = run_cheat(cheat, &expected, &emitted);
+    assert_eq!((succeed, out), (false, log_not_emitted(emitter, &expected)));
+}
+
+/// Expect `expected` to be emitted by the next create, whose init code emits
+/// `emitted`. Returns the address the create left on the stack.
+fn run_create(expected: &Log, emitted: &Log) -> H256 {
+    let contract = H160::from_low_u64_be(0x1000);
+    let init = hex::decode(log2([emitted.topics[0], emitted.topics[1]], &emitted.data)).unwrap();
+    let code = [
+        call(CHEATCODE_ADDRESS, &expect_emit([true, true, true, true])),
+        log2([expected.topics[0], expected.topics[1]], &expected.data),
+        mstore(&init),
+        // PUSH2 len PUSH1 0 PUSH1 0 CREATE PUSH1 0 SSTORE
+        format!("61{:04x}60006000f0600055", init.len()),
+    ]
+    .concat();
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    exec.storage(contract, H256::zero())
+}
+
+#[test]
+fn expect_emit_applies_to_create() {
+    let expected = transfer(0x1000, 1, 100);
+    assert_ne!(
+        run_create(&expected, &transfer(0x2000, 1, 100)),
+        H256::zero()
+    );
+    assert_eq!(
+        run_create(&expected, &transfer(0x2000, 1, 200)),
+        H256::zero()
+    );
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use common::{call, mstore, try_call, vicinity, RETURN_RETURNDATA};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{abi_encode_bytes, revert_message, StackExecutor, CHEATCODE_ADDRESS};
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// STOP
+const STOP: &str = "00";
+
+/// Code that reverts with `data`.
+fn revert(data: &[u8]) -> String {
+    // PUSH1 len PUSH1 0 REVERT
+    mstore(data) + &format!("60{:02x}6000fd", data.len())
+}
+
+/// Call the cheatcode with `cheat`, then a target with code `target`. Returns
+/// whether the second call succeeded and its return data.
+fn run(cheat: Vec<u8>, target: String) -> (bool, Vec<u8>) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let target_address = H160::from_low_u64_be(0x2000);
+    let code = [
+        call(CHEATCODE_ADDRESS, &cheat),
+        try_call(target_address, &[]),
+        // PUSH1 0 SSTORE
+        "600055".to_string(),
+        RETURN_RETURNDATA.to_string(),
+    ]
+    .concat();
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        target_address,
+        MemoryAccount {
+            code: hex::decode(target).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    (
+        exec.storage(contract, H256::zero()) == H256::from_low_u64_be(1),
+        out,
+    )
+}
+
+fn expect_revert_bytes(data: &[u8]) -> Vec<u8> {
+    let mut input = hex::decode("f28dceb3").unwrap();
+    input.extend_from_slice(&abi_encode_bytes(data));
+    input
+}
+
+#[test]
+fn expect_revert_accepts_any_revert() {
+    let cheat = hex::decode("f4844814").unwrap();
+    assert_eq!(
+        run(cheat, revert(&revert_message("nope"))),
+        (true, Vec::new())
+    );
+}
+
+#[test]
+fn expect_revert_matches_reason() {
+    let cheat = expect_revert_bytes(b"nope");
+    assert_eq!(
+        run(cheat, revert(&revert_message("nope"))),
+        (true, Vec::new())
+    );
+}
+
+#[test]
+fn expect_revert_matches_custom_error_selector() {
+    let cheat = hex::decode(format!("c31eb0e0{:0<64}", "deadbeef")).unwrap();
+    let custom_error = hex::decode(format!("deadbeef{:064x}", 42)).unwrap();
+    assert_eq!(run(cheat, revert(&custom_error)), (true, Vec::new()));
+}
+
+#[test]
+fn expect_revert_reports_mismatch() {
+    let cheat = expect_revert_bytes(b"other");
+    assert_eq!(
+        run(cheat, revert(&revert_message("nope"))),
+        (
+            false,
+            revert_message("Error != expected error: nope != other")
+        )
+    );
+}
+
+#[test]
+fn expect_revert_reports_missing_revert() {
+    let cheat = hex::decode("f4844814").unwrap();
+    assert_eq!(
+        run(cheat, STOP.to_string()),
+        (false, revert_message("Call did not revert as expected"))
+    );
+}
+
+/// Call the cheatcode with `cheat`, then create a contract with init code
+/// `init`. Returns the address the create left on the stack and its return
+/// data.
+fn run_create(cheat: Vec<u8>, init: String) -> (H256, Vec<u8>) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let init = hex::decode(init).unwrap();
+    let code = [
+        call(CHEATCODE_ADDRESS, &cheat),
+        mstore(&init),
+        // PUSH2 len PUSH1 0 PUSH1 0 CREATE PUSH1 0 SSTORE
+        format!("61{:04x}60006000f0600055", init.len()),
+        RETURN_RETURNDATA.to_string(),
+    ]
+    .concat();
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    (exec.storage(contract, H256::zero()), out)
+}
+
+#[test]
+fn expect_revert_applies_to_create() {
+    let cheat = expect_revert_bytes(b"nope");
+    let (created, out) = run_create(cheat, revert(&revert_message("nope")));
+    assert_ne!(created, H256::zero());
+    assert!(out.is_empty());
+}
+
+#[test]
+fn expect_revert_reports_create_that_succeeded() {
+    let cheat = hex::decode("f4844814").unwrap();
+    assert_eq!(
+        run_create(cheat, STOP.to_string()),
+        (
+            H256::zero(),
+            revert_message("Call did not revert as expected")
+        )
+    );
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{Inspector, StackExecutor, StepInfo};
+use evm::{Config, ExitReason, ExitSucceed, ExternalOpcode, Opcode};
+use primitive_types::{H160, H256, U256};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+type Step = (usize, usize, Result<Opcode, ExternalOpcode>);
+
+/// Records every opcode the executor runs.
+#[derive(Default)]
+struct Recorder {
+    steps: Vec<Step>,
+    step_ends: Vec<(Step, Option<ExitReason>)>,
+    /// Whether each `step` was followed by its `step_end`.
+    paired: bool,
+}
+
+impl Inspector for Recorder {
+    fn step(&mut self, info: &StepInfo) {
+        self.paired = self.steps.len() == self.step_ends.len();
+        self.steps.push((info.depth, info.pc, info.opcode));
+    }
+
+    fn step_end(&mut self, info: &StepInfo, exit: Option<ExitReason>) {
+        self.paired &= self.steps.last() == Some(&(info.depth, info.pc, info.opcode));
+        self.step_ends
+            .push(((info.depth, info.pc, info.opcode), exit));
+    }
+}
+
+#[test]
+fn inspector_sees_every_step_of_nested_calls() {
+    let caller = H160::from_low_u64_be(0x1000);
+    let callee = H160::from_low_u64_be(0x2000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        caller,
+        MemoryAccount {
+            // STOP
+            code: hex::decode(common::call(callee, &[]) + "00").unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        callee,
+        MemoryAccount {
+            // PUSH1 1 POP STOP
+            code: hex::decode("60015000").unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = common::vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+    let recorder = Rc::new(RefCell::new(Recorder {
+        paired: true,
+        ..Default::default()
+    }));
+    exec.set_inspector(recorder.clone());
+
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::zero(),
+        caller,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+
+    let recorder = recorder.borrow();
+    assert_eq!(
+        recorder.steps,
+        vec![
+            (0, 0, Ok(Opcode::Push(1))),
+            (0, 2, Ok(Opcode::Push(1))),
+            (0, 4, Ok(Opcode::Push(2))),
+            (0, 7, Ok(Opcode::Push(1))),
+            (0, 9, Ok(Opcode::Push(1))),
+            (0, 11, Ok(Opcode::Push(20))),
+            (0, 32, Err(ExternalOpcode::Gas)),
+            (0, 33, Err(ExternalOpcode::Call)),
+            (1, 0, Ok(Opcode::Push(1))),
+            (1, 2, Ok(Opcode::Pop)),
+            (1, 3, Ok(Opcode::Stop)),
+            (0, 34, Ok(Opcode::Pop)),
+            (0, 35, Ok(Opcode::Stop)),
+        ]
+    );
+    assert!(recorder.paired);
+    assert_eq!(recorder.step_ends.len(), recorder.steps.len());
+
+    // Each frame reports its exit on the step it stopped at, and only there.
+    let exits: Vec<(Step, ExitReason)> = recorder
+        .step_ends
+        .iter()
+        .filter_map(|(step, exit)| exit.map(|exit| (*step, exit)))
+        .collect();
+    assert_eq!(
+        exits,
+        vec![
+            (
+                (1, 3, Ok(Opcode::Stop)),
+                ExitReason::Succeed(ExitSucceed::Stopped)
+            ),
+            (
+                (0, 35, Ok(Opcode::Stop)),
+                ExitReason::Succeed(ExitSucceed::Stopped)
+            ),
+        ]
+    );
+    assert_eq!(
+        recorder.step_ends.last().unwrap().1,
+        Some(ExitReason::Succeed(ExitSucceed::Stopped))
+    );
+}
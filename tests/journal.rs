@@ -0,0 +1,111 @@
+mod common;
+
+use common::vicinity;
+use evm::backend::{Log, MemoryAccount, MemoryBackend};
+use evm::executor::StackExecutor;
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 1 PUSH2 0x2000 GAS CALL
+const CALL_CHILD_WITH_VALUE: &str = "600060006000600060016120005af1";
+// PUSH1 1 PUSH1 0 SSTORE
+const STORE_ONE: &str = "6001600055";
+// PUSH1 0 PUSH1 0 LOG0
+const LOG0: &str = "60006000a0";
+// PUSH1 0 PUSH1 0 REVERT
+const REVERT: &str = "60006000fd";
+// STOP
+const STOP: &str = "00";
+
+/// Call a parent contract with code `parent` that calls a child with code
+/// `child`, returning the child's slot 0 and balance after the transaction.
+fn run(parent: &str, child: &str) -> (bool, H256, U256) {
+    let (succeeded, slot, balance, _) = run_with_logs(parent, child);
+    (succeeded, slot, balance)
+}
+
+/// Like `run`, also returning the logs the transaction kept.
+fn run_with_logs(parent: &str, child: &str) -> (bool, H256, U256, Vec<Log>) {
+    let parent_address = H160::from_low_u64_be(0x1000);
+    let child_address = H160::from_low_u64_be(0x2000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        parent_address,
+        MemoryAccount {
+            code: hex::decode(parent).unwrap(),
+            balance: U256::from(10),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        child_address,
+        MemoryAccount {
+            code: hex::decode(child).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        parent_address,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    (
+        reason.is_succeed(),
+        exec.storage(child_address, H256::zero()),
+        exec.balance(child_address),
+        exec.logs.clone(),
+    )
+}
+
+#[test]
+fn succeeded_call_keeps_changes() {
+    let parent = format!("{}{}", CALL_CHILD_WITH_VALUE, STOP);
+    let child = format!("{}{}", STORE_ONE, STOP);
+    assert_eq!(
+        run(&parent, &child),
+        (true, H256::from_low_u64_be(1), U256::one())
+    );
+}
+
+#[test]
+fn reverted_call_discards_changes() {
+    let parent = format!("{}{}", CALL_CHILD_WITH_VALUE, STOP);
+    let child = format!("{}{}", STORE_ONE, REVERT);
+    assert_eq!(run(&parent, &child), (true, H256::zero(), U256::zero()));
+}
+
+#[test]
+fn reverted_parent_discards_succeeded_child() {
+    let parent = format!("{}{}", CALL_CHILD_WITH_VALUE, REVERT);
+    let child = format!("{}{}", STORE_ONE, STOP);
+    assert_eq!(run(&parent, &child), (false, H256::zero(), U256::zero()));
+}
+
+#[test]
+fn reverted_call_discards_storage_and_logs() {
+    let parent = format!("{}{}", CALL_CHILD_WITH_VALUE, STOP);
+    let child = format!("{}{}{}", STORE_ONE, LOG0, REVERT);
+    assert_eq!(
+        run_with_logs(&parent, &child),
+        (true, H256::zero(), U256::zero(), Vec::new())
+    );
+}
+
+#[test]
+fn succeeded_call_keeps_logs() {
+    let parent = format!("{}{}", CALL_CHILD_WITH_VALUE, STOP);
+    let child = format!("{}{}{}", STORE_ONE, LOG0, STOP);
+    let (_, slot, _, logs) = run_with_logs(&parent, &child);
+    assert_eq!(slot, H256::from_low_u64_be(1));
+    assert_eq!(logs.len(), 1);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{call, vicinity};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{abi_encode_bytes, CallTrace, StackExecutor, CHEATCODE_ADDRESS};
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 1 PUSH1 0 SSTORE STOP
+const STORE_ONE: &str = "600160005500";
+
+fn label(address: H160, name: &str) -> Vec<u8> {
+    let mut input = hex::decode("c657c718").unwrap();
+    input.extend_from_slice(H256::from(address).as_bytes());
+    input.extend_from_slice(H256::from_low_u64_be(64).as_bytes());
+    input.extend_from_slice(&abi_encode_bytes(name.as_bytes())[32..]);
+    input
+}
+
+fn contract() -> H160 {
+    H160::from_low_u64_be(0x1000)
+}
+
+fn target() -> H160 {
+    H160::from_low_u64_be(0x2000)
+}
+
+/// Run `code` next to a target contract and return the transaction's trace.
+fn run(code: String) -> Vec<CallTrace> {
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract(),
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        target(),
+        MemoryAccount {
+            code: hex::decode(STORE_ONE).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, _, traces) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract(),
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    assert_eq!(exec.labels.get(&target()).map(String::as_str), Some("WETH"));
+    traces
+}
+
+fn target_label(traces: &[CallTrace]) -> Option<String> {
+    traces[0]
+        .inner
+        .iter()
+        .find(|trace| trace.addr == target())
+        .unwrap()
+        .label
+        .clone()
+}
+
+#[test]
+fn label_names_traced_calls() {
+    let code = call(CHEATCODE_ADDRESS, &label(target(), "WETH")) + &call(target(), &[]);
+
+    let traces = run(code);
+    assert_eq!(target_label(&traces), Some("WETH".to_string()));
+    assert_eq!(traces[0].label, None);
+}
+
+#[test]
+fn label_applies_to_earlier_calls() {
+    let code = call(target(), &[]) + &call(CHEATCODE_ADDRESS, &label(target(), "WETH"));
+
+    assert_eq!(target_label(&run(code)), Some("WETH".to_string()));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::vicinity;
+use evm::backend::{ForkMemoryBackendOwned, MemoryAccount};
+use evm::executor::StackExecutor;
+use evm::provider::memoryprovider::MemoryProvider;
+use evm::provider::StateProvider;
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 0 SLOAD PUSH1 0 MSTORE PUSH1 0x20 PUSH1 0 RETURN
+const RETURN_SLOT_0: &str = "60005460005260206000f3";
+
+#[test]
+fn fork_reads_from_provider() {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut storage = BTreeMap::new();
+    storage.insert(H256::zero(), H256::from_low_u64_be(42));
+    let mut accounts = BTreeMap::new();
+    accounts.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(RETURN_SLOT_0).unwrap(),
+            storage,
+            ..Default::default()
+        },
+    );
+
+    let config = Config::istanbul();
+    let backend = ForkMemoryBackendOwned::new_with_provider(
+        vicinity(),
+        BTreeMap::new(),
+        MemoryProvider::new(accounts),
+    );
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x2000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    assert_eq!(U256::from_big_endian(&out), U256::from(42));
+}
+
+#[test]
+fn blocks_by_number() {
+    let tx = web3::types::Transaction {
+        hash: web3::types::H256::repeat_byte(1),
+        ..Default::default()
+    };
+    let provider = MemoryProvider {
+        blocks: vec![web3::types::Block {
+            number: Some(7.into()),
+            transactions: vec![tx.clone()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    assert_eq!(provider.get_block_number(), Ok(U256::from(7)));
+    assert_eq!(
+        provider
+            .get_block_by_number_txs(U256::from(7))
+            .unwrap()
+            .transactions,
+        vec![tx.clone()]
+    );
+    assert_eq!(
+        provider
+            .get_block_by_number(U256::from(7))
+            .unwrap()
+            .transactions,
+        vec![tx.hash]
+    );
+}
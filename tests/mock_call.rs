@@ -0,0 +1,125 @@
+mod common;
+
+use common::{call, try_call, vicinity, RETURN_RETURNDATA};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{abi_encode_bytes, CallTrace, StackExecutor, CHEATCODE_ADDRESS};
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 0 PUSH1 0 REVERT
+const REVERT: &str = "60006000fd";
+
+fn mock_call(address: H160, calldata: &[u8], ret: &[u8]) -> Vec<u8> {
+    let calldata = abi_encode_bytes(calldata)[32..].to_vec();
+    let mut input = hex::decode("b96213e4").unwrap();
+    input.extend_from_slice(H256::from(address).as_bytes());
+    input.extend_from_slice(H256::from_low_u64_be(96).as_bytes());
+    input.extend_from_slice(H256::from_low_u64_be(96 + calldata.len() as u64).as_bytes());
+    input.extend_from_slice(&calldata);
+    input.extend_from_slice(&abi_encode_bytes(ret)[32..]);
+    input
+}
+
+/// Run the cheatcode calls in `cheats`, then call an oracle that always
+/// reverts with `calldata`. Returns whether the oracle call succeeded, its
+/// return data and the oracle's trace.
+fn run(cheats: Vec<Vec<u8>>, calldata: &[u8]) -> (bool, Vec<u8>, CallTrace) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let oracle = H160::from_low_u64_be(0x2000);
+    let mut code = String::new();
+    for cheat in cheats.iter() {
+        code += &call(CHEATCODE_ADDRESS, cheat);
+    }
+    code += &try_call(oracle, calldata);
+    // PUSH1 0 SSTORE
+    code += "600055";
+    code += RETURN_RETURNDATA;
+
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code).unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        oracle,
+        MemoryAccount {
+            code: hex::decode(REVERT).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, traces) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    let trace = traces[0]
+        .inner
+        .iter()
+        .find(|trace| trace.addr == oracle)
+        .unwrap()
+        .clone();
+    (
+        exec.storage(contract, H256::zero()) == H256::from_low_u64_be(1),
+        out,
+        trace,
+    )
+}
+
+fn oracle() -> H160 {
+    H160::from_low_u64_be(0x2000)
+}
+
+#[test]
+fn mocked_call_returns_data() {
+    let price = H256::from_low_u64_be(2000).as_bytes().to_vec();
+    let cheats = vec![mock_call(
+        oracle(),
+        &hex::decode("50d25bcd").unwrap(),
+        &price,
+    )];
+    let calldata = hex::decode(format!("50d25bcd{:064x}", 1)).unwrap();
+
+    let (succeed, out, trace) = run(cheats, &calldata);
+    assert_eq!((succeed, out), (true, price));
+    assert!(trace.mocked);
+}
+
+#[test]
+fn longest_mocked_calldata_wins() {
+    let calldata = hex::decode(format!("50d25bcd{:064x}", 1)).unwrap();
+    let cheats = vec![
+        mock_call(oracle(), &hex::decode("50d25bcd").unwrap(), &[1]),
+        mock_call(oracle(), &calldata, &[2]),
+    ];
+    assert_eq!(run(cheats.clone(), &calldata).1, vec![2]);
+
+    let other = hex::decode(format!("50d25bcd{:064x}", 2)).unwrap();
+    assert_eq!(run(cheats, &other).1, vec![1]);
+}
+
+#[test]
+fn cleared_mocks_execute_code() {
+    let calldata = hex::decode("50d25bcd").unwrap();
+    let cheats = vec![
+        mock_call(oracle(), &calldata, &[1]),
+        hex::decode("3fdf4e15").unwrap(),
+    ];
+
+    let (succeed, out, trace) = run(cheats, &calldata);
+    assert_eq!((succeed, out), (false, Vec::new()));
+    assert!(!trace.mocked);
+}
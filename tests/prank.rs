@@ -0,0 +1,120 @@
+mod common;
+
+use common::{call, call_cheatcode};
+use evm::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::StackExecutor;
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// CALLER PUSH1 0 SSTORE ORIGIN PUSH1 1 SSTORE STOP
+const STORE_CALLER_AND_ORIGIN: &str = "336000553260015500";
+
+fn vicinity() -> MemoryVicinity {
+    MemoryVicinity {
+        origin: H160::from_low_u64_be(0x9000),
+        ..common::vicinity()
+    }
+}
+
+fn word(address: H160) -> String {
+    format!("{:0>64}", hex::encode(address.as_bytes()))
+}
+
+fn target(n: u64) -> H160 {
+    H160::from_low_u64_be(0x2000 + n)
+}
+
+/// Run `code` from a contract and return what each of the targets recorded as
+/// its caller and origin.
+fn run(code: String, targets: u64) -> Vec<(H160, H160)> {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code + "00").unwrap(),
+            ..Default::default()
+        },
+    );
+    for n in 0..targets {
+        state.insert(
+            target(n),
+            MemoryAccount {
+                code: hex::decode(STORE_CALLER_AND_ORIGIN).unwrap(),
+                ..Default::default()
+            },
+        );
+    }
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x9000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    (0..targets)
+        .map(|n| {
+            (
+                H160::from(exec.storage(target(n), H256::zero())),
+                H160::from(exec.storage(target(n), H256::from_low_u64_be(1))),
+            )
+        })
+        .collect()
+}
+
+fn call_target(n: u64) -> String {
+    call(target(n), &[])
+}
+
+#[test]
+fn prank_applies_to_next_call_only() {
+    let sender = H160::from_low_u64_be(0xbeef);
+    let code =
+        call_cheatcode(&format!("ca669fa7{}", word(sender))) + &call_target(0) + &call_target(1);
+
+    let contract = H160::from_low_u64_be(0x1000);
+    let origin = H160::from_low_u64_be(0x9000);
+    assert_eq!(run(code, 2), vec![(sender, origin), (contract, origin)]);
+}
+
+#[test]
+fn start_prank_persists_until_stop() {
+    let sender = H160::from_low_u64_be(0xbeef);
+    let code = call_cheatcode(&format!("06447d56{}", word(sender)))
+        + &call_target(0)
+        + &call_target(1)
+        + &call_cheatcode("90c5013b")
+        + &call_target(2);
+
+    let contract = H160::from_low_u64_be(0x1000);
+    let origin = H160::from_low_u64_be(0x9000);
+    assert_eq!(
+        run(code, 3),
+        vec![(sender, origin), (sender, origin), (contract, origin)]
+    );
+}
+
+#[test]
+fn prank_can_set_origin() {
+    let sender = H160::from_low_u64_be(0xbeef);
+    let pranked_origin = H160::from_low_u64_be(0xcafe);
+    let code = call_cheatcode(&format!("47e50cce{}{}", word(sender), word(pranked_origin)))
+        + &call_target(0)
+        + &call_target(1);
+
+    let contract = H160::from_low_u64_be(0x1000);
+    let origin = H160::from_low_u64_be(0x9000);
+    assert_eq!(
+        run(code, 2),
+        vec![(sender, pranked_origin), (contract, origin)]
+    );
+}
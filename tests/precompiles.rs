@@ -0,0 +1,125 @@
+use evm::precompiles;
+use evm::{Config, ExitError};
+use primitive_types::H160;
+
+macro_rules! precompile_test {
+    ( $name:ident, $address:expr, $input:expr, $ret:expr, $cost:expr ) => {
+        #[test]
+        fn $name() {
+            let input = hex::decode($input).unwrap();
+            let config = Config::istanbul();
+
+            let (_, out, cost) = precompiles::standard(
+                H160::from_low_u64_be($address),
+                &input,
+                Some(100_000_000),
+                &config,
+            )
+            .expect("address is a precompile")
+            .expect("precompile succeeds");
+            assert_eq!(hex::encode(out), $ret);
+            assert_eq!(cost, $cost);
+        }
+    };
+}
+
+precompile_test!(
+    ecrecover,
+    1,
+    "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e000000000000000000000000000000000000000000000000000000000000001b38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02",
+    "000000000000000000000000ceaccac640adf55b2028469bd36ba501f28b699d",
+    3000
+);
+
+precompile_test!(
+    sha256_empty,
+    2,
+    "",
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    60
+);
+
+precompile_test!(
+    ripemd160_empty,
+    3,
+    "",
+    "0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31",
+    600
+);
+
+precompile_test!(identity, 4, "deadbeef", "deadbeef", 18);
+
+precompile_test!(
+    modexp,
+    5,
+    "000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000001030507",
+    "05",
+    0
+);
+
+precompile_test!(
+    bn128_add,
+    6,
+    "0000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002",
+    "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+    150
+);
+
+precompile_test!(
+    bn128_mul,
+    7,
+    "000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000002",
+    "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+    6000
+);
+
+precompile_test!(
+    bn128_pairing_empty,
+    8,
+    "",
+    "0000000000000000000000000000000000000000000000000000000000000001",
+    45000
+);
+
+precompile_test!(
+    blake2f,
+    9,
+    "0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b61626300000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000300000000000000000000000000000001",
+    "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+    12
+);
+
+#[test]
+fn frontier_has_no_bn128() {
+    let config = Config::frontier();
+    assert!(precompiles::standard(H160::from_low_u64_be(6), &[], None, &config).is_none());
+}
+
+#[test]
+fn berlin_modexp_minimum_cost() {
+    let config = Config::berlin();
+    let input = hex::decode("000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000001030507").unwrap();
+
+    let (_, out, cost) = precompiles::standard(H160::from_low_u64_be(5), &input, None, &config)
+        .expect("address is a precompile")
+        .expect("precompile succeeds");
+    assert_eq!(hex::encode(out), "05");
+    assert_eq!(cost, 200);
+}
+
+#[test]
+fn modexp_with_huge_exponent_is_out_of_gas() {
+    let config = Config::berlin();
+    // base_len 0, exp_len 2^256 - 1, mod_len 1 and a nonzero exponent head.
+    let input = hex::decode(format!(
+        "{}{}{:0>64}ff",
+        "00".repeat(32),
+        "ff".repeat(32),
+        "01"
+    ))
+    .unwrap();
+
+    let result = precompiles::standard(H160::from_low_u64_be(5), &input, None, &config)
+        .expect("address is a precompile");
+    assert_eq!(result.err(), Some(ExitError::OutOfGas));
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::vicinity;
+use evm::backend::{Backend, ForkMemoryBackendOwned};
+use evm::executor::StackExecutor;
+use evm::provider::cache::RpcCache;
+use evm::provider::ProviderError;
+use evm::{Config, ExitFatal, ExitReason};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+fn call(backend: &ForkMemoryBackendOwned) -> ExitReason {
+    call_address(backend, H160::from_low_u64_be(0x1000))
+}
+
+fn call_address(backend: &ForkMemoryBackendOwned, address: H160) -> ExitReason {
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(backend, 1_000_000, &config);
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x2000),
+        address,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    reason
+}
+
+#[test]
+fn unreachable_provider_is_fatal() {
+    // Nothing listens on this port.
+    let backend = ForkMemoryBackendOwned::new(
+        vicinity(),
+        BTreeMap::new(),
+        "http://127.0.0.1:1".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        call(&backend),
+        ExitReason::Fatal(ExitFatal::ProviderError("eth_getProof"))
+    );
+    assert!(backend.take_error().is_some());
+}
+
+#[test]
+fn unreachable_provider_is_fatal_for_precompiles() {
+    // Nothing listens on this port.
+    let backend = ForkMemoryBackendOwned::new(
+        vicinity(),
+        BTreeMap::new(),
+        "http://127.0.0.1:1".to_string(),
+    )
+    .unwrap();
+    // The identity precompile.
+    assert_eq!(
+        call_address(&backend, H160::from_low_u64_be(4)),
+        ExitReason::Fatal(ExitFatal::ProviderError("eth_getProof"))
+    );
+}
+
+#[test]
+fn invalid_url_is_an_error() {
+    match ForkMemoryBackendOwned::new(vicinity(), BTreeMap::new(), "not a url".to_string()) {
+        Err(ProviderError::InvalidUrl { url, .. }) => assert_eq!(url, "not a url"),
+        _ => panic!("expected an invalid url error"),
+    }
+}
+
+#[test]
+fn offline_cache_miss_is_fatal() {
+    let dir = std::env::temp_dir().join("cevm-provider-error-offline");
+    let _ = std::fs::remove_dir_all(&dir);
+    let backend = ForkMemoryBackendOwned::new_with_cache(
+        vicinity(),
+        BTreeMap::new(),
+        "http://127.0.0.1:1".to_string(),
+        RpcCache::open(&dir, U256::one(), true).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        call(&backend),
+        ExitReason::Fatal(ExitFatal::ProviderError("eth_getBalance"))
+    );
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{call, call_cheatcode, vicinity, RETURN_RETURNDATA};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::StackExecutor;
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 3 SLOAD POP PUSH1 1 PUSH1 5 SSTORE STOP
+const TOKEN: &str = "60035450600160055500";
+
+fn token() -> H160 {
+    H160::from_low_u64_be(0x2000)
+}
+
+fn accesses(address: H160) -> String {
+    call_cheatcode(&format!("65bc9481{:0>64}", hex::encode(address.as_bytes())))
+}
+
+/// Run `code` next to a token reading slot 3 and writing slot 5, returning the
+/// return data of the last call.
+fn run(code: String) -> Vec<u8> {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code + RETURN_RETURNDATA).unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        token(),
+        MemoryAccount {
+            code: hex::decode(TOKEN).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    out
+}
+
+/// ABI encoding of `(bytes32[] reads, bytes32[] writes)`.
+fn encode(reads: &[u64], writes: &[u64]) -> Vec<u8> {
+    let mut out = H256::from_low_u64_be(64).as_bytes().to_vec();
+    out.extend_from_slice(H256::from_low_u64_be(96 + 32 * reads.len() as u64).as_bytes());
+    for slots in [reads, writes].iter() {
+        out.extend_from_slice(H256::from_low_u64_be(slots.len() as u64).as_bytes());
+        for slot in slots.iter() {
+            out.extend_from_slice(H256::from_low_u64_be(*slot).as_bytes());
+        }
+    }
+    out
+}
+
+#[test]
+fn accesses_lists_recorded_slots() {
+    let code = call_cheatcode("266cf109") + &call(token(), &[]) + &accesses(token());
+
+    assert_eq!(run(code), encode(&[3, 5], &[5]));
+}
+
+#[test]
+fn accesses_are_empty_without_record() {
+    let code = call(token(), &[]) + &accesses(token());
+    assert_eq!(run(code), encode(&[], &[]));
+
+    let code =
+        call_cheatcode("266cf109") + &call(token(), &[]) + &accesses(H160::from_low_u64_be(0x1000));
+    assert_eq!(run(code), encode(&[], &[]));
+}
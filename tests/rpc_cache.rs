@@ -0,0 +1,137 @@
+mod common;
+
+use evm::backend::{Backend, ForkMemoryBackendOwned, MemoryVicinity};
+use evm::provider::cache::RpcCache;
+use evm::provider::ProviderError;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::thread;
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cevm-rpc-cache-{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn vicinity(block_number: U256) -> MemoryVicinity {
+    MemoryVicinity {
+        block_number,
+        ..common::vicinity()
+    }
+}
+
+#[test]
+fn persists_across_runs() {
+    let dir = cache_dir("persist");
+    let address = H160::from_low_u64_be(0x1000);
+    let block = U256::from(100);
+
+    let cache = RpcCache::open(&dir, U256::one(), false).unwrap();
+    assert_eq!(
+        cache.balance(block, address, || Ok(U256::from(7))),
+        Ok(U256::from(7))
+    );
+    assert_eq!(
+        cache.balance(block, address, || unreachable!()),
+        Ok(U256::from(7))
+    );
+    cache
+        .storage(block, address, H256::zero(), || Ok(H256::repeat_byte(1)))
+        .unwrap();
+    cache.flush().unwrap();
+
+    let cache = RpcCache::open(&dir, U256::one(), true).unwrap();
+    assert_eq!(
+        cache.balance(block, address, || unreachable!()),
+        Ok(U256::from(7))
+    );
+    assert_eq!(
+        cache.storage(block, address, H256::zero(), || unreachable!()),
+        Ok(H256::repeat_byte(1))
+    );
+}
+
+#[test]
+fn chains_are_separate() {
+    let dir = cache_dir("chain");
+    RpcCache::open(&dir, U256::one(), false)
+        .unwrap()
+        .nonce(U256::zero(), H160::zero(), || Ok(U256::zero()))
+        .unwrap();
+    let other = RpcCache::open(&dir, U256::from(5), false).unwrap();
+    assert_eq!(
+        other.nonce(U256::zero(), H160::zero(), || Ok(U256::from(9))),
+        Ok(U256::from(9))
+    );
+}
+
+#[test]
+fn offline_miss_fails() {
+    let cache = RpcCache::open(cache_dir("offline"), U256::one(), true).unwrap();
+    assert_eq!(
+        cache.code(U256::zero(), H160::zero(), || unreachable!()),
+        Err(ProviderError::CacheMiss {
+            method: "eth_getCode"
+        })
+    );
+}
+
+#[test]
+fn backend_reads_through_cache() {
+    let dir = cache_dir("backend");
+    let address = H160::from_low_u64_be(0x1000);
+    let block = U256::from(100);
+    {
+        let cache = RpcCache::open(&dir, U256::one(), false).unwrap();
+        cache.balance(block, address, || Ok(U256::from(7))).unwrap();
+        cache.nonce(block, address, || Ok(U256::from(3))).unwrap();
+        cache.code(block, address, || Ok(vec![0x00])).unwrap();
+    }
+
+    // Nothing listens on this port; every read must be served from the cache.
+    let backend = ForkMemoryBackendOwned::new_with_cache(
+        vicinity(block),
+        BTreeMap::new(),
+        "http://127.0.0.1:1".to_string(),
+        RpcCache::open(&dir, U256::one(), true).unwrap(),
+    )
+    .unwrap();
+    assert!(backend.exists(address));
+    assert_eq!(backend.basic(address).nonce, U256::from(3));
+    assert_eq!(backend.code(address), vec![0x00]);
+}
+
+#[test]
+fn concurrent_flushes_keep_every_entry() {
+    let dir = cache_dir("concurrent");
+    let block = U256::from(100);
+    let cache = RpcCache::open(&dir, U256::one(), false).unwrap();
+    let writers: Vec<_> = (0..8)
+        .map(|i| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                let address = H160::from_low_u64_be(i);
+                cache.balance(block, address, || Ok(U256::from(i))).unwrap();
+                cache.flush().unwrap();
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let cache = RpcCache::open(&dir, U256::one(), true).unwrap();
+    for i in 0..8 {
+        assert_eq!(
+            cache.balance(block, H160::from_low_u64_be(i), || unreachable!()),
+            Ok(U256::from(i))
+        );
+    }
+    // Every writer's temporary file was renamed into place.
+    let files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, vec!["1.json"]);
+}
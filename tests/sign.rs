@@ -0,0 +1,76 @@
+mod common;
+
+use common::{call, vicinity, RETURN_RETURNDATA};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::{revert_message, StackExecutor, CHEATCODE_ADDRESS};
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// Address of private key 1
+const KEY_ONE_ADDRESS: &str = "7e5f4552091a69125d5dfcb7b8c2659029395bdf";
+
+/// Run `code` and return the return data of its last call.
+fn run(code: String) -> Vec<u8> {
+    let contract = H160::from_low_u64_be(0x1000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code + RETURN_RETURNDATA).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let config = Config::istanbul();
+    let mut exec = StackExecutor::new(&backend, 1_000_000, &config);
+
+    let (reason, out, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    out
+}
+
+#[test]
+fn addr_derives_key_address() {
+    let calldata = hex::decode(format!("ffa18649{:064x}", 1)).unwrap();
+
+    let out = run(call(CHEATCODE_ADDRESS, &calldata));
+    assert_eq!(
+        out,
+        hex::decode(format!("{:0>64}", KEY_ONE_ADDRESS)).unwrap()
+    );
+}
+
+#[test]
+fn sign_is_recovered_by_ecrecover() {
+    let digest = H256::repeat_byte(0xab);
+    let calldata = hex::decode(format!("e341eaa4{:064x}{}", 1, hex::encode(digest))).unwrap();
+    let signature = run(call(CHEATCODE_ADDRESS, &calldata));
+    assert_eq!(signature.len(), 96);
+
+    // ecrecover(digest, v, r, s)
+    let mut input = digest.as_bytes().to_vec();
+    input.extend_from_slice(&signature);
+    let out = run(call(H160::from_low_u64_be(1), &input));
+    assert_eq!(
+        out,
+        hex::decode(format!("{:0>64}", KEY_ONE_ADDRESS)).unwrap()
+    );
+}
+
+#[test]
+fn invalid_private_key_reverts() {
+    let calldata = hex::decode(format!("ffa18649{:064x}", 0)).unwrap();
+
+    let out = run(call(CHEATCODE_ADDRESS, &calldata));
+    assert_eq!(out, revert_message("invalid private key"));
+}
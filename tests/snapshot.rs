@@ -0,0 +1,158 @@
+mod common;
+
+use common::{call, call_cheatcode, vicinity};
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::StackExecutor;
+use evm::{Config, Handler};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// PUSH1 1 PUSH1 0 SSTORE STOP
+const STORE_ONE: &str = "600160005500";
+// PUSH1 32 PUSH1 0 PUSH1 0 RETURNDATACOPY PUSH1 0 MLOAD
+const LOAD_RETURN_WORD: &str = "6020600060003e600051";
+
+/// Code that stores the word on top of the stack in `slot`.
+fn store(slot: u8) -> String {
+    // PUSH1 slot SSTORE
+    format!("60{:02x}55", slot)
+}
+
+fn warp(timestamp: u64) -> String {
+    call_cheatcode(&format!("e5d6bf02{:064x}", timestamp))
+}
+
+fn revert_to(id: u64) -> String {
+    call_cheatcode(&format!("44d7f0a4{:064x}", id))
+}
+
+/// Run `code` next to a contract that stores 1 when called. Returns slots 0 to
+/// 3 of the contract and slot 0 of the other contract.
+fn run(code: String) -> (Vec<H256>, H256) {
+    run_with(&Config::istanbul(), code)
+}
+
+fn run_with(config: &Config, code: String) -> (Vec<H256>, H256) {
+    let contract = H160::from_low_u64_be(0x1000);
+    let target = H160::from_low_u64_be(0x2000);
+    let mut state = BTreeMap::new();
+    state.insert(
+        contract,
+        MemoryAccount {
+            code: hex::decode(code + "00").unwrap(),
+            ..Default::default()
+        },
+    );
+    state.insert(
+        target,
+        MemoryAccount {
+            code: hex::decode(STORE_ONE).unwrap(),
+            ..Default::default()
+        },
+    );
+    let vicinity = vicinity();
+    let backend = MemoryBackend::new(&vicinity, state);
+    let mut exec = StackExecutor::new(&backend, 1_000_000, config);
+
+    let (reason, _, _) = exec.transact_call(
+        H256::zero(),
+        H160::from_low_u64_be(0x3000),
+        contract,
+        U256::zero(),
+        Vec::new(),
+        1_000_000,
+        Vec::new(),
+    );
+    assert!(reason.is_succeed());
+    (
+        (0..4)
+            .map(|slot| exec.storage(contract, H256::from_low_u64_be(slot)))
+            .collect(),
+        exec.storage(target, H256::zero()),
+    )
+}
+
+#[test]
+fn revert_to_restores_state() {
+    let code = [
+        warp(100),
+        call_cheatcode("9711715a"),
+        // PUSH1 5
+        "6005".to_string(),
+        store(1),
+        call(H160::from_low_u64_be(0x2000), &[]),
+        warp(200),
+        revert_to(0),
+        LOAD_RETURN_WORD.to_string(),
+        store(2),
+        // TIMESTAMP
+        "42".to_string(),
+        store(3),
+    ]
+    .concat();
+
+    let (slots, target_slot) = run(code);
+    assert_eq!(
+        slots,
+        vec![
+            H256::zero(),
+            H256::zero(),
+            H256::from_low_u64_be(1),
+            H256::from_low_u64_be(100),
+        ]
+    );
+    assert_eq!(target_slot, H256::zero());
+}
+
+#[test]
+fn snapshot_can_be_restored_twice() {
+    let code = [
+        call_cheatcode("9711715a"),
+        // PUSH1 5
+        "6005".to_string(),
+        store(1),
+        revert_to(0),
+        call(H160::from_low_u64_be(0x2000), &[]),
+        revert_to(0),
+        LOAD_RETURN_WORD.to_string(),
+        store(2),
+        revert_to(1),
+        LOAD_RETURN_WORD.to_string(),
+        store(3),
+    ]
+    .concat();
+
+    let (slots, target_slot) = run(code);
+    assert_eq!(
+        slots,
+        vec![
+            H256::zero(),
+            H256::zero(),
+            H256::from_low_u64_be(1),
+            H256::zero(),
+        ]
+    );
+    assert_eq!(target_slot, H256::zero());
+}
+
+#[test]
+fn revert_to_restores_access_sets_and_transient_storage() {
+    let code = [
+        call_cheatcode("9711715a"),
+        // PUSH1 7 PUSH1 0 TSTORE PUSH1 9 SLOAD POP
+        "600760005d60095450".to_string(),
+        revert_to(0),
+        // PUSH1 0 TLOAD
+        "60005c".to_string(),
+        store(1),
+        // GAS PUSH1 9 SLOAD POP GAS SWAP1 SUB
+        "5a600954505a9003".to_string(),
+        store(2),
+    ]
+    .concat();
+
+    let (slots, _) = run_with(&Config::cancun(), code);
+    assert_eq!(slots[1], H256::zero());
+    // PUSH1, a cold SLOAD, POP and GAS.
+    assert_eq!(slots[2], H256::from_low_u64_be(3 + 2100 + 2 + 2));
+}